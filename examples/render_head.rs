@@ -0,0 +1,20 @@
+// The head render using nothing but the library's public API:
+// cargo run --example render_head
+
+extern crate renderer;
+
+use std::path::Path;
+use renderer::{Renderer,Shading,Model,Texture,TgaImage,Mat4};
+
+fn main() {
+    let mut model = Model::new_from_file(&Path::new("data/model.obj")).unwrap();
+    model.normalize_to_unit_cube();
+    let mut diffuse = Texture::new(TgaImage::new_from_file(&Path::new("data/diffuse.tga")).unwrap());
+    diffuse.generate_mipmaps();
+
+    let mut renderer = Renderer::new(800, 800);
+    renderer.set_diffuse(diffuse);
+    renderer.set_shading(Shading::Gouraud);
+    renderer.draw_model(&model, &Mat4::identity());
+    renderer.to_image().save(Path::new("output.tga")).unwrap();
+}
@@ -0,0 +1,180 @@
+extern crate renderer;
+
+use std::env;
+use std::path::Path;
+use std::process;
+use std::time::Instant;
+
+use renderer::{Renderer,Shading,Model,Texture,TgaImage,Kernel,RgbaColor,Mat4};
+use renderer::cli::{self,Command,Mode};
+use renderer::model::ModelError;
+
+// Depth jump that gets outlined in toon mode, a few hundredths of the model's depth
+const TOON_OUTLINE: f32 = 0.03;
+
+// Box blur width in output pixels of the bloom halo, blurred three times
+// over for a gaussian look
+const BLOOM_WIDTH: usize = 9;
+
+fn load_head() -> (Model, Texture) {
+    let mut model = Model::new_from_file(&Path::new("data/model.obj")).unwrap();
+    model.normalize_to_unit_cube();
+    let mut diffuse = Texture::new(TgaImage::new_from_file(&Path::new("data/diffuse.tga")).unwrap());
+    diffuse.generate_mipmaps();
+    (model, diffuse)
+}
+
+// Times 2048x2048 renders of the head with 1 to 4 threads and checks they
+// all match the single threaded one
+fn benchmark() {
+    let mut reference: Option<TgaImage> = None;
+    let mut single = 0.0;
+
+    for threads in 1..5 {
+        let (model, diffuse) = load_head();
+        let mut renderer = Renderer::new(2048, 2048);
+        renderer.set_diffuse(diffuse);
+        renderer.set_shading(Shading::Gouraud);
+        renderer.set_threads(threads);
+
+        let start = Instant::now();
+        renderer.draw_model(&model, &Mat4::identity());
+        let elapsed = start.elapsed().as_secs_f64();
+        if threads == 1 {
+            single = elapsed;
+        }
+
+        let image = renderer.to_image();
+        let same = match reference {
+            Some(ref r) => (0..image.height).all(|y| (0..image.width).all(|x| r.get_pixel(x, y) == image.get_pixel(x, y))),
+            None => true
+        };
+        println!("{} thread(s): {:.3}s, {:.2}x{}", threads, elapsed, single / elapsed, if same { "" } else { ", OUTPUT DIFFERS" });
+        if reference.is_none() {
+            reference = Some(image);
+        }
+    }
+}
+
+#[cfg(feature = "preview")]
+fn preview(renderer: &mut Renderer, model: &Model) {
+    if let Err(e) = renderer::preview::run(renderer, model, "renderer") {
+        fail(format!("cannot open the preview window: {}", e), 1);
+    }
+}
+
+#[cfg(not(feature = "preview"))]
+fn preview(_renderer: &mut Renderer, _model: &Model) {
+    fail("--preview needs the binary built with --features preview".to_string(), 2);
+}
+
+// Exits with 1 when a file can't be read or written, 2 on bad arguments
+fn fail(message: String, code: i32) -> ! {
+    eprintln!("renderer: {}", message);
+    process::exit(code);
+}
+
+fn load_model(path: &Path) -> Result<Model, ModelError> {
+    match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()) {
+        Some(ref ext) if ext == "ply" => Model::new_from_ply(path),
+        Some(ref ext) if ext == "gltf" => Model::new_from_gltf(path),
+        _ => Model::new_from_file(path)
+    }
+}
+
+fn main() {
+    let config = match cli::parse_args(env::args().skip(1)) {
+        Ok(Command::Render(config)) => config,
+        Ok(Command::Benchmark) => {
+            benchmark();
+            return;
+        },
+        Ok(Command::Help) => {
+            print!("{}", cli::USAGE);
+            return;
+        },
+        Err(e) => fail(format!("{}\nTry --help for the list of options", e), 2)
+    };
+
+    let mut model = match load_model(&config.model) {
+        Ok(model) => model,
+        Err(e) => fail(format!("cannot load model {}: {}", config.model.display(), e), 1)
+    };
+    model.normalize_to_unit_cube();
+
+    let mut renderer = match Renderer::try_new(config.width, config.height, config.ssaa) {
+        Ok(renderer) => renderer,
+        Err(e) => fail(format!("cannot render {}x{} pixels: {}", config.width, config.height, e), 1)
+    };
+    if let Some(ref path) = config.diffuse {
+        let mut diffuse = match TgaImage::new_from_file(path) {
+            Ok(image) => Texture::new(image),
+            Err(e) => fail(format!("cannot load texture {}: {}", path.display(), e), 1)
+        };
+        diffuse.generate_mipmaps();
+        renderer.set_diffuse(diffuse);
+    }
+    if let Some(ref path) = config.glow {
+        match TgaImage::new_from_file(path) {
+            Ok(image) => renderer.set_glow_map(image),
+            Err(e) => fail(format!("cannot load glow map {}: {}", path.display(), e), 1)
+        }
+    }
+    if config.bloom {
+        // Odd as kernels need, widened for the supersampled framebuffer
+        let width = (BLOOM_WIDTH * config.ssaa as usize) | 1;
+        if let Err(e) = renderer.set_bloom(&vec![Kernel::box_blur(width); 3]) {
+            fail(format!("cannot enable bloom: {}", e), 1);
+        }
+    }
+    renderer.set_color(config.color);
+    if config.perspective {
+        renderer.set_camera_distance(3.0);
+    }
+    renderer.set_backface_culling(config.cull);
+    if let Mode::Shaded(Shading::Toon { .. }) = config.mode {
+        renderer.set_outline(Some(TOON_OUTLINE));
+    }
+    renderer.set_threads(config.threads);
+
+    if config.preview {
+        if let Mode::Shaded(shading) = config.mode {
+            renderer.set_shading(shading);
+        }
+        preview(&mut renderer, &model);
+        return;
+    }
+
+    if let Some(frames) = config.frames {
+        if let Mode::Shaded(shading) = config.mode {
+            renderer.set_shading(shading);
+        }
+        if config.frame_numbers {
+            renderer.set_frame_label(Some(RgbaColor::new(1.0, 1.0, 1.0, 1.0)));
+        }
+        if let Err(e) = renderer.render_turntable(&model, frames, &config.out_dir) {
+            fail(e.to_string(), 1);
+        }
+        return;
+    }
+
+    let start = Instant::now();
+    match config.mode {
+        Mode::Wireframe => renderer.draw_model_wireframe(&model),
+        Mode::Shaded(shading) => {
+            renderer.set_shading(shading);
+            renderer.draw_model(&model, &Mat4::identity());
+        }
+    }
+    if config.stats {
+        let stats = renderer.stats();
+        println!("{} of {} triangles drawn, {} culled, {} clipped, {} degenerate in {:?}",
+                 stats.drawn, stats.submitted, stats.culled, stats.clipped, stats.degenerate, start.elapsed());
+        println!("{} fragments passed the depth test, {} rejected, {} discarded",
+                 stats.fragments.passed, stats.fragments.rejected, stats.fragments.discarded);
+    }
+
+    if let Err(e) = renderer.to_image().save(&config.output) {
+        fail(format!("cannot write {}: {}", config.output.display(), e), 1);
+    }
+}
@@ -0,0 +1,238 @@
+// Command line of the renderer binary, parsed into a Config so it can be
+// checked without running a render
+
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use shader::Shading;
+use tga::RgbaColor;
+use framebuffer::{buffer_size,MAX_BYTES};
+
+pub const USAGE: &str = "Usage: renderer [OPTIONS]
+
+Renders a model to an image. Without --model the bundled head is drawn with
+its diffuse texture.
+
+Options:
+  --model PATH         OBJ, PLY or glTF model [data/model.obj]
+  --diffuse PATH       TGA diffuse texture, untextured when omitted
+  --output PATH        Image to write, PNG when the extension is .png [output.tga]
+  --width N            Image width in pixels [800]
+  --height N           Image height in pixels [800]
+  --shading MODE       flat, gouraud, phong, toon or wireframe [gouraud]. Toon
+                       shades in 3 bands with black outlines
+  --color RRGGBB       Hex color of untextured surfaces [ffffff]
+  --glow PATH          TGA glow map, added to the lit color regardless of lights
+  --bloom              Blur the glow into a halo around glowing parts
+  --perspective        Perspective camera instead of orthographic
+  --no-cull            Draw back faces too
+  --threads N          Rasterize on N threads [1]
+  --ssaa N             Supersample N times in each direction [1]
+  --stats              Print triangle and fragment counts
+  --frames N           Write N frames of the model turning around instead of one
+                       image, as output_0001.tga onwards
+  --out-dir DIR        Directory the frames go to [.]
+  --frame-numbers      Write the frame number into the corner of every frame
+  --preview            Show the model in a window with orbit controls instead
+                       of writing a file, the default when built with the
+                       preview feature and no --output is given
+  --wireframe          Same as --shading wireframe
+  --png                Same as --output output.png
+  --bench              Time 2048x2048 renders on 1 to 4 threads
+  --help               Show this message
+";
+
+const DEFAULT_MODEL: &str = "data/model.obj";
+const DEFAULT_DIFFUSE: &str = "data/diffuse.tga";
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Mode {
+    Shaded(Shading),
+    Wireframe
+}
+
+#[derive(Clone,PartialEq,Debug)]
+pub struct Config {
+    pub model: PathBuf,
+    pub diffuse: Option<PathBuf>,
+    pub glow: Option<PathBuf>,
+    pub bloom: bool,
+    pub output: PathBuf,
+    pub width: i32,
+    pub height: i32,
+    pub mode: Mode,
+    pub color: RgbaColor,
+    pub perspective: bool,
+    pub cull: bool,
+    pub threads: usize,
+    pub ssaa: u32,
+    pub stats: bool,
+    pub preview: bool,
+    // Turntable frame count and where they're written
+    pub frames: Option<u32>,
+    pub out_dir: PathBuf,
+    pub frame_numbers: bool
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            model: PathBuf::from(DEFAULT_MODEL),
+            diffuse: Some(PathBuf::from(DEFAULT_DIFFUSE)),
+            glow: None,
+            bloom: false,
+            output: PathBuf::from("output.tga"),
+            width: 800,
+            height: 800,
+            mode: Mode::Shaded(Shading::Gouraud),
+            color: RgbaColor::new(1.0, 1.0, 1.0, 1.0),
+            perspective: false,
+            cull: true,
+            threads: 1,
+            ssaa: 1,
+            stats: false,
+            preview: cfg!(feature = "preview"),
+            frames: None,
+            out_dir: PathBuf::from("."),
+            frame_numbers: false
+        }
+    }
+}
+
+#[derive(Clone,PartialEq,Debug)]
+pub enum Command {
+    Render(Config),
+    Benchmark,
+    Help
+}
+
+#[derive(Clone,PartialEq,Debug)]
+pub enum CliError {
+    UnknownOption(String),
+    MissingValue(String),
+    InvalidValue { option: String, value: String, expected: &'static str },
+    // Buffers for the output size and supersampling would pass MAX_BYTES
+    TooLarge { width: i32, height: i32, ssaa: u32 }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CliError::UnknownOption(ref option) => write!(f, "unknown option {}", option),
+            CliError::MissingValue(ref option) => write!(f, "{} needs a value", option),
+            CliError::InvalidValue { ref option, ref value, expected } => {
+                write!(f, "invalid value '{}' for {}, expected {}", value, option, expected)
+            },
+            CliError::TooLarge { width, height, ssaa } =>
+                write!(f, "{}x{} pixels with --ssaa {} need more than the {} bytes of buffers allowed", width, height, ssaa, MAX_BYTES)
+        }
+    }
+}
+
+impl Error for CliError {}
+
+fn invalid(option: &str, value: &str, expected: &'static str) -> CliError {
+    CliError::InvalidValue { option: option.to_string(), value: value.to_string(), expected: expected }
+}
+
+fn positive<T: ::std::str::FromStr + PartialOrd + Default>(option: &str, value: &str) -> Result<T, CliError> {
+    match value.parse::<T>() {
+        Ok(n) if n > T::default() => Ok(n),
+        _ => Err(invalid(option, value, "a positive number"))
+    }
+}
+
+// RRGGBB with an optional leading #
+pub fn parse_color(value: &str) -> Option<RgbaColor> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+    Some(RgbaColor::new_from_u8(channel(0), channel(2), channel(4), 255))
+}
+
+// args excludes the program name. A --model without --diffuse renders
+// untextured, the bundled texture only goes with the bundled model
+pub fn parse_args<I: IntoIterator<Item=String>>(args: I) -> Result<Command, CliError> {
+    let mut config = Config::default();
+    let mut diffuse = None;
+    let mut model = None;
+    let mut output = None;
+    let mut png = false;
+    let mut preview = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let option = arg.as_str();
+        match option {
+            "--help" | "-h" => return Ok(Command::Help),
+            "--bench" => return Ok(Command::Benchmark),
+            "--perspective" => config.perspective = true,
+            "--no-cull" => config.cull = false,
+            "--stats" => config.stats = true,
+            "--bloom" => config.bloom = true,
+            "--frame-numbers" => config.frame_numbers = true,
+            "--preview" => preview = true,
+            "--wireframe" => config.mode = Mode::Wireframe,
+            "--png" => png = true,
+            "--model" | "--diffuse" | "--glow" | "--output" | "--width" | "--height" | "--shading" | "--color" | "--threads" | "--ssaa" |
+            "--frames" | "--out-dir" => {
+                let value = match args.next() {
+                    Some(value) => value,
+                    None => return Err(CliError::MissingValue(arg.clone()))
+                };
+                match option {
+                    "--model" => model = Some(PathBuf::from(value)),
+                    "--diffuse" => diffuse = Some(PathBuf::from(value)),
+                    "--glow" => config.glow = Some(PathBuf::from(value)),
+                    "--output" => output = Some(PathBuf::from(value)),
+                    "--width" => config.width = positive(option, &value)?,
+                    "--height" => config.height = positive(option, &value)?,
+                    "--threads" => config.threads = positive(option, &value)?,
+                    "--ssaa" => config.ssaa = positive(option, &value)?,
+                    "--frames" => config.frames = Some(positive(option, &value)?),
+                    "--out-dir" => config.out_dir = PathBuf::from(value),
+                    "--color" => config.color = parse_color(&value).ok_or(invalid(option, &value, "a hex color like ff8000"))?,
+                    _ => config.mode = match value.as_str() {
+                        "flat" => Mode::Shaded(Shading::Flat),
+                        "gouraud" => Mode::Shaded(Shading::Gouraud),
+                        "phong" => Mode::Shaded(Shading::Phong),
+                        "toon" => Mode::Shaded(Shading::Toon { bands: 3 }),
+                        "wireframe" => Mode::Wireframe,
+                        _ => return Err(invalid(option, &value, "flat, gouraud, phong, toon or wireframe"))
+                    }
+                }
+            },
+            _ => return Err(CliError::UnknownOption(arg.clone()))
+        }
+    }
+
+    if let Some(model) = model {
+        config.model = model;
+        config.diffuse = None;
+    }
+    if diffuse.is_some() {
+        config.diffuse = diffuse;
+    }
+    if let Some(output) = output {
+        config.output = output;
+        config.preview = false;
+    } else if png {
+        config.output = PathBuf::from("output.png");
+        config.preview = false;
+    }
+    if config.frames.is_some() {
+        config.preview = false;
+    }
+    if preview {
+        config.preview = true;
+    }
+
+    let n = config.ssaa as usize;
+    let samples = (config.width as usize).checked_mul(n).and_then(|w| (config.height as usize).checked_mul(n).map(|h| (w, h)));
+    if samples.is_none_or(|(w, h)| buffer_size(w, h).is_err()) {
+        return Err(CliError::TooLarge { width: config.width, height: config.height, ssaa: config.ssaa });
+    }
+    Ok(Command::Render(config))
+}
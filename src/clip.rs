@@ -0,0 +1,109 @@
+// Sutherland–Hodgman clipping of polygons in homogeneous clip space, done
+// before the perspective divide so vertices behind the camera never get
+// mirrored to the front
+
+use math::{Vec2f,Vec3f,Vec4f,Lerp};
+
+// Smallest w kept by the near plane, keeps the divide finite
+const NEAR_W: f32 = 1e-5;
+
+// Position plus the weights of the original triangle's corners, which is
+// all a shader needs to interpolate its own attributes
+#[derive(Clone,Copy,Debug)]
+pub struct ClipVertex {
+    pub position: Vec4f,
+    pub bary: Vec3f
+}
+
+// Weights are linear in clip space, so new vertices interpolate them with
+// the same factor as the position
+impl Lerp for ClipVertex {
+    fn lerp(self, other: ClipVertex, t: f32) -> ClipVertex {
+        ClipVertex {
+            position: self.position + (other.position - self.position) * t,
+            bary: self.bary.lerp(other.bary, t)
+        }
+    }
+}
+
+#[derive(Clone,Copy,Debug)]
+enum Plane {
+    Near,
+    Left,
+    Right,
+    Bottom,
+    Top
+}
+
+const PLANES: [Plane; 5] = [Plane::Near, Plane::Left, Plane::Right, Plane::Bottom, Plane::Top];
+
+impl Plane {
+    // Non-negative on the visible side
+    fn distance(&self, p: Vec4f, extent: Vec2f) -> f32 {
+        match *self {
+            Plane::Near => p.w - NEAR_W,
+            Plane::Left => p.x + p.w * extent.x,
+            Plane::Right => p.w * extent.x - p.x,
+            Plane::Bottom => p.y + p.w * extent.y,
+            Plane::Top => p.w * extent.y - p.y
+        }
+    }
+}
+
+// Clips a convex polygon to the near plane and to |x| <= extent.x, |y| <= extent.y
+// after the divide. There are no depth planes since the z-buffer has no range to
+// overflow. Vertices inside every plane come back untouched, an empty result
+// means nothing is visible
+pub fn clip_polygon(polygon: &[ClipVertex], extent: Vec2f) -> Vec<ClipVertex> {
+    let mut output = polygon.to_vec();
+
+    for plane in PLANES.iter() {
+        let distances: Vec<f32> = output.iter().map(|v| plane.distance(v.position, extent)).collect();
+        if distances.iter().all(|&d| d >= 0.0) {
+            continue;
+        }
+        if distances.iter().all(|&d| d < 0.0) {
+            return Vec::new();
+        }
+
+        let input = output;
+        output = Vec::with_capacity(input.len() + 1);
+        for i in 0..input.len() {
+            let j = (i + 1) % input.len();
+            let (da, db) = (distances[i], distances[j]);
+            if da >= 0.0 {
+                output.push(input[i]);
+            }
+            if (da >= 0.0) != (db >= 0.0) {
+                output.push(input[i].lerp(input[j], da / (da - db)));
+            }
+        }
+    }
+
+    output
+}
+
+// Clips the segment a-b to the same planes as clip_polygon, None when nothing
+// of it is visible
+pub fn clip_segment(a: Vec4f, b: Vec4f, extent: Vec2f) -> Option<(Vec4f, Vec4f)> {
+    let mut t0 = 0f32;
+    let mut t1 = 1f32;
+
+    for plane in PLANES.iter() {
+        let da = plane.distance(a, extent);
+        let db = plane.distance(b, extent);
+        if da < 0.0 && db < 0.0 {
+            return None;
+        }
+        if da < 0.0 {
+            t0 = t0.max(da / (da - db));
+        } else if db < 0.0 {
+            t1 = t1.min(da / (da - db));
+        }
+    }
+
+    if t0 > t1 {
+        return None;
+    }
+    Some((a + (b - a) * t0, a + (b - a) * t1))
+}
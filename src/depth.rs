@@ -0,0 +1,97 @@
+// Depth per pixel, the renderer's z-buffer and the shadow map's light space depth
+
+use std::f32;
+use tga::TgaImage;
+use framebuffer::{FramebufferError,DepthFunc,buffer_size,depth_to_image,try_filled};
+
+// Row-major, -inf where nothing was drawn. In the z-buffer depths lie within
+// the renderer's depth_range, and as the camera looks down -z larger depths
+// are closer to the viewer by default
+pub struct DepthBuffer {
+    pub width: i32,
+    pub height: i32,
+    data: Vec<f32>
+}
+
+impl DepthBuffer {
+    pub fn new(width: i32, height: i32) -> DepthBuffer {
+        assert!(width > 0, "width must be positive");
+        assert!(height > 0, "height must be positive");
+
+        if let Err(e) = buffer_size(width as usize, height as usize) {
+            panic!("{}", e);
+        }
+        let data = vec![f32::NEG_INFINITY; width as usize * height as usize];
+        return DepthBuffer { width: width, height: height, data: data };
+    }
+
+    pub fn try_new(width: i32, height: i32) -> Result<DepthBuffer, FramebufferError> {
+        assert!(width > 0, "width must be positive");
+        assert!(height > 0, "height must be positive");
+
+        let data = try_filled((width as usize) * (height as usize), f32::NEG_INFINITY)?;
+        return Ok(DepthBuffer { width: width, height: height, data: data });
+    }
+
+    pub fn clear(&mut self) {
+        for z in self.data.iter_mut() {
+            *z = f32::NEG_INFINITY;
+        }
+    }
+
+    #[inline(always)]
+    pub fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((x + self.width * y) as usize)
+    }
+
+    // None outside the buffer, -inf where nothing was drawn
+    pub fn get(&self, x: i32, y: i32) -> Option<f32> {
+        self.index(x, y).map(|idx| self.data[idx])
+    }
+
+    // Stores z if it passes func against the stored depth, returns whether it
+    // did. Always false outside the buffer
+    pub fn test_and_set(&mut self, x: i32, y: i32, z: f32, func: DepthFunc) -> bool {
+        match self.index(x, y) {
+            Some(idx) if func.passes(z, self.data[idx]) => {
+                self.data[idx] = z;
+                true
+            },
+            _ => false
+        }
+    }
+
+    // Number of pixels in the rectangle from (x0, y0) to (x1, y1), max
+    // exclusive, where depth z would pass func. The rectangle is clipped to
+    // the buffer, 0 means something drawn hides all of it
+    pub fn occlusion_query(&self, x0: i32, y0: i32, x1: i32, y1: i32, z: f32, func: DepthFunc) -> usize {
+        let (x0, y0) = (x0.max(0), y0.max(0));
+        let (x1, y1) = (x1.min(self.width), y1.min(self.height));
+        let mut visible = 0;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                if func.passes(z, self.data[(x + self.width * y) as usize]) {
+                    visible += 1;
+                }
+            }
+        }
+        visible
+    }
+
+    // See depth_to_image
+    pub fn to_grayscale_image(&self) -> TgaImage {
+        depth_to_image(&self.data, self.width, self.height)
+    }
+
+    // Row-major, for copying tiles in and out
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut [f32] {
+        &mut self.data
+    }
+}
@@ -0,0 +1,44 @@
+// Surroundings of the scene as an equirectangular (latitude-longitude) image,
+// seen where nothing was drawn and in reflections
+
+use std::f32::consts::PI;
+use math::Vec3f;
+use tga::{TgaImage,RgbaColor};
+
+pub struct Environment {
+    image: TgaImage
+}
+
+impl Environment {
+    // Longitude runs left to right across the image with -z, where the
+    // default camera looks, in the middle. Latitude runs from straight down
+    // on the bottom row to straight up on the top one
+    pub fn new(image: TgaImage) -> Environment {
+        return Environment { image: image };
+    }
+
+    pub fn image(&self) -> &TgaImage {
+        &self.image
+    }
+
+    // Bilinear lookup in direction dir, which needn't be normalized. Wraps
+    // around horizontally and stops at the top and bottom rows, so neither
+    // the date line nor the poles show a seam
+    pub fn sample(&self, dir: Vec3f) -> RgbaColor {
+        let dir = dir.normalized();
+        let u = 0.5 + dir.x.atan2(-dir.z) / (2.0 * PI);
+        let v = 0.5 + dir.y.clamp(-1.0, 1.0).asin() / PI;
+
+        let (w, h) = (self.image.width, self.image.height);
+        let x = u * w as f32 - 0.5;
+        let y = v * h as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i32, y0 as i32);
+
+        let texel = |tx: i32, ty: i32| self.image.get_pixel(((tx % w) + w) % w, ty.max(0).min(h - 1));
+        let bottom = texel(x0, y0).lerp(texel(x0 + 1, y0), fx);
+        let top = texel(x0, y0 + 1).lerp(texel(x0 + 1, y0 + 1), fx);
+        bottom.lerp(top, fy)
+    }
+}
@@ -0,0 +1,47 @@
+// Distance fog StandardShader blends shaded fragments toward
+
+use tga::RgbaColor;
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum FogMode {
+    // None up to start, all fog from end on and linear in between
+    Linear { start: f32, end: f32 },
+    // 1 - e^(-density d) fog at depth d
+    Exp { density: f32 }
+}
+
+impl FogMode {
+    // Fraction of fog at view space depth, in [0, 1]
+    pub fn factor(&self, depth: f32) -> f32 {
+        let f = match *self {
+            FogMode::Linear { start, end } => {
+                if depth >= end {
+                    1.0
+                } else if depth <= start {
+                    0.0
+                } else {
+                    (depth - start) / (end - start)
+                }
+            },
+            FogMode::Exp { density } => 1.0 - (-density * depth.max(0.0)).exp()
+        };
+        f.clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub struct Fog {
+    pub color: RgbaColor,
+    pub mode: FogMode
+}
+
+impl Fog {
+    // color seen through depth of fog, keeping its alpha. Full fog gives the
+    // fog color exactly
+    pub fn apply(&self, color: RgbaColor, depth: f32) -> RgbaColor {
+        let f = self.mode.factor(depth);
+        let mut c = if f >= 1.0 { self.color } else { color.lerp(self.color, f) };
+        c.a = color.a;
+        c
+    }
+}
@@ -0,0 +1,314 @@
+use std::error::Error;
+use std::fmt;
+use std::mem::size_of;
+use tga::{TgaImage,RgbaColor};
+use kernel::Kernel;
+
+// Color plus depth per pixel
+pub const BYTES_PER_PIXEL: usize = size_of::<RgbaColor>() + size_of::<f32>();
+// Most a renderer's buffers may take, larger sizes fail with TooLarge. 4 GiB
+// doesn't fit a 32-bit usize, there the address space is the limit
+#[cfg(target_pointer_width = "64")]
+pub const MAX_BYTES: usize = 4 << 30;
+#[cfg(not(target_pointer_width = "64"))]
+pub const MAX_BYTES: usize = usize::MAX;
+
+#[derive(Debug)]
+pub enum FramebufferError {
+    // Over MAX_BYTES, bytes is None when the size doesn't fit in usize
+    TooLarge { width: usize, height: usize, bytes: Option<usize> },
+    // The allocator refused
+    OutOfMemory { bytes: usize }
+}
+
+impl fmt::Display for FramebufferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FramebufferError::TooLarge { width, height, bytes: Some(bytes) } =>
+                write!(f, "{}x{} buffers need {} bytes, more than the {} allowed", width, height, bytes, MAX_BYTES),
+            FramebufferError::TooLarge { width, height, bytes: None } =>
+                write!(f, "{}x{} buffers are too large to address", width, height),
+            FramebufferError::OutOfMemory { bytes } => write!(f, "failed to allocate {} bytes", bytes)
+        }
+    }
+}
+
+impl Error for FramebufferError {}
+
+// Bytes of color and depth for width x height pixels, checked against MAX_BYTES
+pub fn buffer_size(width: usize, height: usize) -> Result<usize, FramebufferError> {
+    match width.checked_mul(height).and_then(|n| n.checked_mul(BYTES_PER_PIXEL)) {
+        Some(bytes) if bytes <= MAX_BYTES => Ok(bytes),
+        bytes => Err(FramebufferError::TooLarge { width: width, height: height, bytes: bytes })
+    }
+}
+
+// vec![value; len] reporting allocation failure instead of aborting
+pub fn try_filled<T: Clone>(len: usize, value: T) -> Result<Vec<T>, FramebufferError> {
+    let mut v = Vec::new();
+    if v.try_reserve_exact(len).is_err() {
+        return Err(FramebufferError::OutOfMemory { bytes: len * size_of::<T>() });
+    }
+    v.resize(len, value);
+    Ok(v)
+}
+
+// sRGB transfer functions for a single channel in [0, 1]
+pub fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+}
+
+pub fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 { v * 12.92 } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 }
+}
+
+pub fn color_to_linear(c: RgbaColor) -> RgbaColor {
+    RgbaColor::new(srgb_to_linear(c.r), srgb_to_linear(c.g), srgb_to_linear(c.b), c.a)
+}
+
+// Grayscale image of a depth buffer with larger values closer. Finite depths
+// are stretched over the full range, a constant depth is white and pixels
+// that were never drawn (-inf) are black
+pub fn depth_to_image(depth: &[f32], width: i32, height: i32) -> TgaImage {
+    let (min, max) = depth.iter().filter(|d| d.is_finite())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &d| (lo.min(d), hi.max(d)));
+
+    let mut image = TgaImage::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let d = depth[(x + width * y) as usize];
+            let v = if !d.is_finite() {
+                0.0
+            } else if max > min {
+                (d - min) / (max - min)
+            } else {
+                1.0
+            };
+            image.set_pixel(x, y, &RgbaColor::new(v, v, v, 1.0));
+        }
+    }
+    image
+}
+
+// 4x4 Bayer threshold matrix
+const BAYER: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5]
+];
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum BlendMode {
+    // Overwrites the stored pixel
+    Replace,
+    // Source-over using the incoming alpha
+    AlphaBlend,
+    // Adds the incoming color scaled by its alpha
+    Additive
+}
+
+// dst with color written over it, clamped to [0, 1]
+pub fn blend(dst: RgbaColor, color: &RgbaColor, mode: BlendMode) -> RgbaColor {
+    let a = color.a.clamp(0.0, 1.0);
+    let mut c = match mode {
+        BlendMode::Replace => color.to_owned(),
+        BlendMode::AlphaBlend => RgbaColor::new(
+            color.r * a + dst.r * (1.0 - a),
+            color.g * a + dst.g * (1.0 - a),
+            color.b * a + dst.b * (1.0 - a),
+            a + dst.a * (1.0 - a)
+        ),
+        BlendMode::Additive => RgbaColor::new(dst.r + color.r * a, dst.g + color.g * a, dst.b + color.b * a, dst.a + a)
+    };
+    c.clamp()
+}
+
+// How an incoming fragment's depth compares to the stored one to pass
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum DepthFunc {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Always,
+    Never
+}
+
+impl DepthFunc {
+    // Pixels nothing was drawn to store -inf and pass every test but Never
+    pub fn passes(self, z: f32, stored: f32) -> bool {
+        if stored == f32::NEG_INFINITY {
+            return self != DepthFunc::Never;
+        }
+        match self {
+            DepthFunc::Less => z < stored,
+            DepthFunc::LessEqual => z <= stored,
+            DepthFunc::Greater => z > stored,
+            DepthFunc::GreaterEqual => z >= stored,
+            DepthFunc::Always => true,
+            DepthFunc::Never => false
+        }
+    }
+}
+
+// Floating point RGBA render target, colors are only quantized to 8 bits when
+// converted to an image for writing
+#[derive(Clone)]
+pub struct Framebuffer {
+    pub width: i32,
+    pub height: i32,
+    pixels: Vec<RgbaColor>
+}
+
+impl Framebuffer {
+    pub fn new(width: i32, height: i32) -> Framebuffer {
+        assert!(width > 0, "width must be positive");
+        assert!(height > 0, "height must be positive");
+
+        // Sizes past MAX_BYTES panic here instead of overflowing the count
+        if let Err(e) = buffer_size(width as usize, height as usize) {
+            panic!("{}", e);
+        }
+        let pixels = vec![RgbaColor::new(0.0, 0.0, 0.0, 0.0); width as usize * height as usize];
+        return Framebuffer { width: width, height: height, pixels: pixels };
+    }
+
+    pub fn try_new(width: i32, height: i32) -> Result<Framebuffer, FramebufferError> {
+        assert!(width > 0, "width must be positive");
+        assert!(height > 0, "height must be positive");
+
+        let pixels = try_filled((width as usize) * (height as usize), RgbaColor::new(0.0, 0.0, 0.0, 0.0))?;
+        return Ok(Framebuffer { width: width, height: height, pixels: pixels });
+    }
+
+    // Sets every pixel to color
+    pub fn clear(&mut self, color: RgbaColor) {
+        let color = color.to_owned().clamp();
+        for p in self.pixels.iter_mut() {
+            *p = color;
+        }
+    }
+
+    #[inline(always)]
+    pub fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((x + self.width * y) as usize)
+    }
+
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: &RgbaColor) {
+        self.set_pixel_blended(x, y, color, BlendMode::Replace);
+    }
+
+    // Source-over compositing of color onto the stored pixel using color's alpha
+    pub fn set_pixel_blend(&mut self, x: i32, y: i32, color: &RgbaColor) {
+        self.set_pixel_blended(x, y, color, BlendMode::AlphaBlend);
+    }
+
+    pub fn set_pixel_blended(&mut self, x: i32, y: i32, color: &RgbaColor, mode: BlendMode) {
+        if let Some(idx) = self.index(x, y) {
+            self.pixels[idx] = blend(self.pixels[idx], color, mode);
+        }
+    }
+
+    pub fn get_pixel(&self, x: i32, y: i32) -> Option<RgbaColor> {
+        self.index(x, y).map(|idx| self.pixels[idx])
+    }
+
+    // Averages factor x factor blocks into single pixels. The stored values
+    // are averaged as they are, which is linear light when gamma correction
+    // is on
+    pub fn resolve(&self, factor: i32) -> Framebuffer {
+        let mut resolved = Framebuffer::new(self.width / factor, self.height / factor);
+        let weight = 1.0 / (factor * factor) as f32;
+
+        for y in 0..resolved.height {
+            for x in 0..resolved.width {
+                let mut sum = RgbaColor::new(0.0, 0.0, 0.0, 0.0);
+                for sy in 0..factor {
+                    for sx in 0..factor {
+                        let c = self.pixels[(x * factor + sx + self.width * (y * factor + sy)) as usize];
+                        sum.r += c.r;
+                        sum.g += c.g;
+                        sum.b += c.b;
+                        sum.a += c.a;
+                    }
+                }
+                resolved.pixels[(x + resolved.width * y) as usize] = RgbaColor::new(sum.r * weight, sum.g * weight, sum.b * weight, sum.a * weight);
+            }
+        }
+        resolved
+    }
+
+    pub fn convolve(&self, kernel: &Kernel) -> Framebuffer {
+        let pixels = kernel.apply(&self.pixels, self.width, self.height);
+        return Framebuffer { width: self.width, height: self.height, pixels: pixels };
+    }
+
+    // Adds other's color to every pixel, clamped, keeping alpha. Both must
+    // be the same size
+    pub fn add(&mut self, other: &Framebuffer) {
+        assert!(self.width == other.width && self.height == other.height, "framebuffers must be the same size");
+        for (p, o) in self.pixels.iter_mut().zip(other.pixels.iter()) {
+            *p = RgbaColor::new(p.r + o.r, p.g + o.g, p.b + o.b, p.a).clamp();
+        }
+    }
+
+    // Row-major, for copying tiles in and out
+    pub fn pixels(&self) -> &[RgbaColor] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [RgbaColor] {
+        &mut self.pixels
+    }
+
+    // Pixel as written out, encoded to sRGB when gamma and with ordered
+    // dithering ahead of quantizing to 8 bits
+    fn output_color(&self, x: i32, y: i32, gamma: bool, dither: bool) -> RgbaColor {
+        let c = self.pixels[(x + self.width * y) as usize];
+        let mut c = if gamma {
+            RgbaColor::new(linear_to_srgb(c.r), linear_to_srgb(c.g), linear_to_srgb(c.b), c.a)
+        } else {
+            c
+        };
+
+        if dither {
+            let offset = (BAYER[(y & 3) as usize][(x & 3) as usize] as f32 + 0.5) / (16.0 * 255.0);
+            c.r += offset;
+            c.g += offset;
+            c.b += offset;
+        }
+        c
+    }
+
+    // 8 bit image of the framebuffer, sRGB encoded when gamma is set and
+    // written as is otherwise. Dithering adds a position dependent offset of
+    // less than one 8 bit step to the color channels before they're truncated,
+    // trading banding for a fixed fine pattern
+    pub fn to_image(&self, gamma: bool, dither: bool) -> TgaImage {
+        let mut image = TgaImage::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                image.set_pixel(x, y, &self.output_color(x, y, gamma, dither));
+            }
+        }
+        image
+    }
+
+    // Same 8 bit values as to_image packed as 0x00RRGGBB, the layout window
+    // toolkits take. buffer is reused, it only grows when the size does
+    pub fn to_rgb32(&self, gamma: bool, dither: bool, buffer: &mut Vec<u32>) {
+        buffer.clear();
+        buffer.reserve((self.width * self.height) as usize);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let c = self.output_color(x, y, gamma, dither).clamp();
+                let (r, g, b) = ((c.r * 255.0) as u8 as u32, (c.g * 255.0) as u8 as u32, (c.b * 255.0) as u8 as u32);
+                buffer.push(r << 16 | g << 8 | b);
+            }
+        }
+    }
+}
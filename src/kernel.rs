@@ -0,0 +1,106 @@
+// Square convolution kernels for post-processing images. Pixels beyond the
+// edges repeat the nearest edge pixel
+
+use tga::RgbaColor;
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct Kernel {
+    width: usize,
+    // Row-major, width x width
+    weights: Vec<f32>,
+    // When the kernel is the outer product of this with itself, the image is
+    // convolved with it along rows and then columns instead, width instead
+    // of width^2 reads per pixel
+    factors: Option<Vec<f32>>
+}
+
+impl Kernel {
+    pub fn new(weights: Vec<f32>, width: usize) -> Kernel {
+        assert!(width % 2 == 1, "kernel width must be odd");
+        assert!(weights.len() == width * width, "kernel needs width x width weights");
+        return Kernel { width: width, weights: weights, factors: None };
+    }
+
+    pub fn separable(factors: Vec<f32>) -> Kernel {
+        let width = factors.len();
+        let weights = factors.iter().flat_map(|a| factors.iter().map(move |b| a * b)).collect();
+        let mut kernel = Kernel::new(weights, width);
+        kernel.factors = Some(factors);
+        kernel
+    }
+
+    pub fn identity() -> Kernel {
+        Kernel::separable(vec![1.0])
+    }
+
+    // Average of the width x width neighbourhood
+    pub fn box_blur(width: usize) -> Kernel {
+        Kernel::separable(vec![1.0 / width as f32; width])
+    }
+
+    // Binomial approximations of a gaussian
+    pub fn gaussian3() -> Kernel {
+        Kernel::separable(vec![0.25, 0.5, 0.25])
+    }
+
+    pub fn gaussian5() -> Kernel {
+        Kernel::separable(vec![1.0 / 16.0, 4.0 / 16.0, 6.0 / 16.0, 4.0 / 16.0, 1.0 / 16.0])
+    }
+
+    pub fn sharpen() -> Kernel {
+        Kernel::new(vec![0.0, -1.0, 0.0, -1.0, 5.0, -1.0, 0.0, -1.0, 0.0], 3)
+    }
+
+    // Laplacian, black where the image is flat and bright along edges
+    pub fn edge_detect() -> Kernel {
+        Kernel::new(vec![-1.0, -1.0, -1.0, -1.0, 8.0, -1.0, -1.0, -1.0, -1.0], 3)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    // Convolves a row-major width x height image, results aren't clamped
+    pub fn apply(&self, pixels: &[RgbaColor], width: i32, height: i32) -> Vec<RgbaColor> {
+        match self.factors {
+            Some(ref factors) => {
+                let rows = convolve(pixels, width, height, factors, factors.len(), 1);
+                convolve(&rows, width, height, factors, 1, factors.len())
+            },
+            None => convolve(pixels, width, height, &self.weights, self.width, self.width)
+        }
+    }
+}
+
+// kernel is kw x kh and centered on the pixel, both sizes odd
+pub fn convolve(pixels: &[RgbaColor], width: i32, height: i32, kernel: &[f32], kw: usize, kh: usize) -> Vec<RgbaColor> {
+    let (rx, ry) = ((kw / 2) as i32, (kh / 2) as i32);
+    let mut result = Vec::with_capacity(pixels.len());
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = RgbaColor::new(0.0, 0.0, 0.0, 0.0);
+            for ky in 0..kh as i32 {
+                let sy = (y + ky - ry).max(0).min(height - 1);
+                for kx in 0..kw as i32 {
+                    let weight = kernel[(kx + kw as i32 * ky) as usize];
+                    if weight == 0.0 {
+                        continue;
+                    }
+                    let sx = (x + kx - rx).max(0).min(width - 1);
+                    let c = pixels[(sx + width * sy) as usize];
+                    sum.r += c.r * weight;
+                    sum.g += c.g * weight;
+                    sum.b += c.b * weight;
+                    sum.a += c.a * weight;
+                }
+            }
+            result.push(sum);
+        }
+    }
+    result
+}
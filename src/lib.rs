@@ -0,0 +1,45 @@
+// Software rasterizer. Renderer draws models and screen space primitives into
+// a framebuffer and hands the result out as a TgaImage
+
+#[cfg(feature = "preview")]
+extern crate minifb;
+
+pub mod tga;
+mod png;
+pub mod texture;
+pub mod framebuffer;
+pub mod depth;
+pub mod target;
+pub mod model;
+pub mod material;
+pub mod math;
+mod clip;
+mod shadow;
+pub mod shader;
+mod raster;
+pub mod light;
+pub mod projection;
+pub mod fog;
+pub mod kernel;
+pub mod text;
+pub mod environment;
+pub mod renderer;
+pub mod scene;
+pub mod cli;
+pub mod test_scene;
+#[cfg(feature = "preview")]
+pub mod preview;
+
+pub use renderer::{Renderer,RenderStats,Winding,FrameError,PointColor};
+pub use shader::{Shader,StandardShader,Shading,Vertex};
+pub use light::Light;
+pub use projection::Projection;
+pub use fog::FogMode;
+pub use tga::{TgaImage,RgbaColor,DiffResult};
+pub use texture::{Texture,TextureId};
+pub use kernel::Kernel;
+pub use depth::DepthBuffer;
+pub use target::RenderTarget;
+pub use model::Model;
+pub use scene::{Scene,NodeId,NodeMaterial};
+pub use math::{Vec2f,Vec3f,Vec4f,Mat4};
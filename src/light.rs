@@ -0,0 +1,51 @@
+// Light sources StandardShader sums the contributions of
+
+use math::Vec3f;
+use tga::RgbaColor;
+
+#[derive(Clone,Copy,Debug)]
+pub enum LightKind {
+    // Direction the light travels in world space, like sunlight
+    Directional(Vec3f),
+    // World space position and constant, linear and quadratic attenuation,
+    // the light falls off as 1 / (x + y d + z d^2) with distance d
+    Point { position: Vec3f, attenuation: Vec3f }
+}
+
+#[derive(Clone,Copy,Debug)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: RgbaColor,
+    pub intensity: f32
+}
+
+impl Light {
+    pub fn directional(dir: Vec3f, color: RgbaColor, intensity: f32) -> Light {
+        return Light { kind: LightKind::Directional(dir.normalized()), color: color, intensity: intensity };
+    }
+
+    pub fn point(position: Vec3f, attenuation: Vec3f, color: RgbaColor, intensity: f32) -> Light {
+        return Light { kind: LightKind::Point { position: position, attenuation: attenuation }, color: color, intensity: intensity };
+    }
+
+    // Direction the light travels at p and the intensity arriving there
+    pub fn incident(&self, p: Vec3f) -> (Vec3f, f32) {
+        match self.kind {
+            LightKind::Directional(dir) => (dir, self.intensity),
+            LightKind::Point { position, attenuation } => {
+                let d = p - position;
+                let distance = d.length();
+                let falloff = attenuation.x + attenuation.y * distance + attenuation.z * distance * distance;
+                let dir = if distance > 0.0 { d / distance } else { Vec3f::new(0.0, 0.0, -1.0) };
+                (dir, if falloff > 0.0 { self.intensity / falloff } else { self.intensity })
+            }
+        }
+    }
+}
+
+impl Default for Light {
+    // White light straight into the screen
+    fn default() -> Light {
+        Light::directional(Vec3f::new(0.0, 0.0, -1.0), RgbaColor::new(1.0, 1.0, 1.0, 1.0), 1.0)
+    }
+}
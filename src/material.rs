@@ -0,0 +1,90 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::Path;
+use std::str::FromStr;
+use tga::{TgaImage,RgbaColor};
+use texture::Texture;
+
+pub struct Material {
+    pub name: String,
+    pub diffuse_color: RgbaColor,   // Kd, alpha taken from d
+    pub specular_color: RgbaColor,  // Ks
+    pub shininess: f32,             // Ns
+    pub diffuse_map: Option<Texture> // map_Kd, with mipmaps
+}
+
+impl Material {
+    pub fn new(name: &str) -> Material {
+        Material {
+            name: name.to_string(),
+            diffuse_color: RgbaColor::new(1.0, 1.0, 1.0, 1.0),
+            specular_color: RgbaColor::new(0.0, 0.0, 0.0, 1.0),
+            shininess: 0.0,
+            diffuse_map: None
+        }
+    }
+}
+
+fn parse_color(str: &str) -> Option<RgbaColor> {
+    let mut rgb = [0f32; 3];
+    for (i, word) in str.split_whitespace().take(3).enumerate() {
+        rgb[i] = match f32::from_str(word) {
+            Ok(v) => v,
+            Err(_) => return None
+        };
+    }
+    Some(RgbaColor::new(rgb[0], rgb[1], rgb[2], 1.0))
+}
+
+// Reads materials from an MTL file. Texture paths are resolved against the
+// library's directory, unreadable lines and missing textures are skipped so
+// a broken library degrades to untextured rendering instead of failing the load
+pub fn load_library(filename: &Path) -> Vec<Material> {
+    let mut materials: Vec<Material> = Vec::new();
+    let file = match File::open(filename) {
+        Ok(file) => file,
+        Err(_) => return materials
+    };
+    let dir = filename.parent().unwrap_or(Path::new(""));
+
+    for line in BufReader::new(file).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break
+        };
+        let line = line.trim();
+
+        if let Some(name) = line.strip_prefix("newmtl ") {
+            materials.push(Material::new(name.trim()));
+            continue;
+        }
+
+        let material = match materials.last_mut() {
+            Some(m) => m,
+            None => continue
+        };
+
+        if let Some(rest) = line.strip_prefix("Kd ") {
+            if let Some(c) = parse_color(rest) {
+                material.diffuse_color = RgbaColor::new(c.r, c.g, c.b, material.diffuse_color.a);
+            }
+        } else if let Some(rest) = line.strip_prefix("Ks ") {
+            if let Some(c) = parse_color(rest) { material.specular_color = c; }
+        } else if let Some(rest) = line.strip_prefix("Ns ") {
+            if let Ok(v) = f32::from_str(rest.trim()) { material.shininess = v; }
+        } else if let Some(rest) = line.strip_prefix("d ") {
+            if let Ok(v) = f32::from_str(rest.trim()) { material.diffuse_color.a = v; }
+        } else if let Some(rest) = line.strip_prefix("map_Kd ") {
+            let path = dir.join(rest.trim());
+            // Missing or unreadable textures leave the material untextured
+            material.diffuse_map = TgaImage::new_from_file(&path).ok().map(|image| {
+                let mut texture = Texture::new(image);
+                texture.generate_mipmaps();
+                texture
+            });
+        }
+    }
+
+    materials
+}
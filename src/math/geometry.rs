@@ -0,0 +1,87 @@
+// Triangle helpers shared by rasterization, picking and ray casting
+
+use super::{Vec2f,Vec3f};
+
+const EPSILON: f32 = 1e-7;
+
+// Signed area, positive when a, b, c run counter-clockwise
+pub fn triangle_area_2d(a: Vec2f, b: Vec2f, c: Vec2f) -> f32 {
+    0.5 * ((b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y))
+}
+
+// Shoelace formula for a simple polygon, positive when counter-clockwise
+pub fn polygon_area_2d(points: &[Vec2f]) -> f32 {
+    let mut sum = 0.0;
+    for i in 0..points.len() {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+        sum += a.x * b.y - b.x * a.y;
+    }
+    0.5 * sum
+}
+
+// Twice the signed area of a, b, p: positive when p is to the left of a -> b.
+// The endpoints are always taken in the same order, so an edge shared by two
+// triangles gives exactly opposite values in both and pixels on it can't be
+// missed by both
+pub fn edge_function(a: Vec2f, b: Vec2f, p: Vec2f) -> f32 {
+    if (a.x, a.y) > (b.x, b.y) {
+        return -edge_function(b, a, p);
+    }
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+// Weights of a, b and c for p, they sum to 1 and are all non-negative inside the
+// triangle. Degenerate triangles give (-1, 1, 1) so callers skipping negative
+// weights reject every point
+pub fn barycentric(p: Vec2f, a: Vec2f, b: Vec2f, c: Vec2f) -> Vec3f {
+    let area = triangle_area_2d(a, b, c);
+    if area.abs() < EPSILON {
+        return Vec3f::new(-1.0, 1.0, 1.0);
+    }
+
+    let u = triangle_area_2d(p, b, c) / area;
+    let v = triangle_area_2d(a, p, c) / area;
+    Vec3f::new(u, v, 1.0 - u - v)
+}
+
+// Points on edges and vertices count as inside
+pub fn point_in_triangle(p: Vec2f, a: Vec2f, b: Vec2f, c: Vec2f) -> bool {
+    let w = barycentric(p, a, b, c);
+    w.x >= 0.0 && w.y >= 0.0 && w.z >= 0.0
+}
+
+// Möller–Trumbore. Returns the distance t along dir and the barycentric u, v of
+// v1 and v2 at the hit. Hits behind the origin and rays parallel to the plane
+// are misses, as are triangles seen from the back (clockwise from the ray's
+// point of view) when cull_backfaces is set
+pub fn ray_triangle_intersect(origin: Vec3f, dir: Vec3f, v0: Vec3f, v1: Vec3f, v2: Vec3f, cull_backfaces: bool) -> Option<(f32, f32, f32)> {
+    let e1 = v1 - v0;
+    let e2 = v2 - v0;
+    let p = dir ^ e2;
+    let det = e1 * p;
+
+    if (cull_backfaces && det < EPSILON) || det.abs() < EPSILON {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let s = origin - v0;
+    let u = (s * p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s ^ e1;
+    let v = (dir * q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = (e2 * q) * inv_det;
+    if t <= EPSILON {
+        return None;
+    }
+
+    Some((t, u, v))
+}
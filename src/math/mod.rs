@@ -0,0 +1,914 @@
+use std::ops::{Add,Sub,Mul,Div,BitXor,Neg,AddAssign,SubAssign,MulAssign,DivAssign,Index,IndexMut};
+
+pub mod geometry;
+
+// Floating point scalar the vector and matrix types are generic over. The
+// renderer uses f32, f64 is there for standalone geometry processing where
+// single precision drifts
+pub trait Float: Copy + PartialOrd
+    + Add<Output=Self> + Sub<Output=Self> + Mul<Output=Self> + Div<Output=Self> + Neg<Output=Self>
+    + AddAssign + SubAssign + MulAssign + DivAssign {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn from_f64(v: f64) -> Self;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn tan(self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+    fn is_finite(self) -> bool;
+}
+
+impl Float for f32 {
+    fn zero() -> f32 { 0.0 }
+    fn one() -> f32 { 1.0 }
+    fn from_f64(v: f64) -> f32 { v as f32 }
+    fn sqrt(self) -> f32 { f32::sqrt(self) }
+    fn abs(self) -> f32 { f32::abs(self) }
+    fn tan(self) -> f32 { f32::tan(self) }
+    fn sin_cos(self) -> (f32, f32) { f32::sin_cos(self) }
+    fn is_finite(self) -> bool { f32::is_finite(self) }
+}
+
+impl Float for f64 {
+    fn zero() -> f64 { 0.0 }
+    fn one() -> f64 { 1.0 }
+    fn from_f64(v: f64) -> f64 { v }
+    fn sqrt(self) -> f64 { f64::sqrt(self) }
+    fn abs(self) -> f64 { f64::abs(self) }
+    fn tan(self) -> f64 { f64::tan(self) }
+    fn sin_cos(self) -> (f64, f64) { f64::sin_cos(self) }
+    fn is_finite(self) -> bool { f64::is_finite(self) }
+}
+
+#[derive(Debug,Clone,Copy)]
+pub struct Vec2<T> {
+    pub x: T,
+    pub y: T
+}
+
+pub type Vec2f = Vec2<f32>;
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Vec3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T
+}
+
+pub type Vec3f = Vec3<f32>;
+pub type Vec3d = Vec3<f64>;
+pub type Vec3i = Vec3<i32>;
+
+// Homogeneous coordinates, w is 1 for points and 0 for directions
+#[derive(Debug,Clone,Copy)]
+pub struct Vec4<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pub w: T
+}
+
+pub type Vec4f = Vec4<f32>;
+pub type Vec4d = Vec4<f64>;
+
+impl<T> Vec2<T> {
+    pub fn new(x: T, y: T) -> Vec2<T> {
+        Vec2::<T> {x: x, y: y}
+    }
+}
+
+impl<T> Mul<T> for Vec2<T>
+    where T: Mul<T, Output=T> + Copy {
+    type Output = Vec2<T>;
+
+    fn mul(self, rhs: T) -> Vec2<T> {
+        Vec2::<T>::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl<T> Add<Vec2<T>> for Vec2<T>
+    where T: Add<T, Output=T> {
+    type Output = Vec2<T>;
+
+    fn add(self, rhs: Vec2<T>) -> Vec2<T> {
+        Vec2::<T>::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl<T> Sub<Vec2<T>> for Vec2<T>
+    where T: Sub<T, Output=T> {
+    type Output = Vec2<T>;
+
+    fn sub(self, rhs: Vec2<T>) -> Vec2<T> {
+        Vec2::<T>::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl<T> Vec3<T> {
+    pub fn new(x: T, y: T, z: T) -> Vec3<T> {
+        Vec3::<T> { x: x, y: y, z: z }
+    }
+}
+
+impl<T> Vec3<T>
+    where T: Mul<T, Output=T> + Add<T, Output=T> + Sub<T, Output=T> + Copy {
+    pub fn dot(&self, other: Vec3<T>) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: Vec3<T>) -> Vec3<T> {
+        Vec3::<T>::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x
+        )
+    }
+}
+
+// Truncates towards zero like an `as` cast
+impl From<Vec3f> for Vec3i {
+    fn from(v: Vec3f) -> Vec3i {
+        Vec3i::new(v.x as i32, v.y as i32, v.z as i32)
+    }
+}
+
+impl From<Vec3i> for Vec3f {
+    fn from(v: Vec3i) -> Vec3f {
+        Vec3f::new(v.x as f32, v.y as f32, v.z as f32)
+    }
+}
+
+impl From<Vec3f> for Vec3d {
+    fn from(v: Vec3f) -> Vec3d {
+        Vec3d::new(v.x as f64, v.y as f64, v.z as f64)
+    }
+}
+
+// Rounds to the nearest f32
+impl From<Vec3d> for Vec3f {
+    fn from(v: Vec3d) -> Vec3f {
+        Vec3f::new(v.x as f32, v.y as f32, v.z as f32)
+    }
+}
+
+impl<T: Float> Vec3<T> {
+    pub fn length_squared(&self) -> T {
+        self.dot(*self)
+    }
+
+    pub fn length(&self) -> T {
+        self.length_squared().sqrt()
+    }
+
+    // Unit length copy, the zero vector stays zero instead of turning into NaNs
+    pub fn normalized(&self) -> Vec3<T> {
+        self.try_normalized().unwrap_or(*self)
+    }
+
+    pub fn try_normalized(&self) -> Option<Vec3<T>> {
+        let len = self.length();
+        if len == T::zero() || !len.is_finite() {
+            return None;
+        }
+        Some(*self / len)
+    }
+
+    pub fn approx_eq(&self, other: &Vec3<T>, epsilon: T) -> bool {
+        (self.x - other.x).abs() <= epsilon && (self.y - other.y).abs() <= epsilon && (self.z - other.z).abs() <= epsilon
+    }
+
+    // Normalizes in place and returns the result, NaN for the zero vector
+    pub fn normalize(&mut self) -> Vec3<T> {
+        let v = *self * (T::one() / (self.x * self.x + self.y * self.y + self.z * self.z).sqrt());
+        self.x = v.x;
+        self.y = v.y;
+        self.z = v.z;
+        return *self;
+    }
+
+    pub fn to_point(&self) -> Vec4<T> {
+        Vec4::new(self.x, self.y, self.z, T::one())
+    }
+
+    pub fn to_direction(&self) -> Vec4<T> {
+        Vec4::new(self.x, self.y, self.z, T::zero())
+    }
+}
+
+impl<T> Vec4<T> {
+    pub fn new(x: T, y: T, z: T, w: T) -> Vec4<T> {
+        Vec4::<T> { x: x, y: y, z: z, w: w }
+    }
+}
+
+impl<T: Float> Vec4<T> {
+    // Perspective divide. Directions and points on the camera plane have w = 0
+    // and come back undivided rather than as infinities
+    pub fn to_vec3(&self) -> Vec3<T> {
+        if self.w == T::zero() {
+            return Vec3::new(self.x, self.y, self.z);
+        }
+        Vec3::new(self.x / self.w, self.y / self.w, self.z / self.w)
+    }
+}
+
+impl From<Vec4f> for Vec4d {
+    fn from(v: Vec4f) -> Vec4d {
+        Vec4d::new(v.x as f64, v.y as f64, v.z as f64, v.w as f64)
+    }
+}
+
+impl From<Vec4d> for Vec4f {
+    fn from(v: Vec4d) -> Vec4f {
+        Vec4f::new(v.x as f32, v.y as f32, v.z as f32, v.w as f32)
+    }
+}
+
+impl<T: Float> Add<Vec4<T>> for Vec4<T> {
+    type Output = Vec4<T>;
+
+    #[inline(always)]
+    fn add(self, other: Vec4<T>) -> Vec4<T> {
+        Vec4::new(self.x + other.x, self.y + other.y, self.z + other.z, self.w + other.w)
+    }
+}
+
+impl<T: Float> Sub<Vec4<T>> for Vec4<T> {
+    type Output = Vec4<T>;
+
+    #[inline(always)]
+    fn sub(self, other: Vec4<T>) -> Vec4<T> {
+        Vec4::new(self.x - other.x, self.y - other.y, self.z - other.z, self.w - other.w)
+    }
+}
+
+impl<T: Float> Mul<T> for Vec4<T> {
+    type Output = Vec4<T>;
+
+    #[inline(always)]
+    fn mul(self, f: T) -> Vec4<T> {
+        Vec4::new(self.x * f, self.y * f, self.z * f, self.w * f)
+    }
+}
+
+impl<T: Float> Div<T> for Vec4<T> {
+    type Output = Vec4<T>;
+
+    #[inline(always)]
+    fn div(self, f: T) -> Vec4<T> {
+        Vec4::new(self.x / f, self.y / f, self.z / f, self.w / f)
+    }
+}
+
+impl<T: Float> Mul<Vec4<T>> for Vec4<T> {
+    type Output = T;
+
+    #[inline(always)]
+    fn mul(self, other: Vec4<T>) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+}
+
+impl<T> Add<Vec3<T>> for Vec3<T>
+    where T: Add<T, Output=T> {
+    type Output = Vec3<T>;
+
+    #[inline(always)]
+    fn add(self, other: Vec3<T>) -> Vec3<T> {
+        Vec3::<T>::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl<T> Sub<Vec3<T>> for Vec3<T>
+    where T: Sub<T, Output=T> {
+    type Output = Vec3<T>;
+
+    #[inline(always)]
+    fn sub(self, other: Vec3<T>) -> Vec3<T> {
+        Vec3::<T>::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl<'a,'b,T> Sub<&'a Vec3<T>> for &'b Vec3<T>
+    where T: Sub<T, Output=T> + Copy {
+    type Output = Vec3<T>;
+
+    #[inline(always)]
+    fn sub(self, other: &Vec3<T>) -> Vec3<T> {
+        Vec3::<T>::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+// Cross product
+impl<T> BitXor<Vec3<T>> for Vec3<T>
+    where T: Mul<T, Output=T> + Add<T, Output=T> + Sub<T, Output=T> + Copy {
+    type Output = Vec3<T>;
+
+    #[inline(always)]
+    fn bitxor(self, other: Vec3<T>) -> Vec3<T> {
+        self.cross(other)
+    }
+}
+
+impl<T> Neg for Vec3<T>
+    where T: Neg<Output=T> {
+    type Output = Vec3<T>;
+
+    #[inline(always)]
+    fn neg(self) -> Vec3<T> {
+        Vec3::<T>::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl<T> AddAssign<Vec3<T>> for Vec3<T>
+    where T: AddAssign<T> {
+    fn add_assign(&mut self, other: Vec3<T>) {
+        self.x += other.x;
+        self.y += other.y;
+        self.z += other.z;
+    }
+}
+
+impl<T> SubAssign<Vec3<T>> for Vec3<T>
+    where T: SubAssign<T> {
+    fn sub_assign(&mut self, other: Vec3<T>) {
+        self.x -= other.x;
+        self.y -= other.y;
+        self.z -= other.z;
+    }
+}
+
+impl<T> MulAssign<T> for Vec3<T>
+    where T: MulAssign<T> + Copy {
+    fn mul_assign(&mut self, f: T) {
+        self.x *= f;
+        self.y *= f;
+        self.z *= f;
+    }
+}
+
+impl<T> Index<usize> for Vec3<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        match i {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 index {} out of range", i)
+        }
+    }
+}
+
+impl<T> IndexMut<usize> for Vec3<T> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        match i {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("Vec3 index {} out of range", i)
+        }
+    }
+}
+
+impl<T> Mul<T> for Vec3<T>
+    where T: Mul<T, Output=T> + Copy {
+    type Output = Vec3<T>;
+
+    #[inline(always)]
+    fn mul(self, f: T) -> Vec3<T> {
+        Vec3::<T>::new(self.x * f, self.y * f, self.z * f)
+    }
+}
+
+impl<T> Div<T> for Vec3<T>
+    where T: Div<T, Output=T> + Copy {
+    type Output = Vec3<T>;
+
+    #[inline(always)]
+    fn div(self, f: T) -> Vec3<T> {
+        Vec3::<T>::new(self.x / f, self.y / f, self.z / f)
+    }
+}
+
+// Dot product
+impl<T> Mul<Vec3<T>> for Vec3<T>
+    where T: Mul<T, Output=T> + Add<T, Output=T> + Sub<T, Output=T> + Copy {
+    type Output = T;
+
+    #[inline(always)]
+    fn mul(self, other: Vec3<T>) -> T {
+        self.dot(other)
+    }
+}
+
+// Linear interpolation, t outside [0, 1] extrapolates
+pub trait Lerp: Copy {
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    #[inline(always)]
+    fn lerp(self, other: f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vec2f {
+    #[inline(always)]
+    fn lerp(self, other: Vec2f, t: f32) -> Vec2f {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vec3f {
+    #[inline(always)]
+    fn lerp(self, other: Vec3f, t: f32) -> Vec3f {
+        self + (other - self) * t
+    }
+}
+
+#[inline(always)]
+pub fn lerp<T: Lerp>(a: T, b: T, t: f32) -> T {
+    a.lerp(b, t)
+}
+
+// Blends three attributes by barycentric weights summing to 1
+#[inline(always)]
+pub fn barycentric_mix<T: Lerp>(a: T, b: T, c: T, weights: Vec3f) -> T {
+    let ab = weights.x + weights.y;
+    if ab == 0.0 {
+        return c;
+    }
+    a.lerp(b, weights.y / ab).lerp(c, weights.z)
+}
+
+pub fn clamp01(x: f32) -> f32 {
+    x.clamp(0.0, 1.0)
+}
+
+// Hermite step from 0 at edge0 to 1 at edge1, clamped outside
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = clamp01((x - edge0) / (edge1 - edge0));
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Row-major 3x3 matrix, m[row][col], applied to column vectors like Mat4
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Matrix3<T> {
+    pub m: [[T; 3]; 3]
+}
+
+pub type Mat3 = Matrix3<f32>;
+pub type Mat3d = Matrix3<f64>;
+
+impl<T: Float> Matrix3<T> {
+    pub fn new(m: [[T; 3]; 3]) -> Matrix3<T> {
+        Matrix3 { m: m }
+    }
+
+    pub fn identity() -> Matrix3<T> {
+        let (o, l) = (T::zero(), T::one());
+        Matrix3::new([
+            [l, o, o],
+            [o, l, o],
+            [o, o, l]
+        ])
+    }
+
+    pub fn transpose(&self) -> Matrix3<T> {
+        let mut r = self.m;
+        for (i, row) in r.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.m[j][i];
+            }
+        }
+        Matrix3::new(r)
+    }
+
+    pub fn determinant(&self) -> T {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    // Adjugate over determinant, None for singular matrices
+    pub fn inverse(&self) -> Option<Matrix3<T>> {
+        let det = self.determinant();
+        if det.abs() < T::from_f64(1e-12) {
+            return None;
+        }
+
+        let m = &self.m;
+        let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+        let adj = [
+            [cofactor(1, 2, 1, 2), -cofactor(0, 2, 1, 2), cofactor(0, 1, 1, 2)],
+            [-cofactor(1, 2, 0, 2), cofactor(0, 2, 0, 2), -cofactor(0, 1, 0, 2)],
+            [cofactor(1, 2, 0, 1), -cofactor(0, 2, 0, 1), cofactor(0, 1, 0, 1)]
+        ];
+
+        let mut r = [[T::zero(); 3]; 3];
+        for i in 0..3 {
+            for j in 0..3 {
+                r[i][j] = adj[i][j] / det;
+            }
+        }
+        Some(Matrix3::new(r))
+    }
+}
+
+impl From<Mat3> for Mat3d {
+    fn from(a: Mat3) -> Mat3d {
+        Mat3d::new(a.m.map(|row| row.map(|v| v as f64)))
+    }
+}
+
+impl From<Mat3d> for Mat3 {
+    fn from(a: Mat3d) -> Mat3 {
+        Mat3::new(a.m.map(|row| row.map(|v| v as f32)))
+    }
+}
+
+impl<T: Float> Mul<Matrix3<T>> for Matrix3<T> {
+    type Output = Matrix3<T>;
+
+    fn mul(self, other: Matrix3<T>) -> Matrix3<T> {
+        let mut r = [[T::zero(); 3]; 3];
+        for (i, row) in r.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.m[i][0] * other.m[0][j] + self.m[i][1] * other.m[1][j] + self.m[i][2] * other.m[2][j];
+            }
+        }
+        Matrix3::new(r)
+    }
+}
+
+impl<T: Float> Mul<Vec3<T>> for Matrix3<T> {
+    type Output = Vec3<T>;
+
+    #[inline(always)]
+    fn mul(self, v: Vec3<T>) -> Vec3<T> {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z
+        )
+    }
+}
+
+// Row-major 4x4 matrix, m[row][col], applied to column vectors: M * p.
+// A * B transforms by B first, then by A
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Matrix4<T> {
+    pub m: [[T; 4]; 4]
+}
+
+pub type Mat4 = Matrix4<f32>;
+pub type Mat4d = Matrix4<f64>;
+
+impl<T: Float> Matrix4<T> {
+    pub fn new(m: [[T; 4]; 4]) -> Matrix4<T> {
+        Matrix4 { m: m }
+    }
+
+    pub fn identity() -> Matrix4<T> {
+        let (o, l) = (T::zero(), T::one());
+        Matrix4::new([
+            [l, o, o, o],
+            [o, l, o, o],
+            [o, o, l, o],
+            [o, o, o, l]
+        ])
+    }
+
+    pub fn translation(t: Vec3<T>) -> Matrix4<T> {
+        let mut r = Matrix4::identity();
+        r.m[0][3] = t.x;
+        r.m[1][3] = t.y;
+        r.m[2][3] = t.z;
+        r
+    }
+
+    pub fn scale(s: Vec3<T>) -> Matrix4<T> {
+        let mut r = Matrix4::identity();
+        r.m[0][0] = s.x;
+        r.m[1][1] = s.y;
+        r.m[2][2] = s.z;
+        r
+    }
+
+    // Rotations are counter-clockwise in radians when looking down the axis towards the origin
+    pub fn rotation_x(angle: T) -> Matrix4<T> {
+        let (s, c) = angle.sin_cos();
+        let mut r = Matrix4::identity();
+        r.m[1][1] = c; r.m[1][2] = -s;
+        r.m[2][1] = s; r.m[2][2] = c;
+        r
+    }
+
+    pub fn rotation_y(angle: T) -> Matrix4<T> {
+        let (s, c) = angle.sin_cos();
+        let mut r = Matrix4::identity();
+        r.m[0][0] = c; r.m[0][2] = s;
+        r.m[2][0] = -s; r.m[2][2] = c;
+        r
+    }
+
+    pub fn rotation_z(angle: T) -> Matrix4<T> {
+        let (s, c) = angle.sin_cos();
+        let mut r = Matrix4::identity();
+        r.m[0][0] = c; r.m[0][1] = -s;
+        r.m[1][0] = s; r.m[1][1] = c;
+        r
+    }
+
+    // Right-handed perspective with the camera looking down -z. Unlike OpenGL,
+    // the near plane maps to z = 1 and the far plane to z = -1 so larger z stays
+    // closer to the viewer, as the z-buffer expects
+    pub fn perspective(fov_y: T, aspect: T, near: T, far: T) -> Matrix4<T> {
+        let (o, l, two) = (T::zero(), T::one(), T::from_f64(2.0));
+        let f = l / (fov_y * T::from_f64(0.5)).tan();
+        Matrix4::new([
+            [f / aspect, o, o, o],
+            [o, f, o, o],
+            [o, o, (far + near) / (far - near), two * far * near / (far - near)],
+            [o, o, -l, o]
+        ])
+    }
+
+    // Parallel projection of the box [left, right] x [bottom, top] between
+    // view space z = -near and z = -far, with near at z = 1 and far at z = -1
+    // like perspective
+    pub fn orthographic(left: T, right: T, bottom: T, top: T, near: T, far: T) -> Matrix4<T> {
+        let (o, l, two) = (T::zero(), T::one(), T::from_f64(2.0));
+        Matrix4::new([
+            [two / (right - left), o, o, -(right + left) / (right - left)],
+            [o, two / (top - bottom), o, -(top + bottom) / (top - bottom)],
+            [o, o, two / (far - near), (far + near) / (far - near)],
+            [o, o, o, l]
+        ])
+    }
+
+    // Projection from the tinyrenderer lessons, camera on the z axis at distance c
+    // from the origin: points are divided by 1 - z / c
+    pub fn simple_projection(c: T) -> Matrix4<T> {
+        let mut r = Matrix4::identity();
+        r.m[3][2] = -T::one() / c;
+        r
+    }
+
+    // View transform for a camera at eye looking at center. When up is parallel
+    // to the view direction another axis is used instead of producing NaNs
+    pub fn lookat(eye: Vec3<T>, center: Vec3<T>, up: Vec3<T>) -> Matrix4<T> {
+        let (o, l) = (T::zero(), T::one());
+        let mut z = eye - center;
+        z.normalize();
+
+        let mut x = up ^ z;
+        if x * x < T::from_f64(1e-12) {
+            let fallback = if z.y.abs() < T::from_f64(0.9) { Vec3::new(o, l, o) } else { Vec3::new(l, o, o) };
+            x = fallback ^ z;
+        }
+        x.normalize();
+        let y = z ^ x;
+
+        Matrix4::new([
+            [x.x, x.y, x.z, -(x * eye)],
+            [y.x, y.y, y.z, -(y * eye)],
+            [z.x, z.y, z.z, -(z * eye)],
+            [o, o, o, l]
+        ])
+    }
+
+    // Maps [-1, 1] NDC to the w x h rectangle at (x, y) and z to [0, depth]
+    pub fn viewport(x: T, y: T, w: T, h: T, depth: T) -> Matrix4<T> {
+        let (o, l, two) = (T::zero(), T::one(), T::from_f64(2.0));
+        Matrix4::new([
+            [w / two, o, o, x + w / two],
+            [o, h / two, o, y + h / two],
+            [o, o, depth / two, depth / two],
+            [o, o, o, l]
+        ])
+    }
+
+    pub fn transpose(&self) -> Matrix4<T> {
+        let mut r = Matrix4::identity();
+        for i in 0..4 {
+            for j in 0..4 {
+                r.m[i][j] = self.m[j][i];
+            }
+        }
+        r
+    }
+
+    // Gauss-Jordan elimination with partial pivoting, None for singular matrices
+    pub fn inverse(&self) -> Option<Matrix4<T>> {
+        let mut a = self.m;
+        let mut inv = Matrix4::<T>::identity().m;
+
+        for col in 0..4 {
+            let pivot = (col..4).fold(col, |best, row| if a[row][col].abs() > a[best][col].abs() { row } else { best });
+            if a[pivot][col].abs() < T::from_f64(1e-12) {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let d = a[col][col];
+            for j in 0..4 {
+                a[col][j] /= d;
+                inv[col][j] /= d;
+            }
+
+            for row in 0..4 {
+                if row == col { continue; }
+                let f = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= f * a[col][j];
+                    inv[row][j] -= f * inv[col][j];
+                }
+            }
+        }
+
+        Some(Matrix4::new(inv))
+    }
+
+    // Upper left 3x3, the linear part without translation
+    pub fn linear(&self) -> Matrix3<T> {
+        let m = &self.m;
+        Matrix3::new([
+            [m[0][0], m[0][1], m[0][2]],
+            [m[1][0], m[1][1], m[1][2]],
+            [m[2][0], m[2][1], m[2][2]]
+        ])
+    }
+
+    // Inverse transpose of the linear part, keeps transformed normals perpendicular
+    // to transformed surfaces under non-uniform scale. Results need renormalizing.
+    // Singular transforms fall back to the linear part itself
+    pub fn normal_matrix(&self) -> Matrix3<T> {
+        let linear = self.linear();
+        match linear.inverse() {
+            Some(inv) => inv.transpose(),
+            None => linear
+        }
+    }
+
+    // Applies the matrix to a direction (w = 0), translation is ignored
+    pub fn transform_direction(&self, v: Vec3<T>) -> Vec3<T> {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z
+        )
+    }
+}
+
+impl From<Mat4> for Mat4d {
+    fn from(a: Mat4) -> Mat4d {
+        Mat4d::new(a.m.map(|row| row.map(|v| v as f64)))
+    }
+}
+
+impl From<Mat4d> for Mat4 {
+    fn from(a: Mat4d) -> Mat4 {
+        Mat4::new(a.m.map(|row| row.map(|v| v as f32)))
+    }
+}
+
+impl<T: Float> Mul<Matrix4<T>> for Matrix4<T> {
+    type Output = Matrix4<T>;
+
+    fn mul(self, other: Matrix4<T>) -> Matrix4<T> {
+        let mut r = [[T::zero(); 4]; 4];
+        for (i, row) in r.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.m[i][0] * other.m[0][j] + self.m[i][1] * other.m[1][j]
+                    + self.m[i][2] * other.m[2][j] + self.m[i][3] * other.m[3][j];
+            }
+        }
+        Matrix4::new(r)
+    }
+}
+
+impl<T: Float> Mul<Vec4<T>> for Matrix4<T> {
+    type Output = Vec4<T>;
+
+    #[inline(always)]
+    fn mul(self, v: Vec4<T>) -> Vec4<T> {
+        let m = &self.m;
+        let row = |i: usize| m[i][0] * v.x + m[i][1] * v.y + m[i][2] * v.z + m[i][3] * v.w;
+        Vec4::new(row(0), row(1), row(2), row(3))
+    }
+}
+
+// Transforms a point (w = 1) and divides by the resulting w
+impl<T: Float> Mul<Vec3<T>> for Matrix4<T> {
+    type Output = Vec3<T>;
+
+    #[inline(always)]
+    fn mul(self, v: Vec3<T>) -> Vec3<T> {
+        (self * v.to_point()).to_vec3()
+    }
+}
+
+// Rotation quaternion, w is the scalar part
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32
+}
+
+impl Quat {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Quat {
+        Quat { x: x, y: y, z: z, w: w }
+    }
+
+    pub fn identity() -> Quat {
+        Quat::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    // Counter-clockwise rotation about axis, which doesn't have to be normalized
+    pub fn from_axis_angle(axis: Vec3f, radians: f32) -> Quat {
+        let mut axis = axis;
+        axis.normalize();
+        let (s, c) = (radians * 0.5).sin_cos();
+        Quat::new(axis.x * s, axis.y * s, axis.z * s, c)
+    }
+
+    pub fn normalize(&self) -> Quat {
+        let len = (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).sqrt();
+        Quat::new(self.x / len, self.y / len, self.z / len, self.w / len)
+    }
+
+    pub fn conjugate(&self) -> Quat {
+        Quat::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn dot(&self, other: &Quat) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn rotate_vec3(&self, v: Vec3f) -> Vec3f {
+        // v + 2w (q x v) + 2 q x (q x v) with q the vector part
+        let q = Vec3f::new(self.x, self.y, self.z);
+        let t = (q ^ v) * 2.0;
+        v + t * self.w + (q ^ t)
+    }
+
+    pub fn to_mat4(&self) -> Mat4 {
+        let (x, y, z, w) = (self.x, self.y, self.z, self.w);
+        Mat4::new([
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w), 0.0],
+            [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w), 0.0],
+            [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y), 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ])
+    }
+
+    // Spherical interpolation along the shortest arc, nearly parallel
+    // rotations fall back to a normalized linear blend
+    pub fn slerp(a: Quat, b: Quat, t: f32) -> Quat {
+        let mut b = b;
+        let mut d = a.dot(&b);
+        if d < 0.0 {
+            b = Quat::new(-b.x, -b.y, -b.z, -b.w);
+            d = -d;
+        }
+
+        let (wa, wb) = if d > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = d.acos();
+            let s = theta.sin();
+            (((1.0 - t) * theta).sin() / s, (t * theta).sin() / s)
+        };
+
+        Quat::new(a.x * wa + b.x * wb, a.y * wa + b.y * wb, a.z * wa + b.z * wb, a.w * wa + b.w * wb).normalize()
+    }
+}
+
+// Hamilton product, a * b rotates by b first, then by a
+impl Mul<Quat> for Quat {
+    type Output = Quat;
+
+    fn mul(self, o: Quat) -> Quat {
+        Quat::new(
+            self.w * o.x + self.x * o.w + self.y * o.z - self.z * o.y,
+            self.w * o.y - self.x * o.z + self.y * o.w + self.z * o.x,
+            self.w * o.z + self.x * o.y - self.y * o.x + self.z * o.w,
+            self.w * o.w - self.x * o.x - self.y * o.y - self.z * o.z
+        )
+    }
+}
+
+impl From<Quat> for Mat4 {
+    fn from(q: Quat) -> Mat4 {
+        q.to_mat4()
+    }
+}
@@ -0,0 +1,212 @@
+// glTF 2.0 loader, reads POSITION, NORMAL and TEXCOORD_0 of the first primitive
+// of the first mesh from a .gltf document with external or data URI buffers
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+use math::{Vec2f,Vec3f};
+use super::{Model,ModelError};
+use super::json::Json;
+
+const MODE_TRIANGLES: usize = 4;
+
+fn invalid(what: &str) -> ModelError {
+    ModelError::Invalid(what.to_string())
+}
+
+fn decode_base64(data: &str) -> Result<Vec<u8>, ModelError> {
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+
+    for c in data.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return Err(invalid("bad base64 data"))
+        };
+
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+
+    Ok(bytes)
+}
+
+fn load_buffer(buffer: &Json, dir: &Path) -> Result<Vec<u8>, ModelError> {
+    let uri = match buffer.get("uri").and_then(|u| u.as_str()) {
+        Some(uri) => uri,
+        None => return Err(ModelError::Unsupported("buffers without uri (binary .glb chunk)".to_string()))
+    };
+
+    if uri.starts_with("data:") {
+        return match uri.find(";base64,") {
+            Some(pos) => decode_base64(&uri[pos + 8..]),
+            None => Err(ModelError::Unsupported("non-base64 data uri".to_string()))
+        };
+    }
+
+    let mut data = Vec::new();
+    File::open(dir.join(uri))?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+fn component_size(component_type: usize) -> Result<usize, ModelError> {
+    match component_type {
+        5120 | 5121 => Ok(1),
+        5122 | 5123 => Ok(2),
+        5125 | 5126 => Ok(4),
+        _ => Err(ModelError::Unsupported(format!("accessor component type {}", component_type)))
+    }
+}
+
+fn read_component(data: &[u8], component_type: usize) -> f64 {
+    match component_type {
+        5120 => data[0] as i8 as f64,
+        5121 => data[0] as f64,
+        5122 => (data[0] as u16 | (data[1] as u16) << 8) as i16 as f64,
+        5123 => (data[0] as u16 | (data[1] as u16) << 8) as f64,
+        5125 => (data[0] as u32 | (data[1] as u32) << 8 | (data[2] as u32) << 16 | (data[3] as u32) << 24) as f64,
+        _ => f32::from_bits(data[0] as u32 | (data[1] as u32) << 8 | (data[2] as u32) << 16 | (data[3] as u32) << 24) as f64
+    }
+}
+
+// Returns accessor elements flattened, `components` values per element
+fn read_accessor(doc: &Json, buffers: &[Vec<u8>], idx: usize, components: usize) -> Result<Vec<f64>, ModelError> {
+    let accessor = doc.get("accessors").and_then(|a| a.at(idx)).ok_or(invalid("missing accessor"))?;
+
+    if accessor.get("sparse").is_some() {
+        return Err(ModelError::Unsupported("sparse accessors".to_string()));
+    }
+
+    let expected = match components { 1 => "SCALAR", 2 => "VEC2", 3 => "VEC3", _ => "VEC4" };
+    if accessor.get("type").and_then(|t| t.as_str()) != Some(expected) {
+        return Err(invalid(&format!("accessor {} is not {}", idx, expected)));
+    }
+
+    let count = accessor.get("count").and_then(|c| c.as_usize()).ok_or(invalid("accessor without count"))?;
+    let component_type = accessor.get("componentType").and_then(|c| c.as_usize()).ok_or(invalid("accessor without componentType"))?;
+    let size = component_size(component_type)?;
+    let offset = accessor.get("byteOffset").and_then(|o| o.as_usize()).unwrap_or(0);
+
+    let view_idx = accessor.get("bufferView").and_then(|v| v.as_usize()).ok_or(invalid("accessor without bufferView"))?;
+    let view = doc.get("bufferViews").and_then(|v| v.at(view_idx)).ok_or(invalid("missing bufferView"))?;
+    let buffer_idx = view.get("buffer").and_then(|b| b.as_usize()).ok_or(invalid("bufferView without buffer"))?;
+    let buffer = buffers.get(buffer_idx).ok_or(invalid("missing buffer"))?;
+    let view_offset = view.get("byteOffset").and_then(|o| o.as_usize()).unwrap_or(0);
+    let element_size = size * components;
+    let stride = match view.get("byteStride").and_then(|s| s.as_usize()) {
+        Some(stride) if !(4..=252).contains(&stride) => return Err(invalid(&format!("bufferView {} has byteStride {}", view_idx, stride))),
+        Some(stride) => stride,
+        None => element_size
+    };
+
+    // count comes straight from the document, so the whole accessor is
+    // checked against the buffer before anything is reserved for it
+    let past_end = || invalid(&format!("accessor {} reads past the end of buffer {}", idx, buffer_idx));
+    let start = view_offset.checked_add(offset).ok_or_else(&past_end)?;
+    if count > 0 {
+        let end = (count - 1).checked_mul(stride)
+            .and_then(|n| n.checked_add(start))
+            .and_then(|n| n.checked_add(element_size))
+            .ok_or_else(&past_end)?;
+        if end > buffer.len() {
+            return Err(past_end());
+        }
+    }
+
+    let mut values = Vec::with_capacity(count * components);
+    for i in 0..count {
+        let element = start + i * stride;
+        for c in 0..components {
+            let pos = element + c * size;
+            values.push(read_component(&buffer[pos..pos + size], component_type));
+        }
+    }
+
+    Ok(values)
+}
+
+// Parses a glTF JSON document, relative buffer paths are resolved against dir
+pub fn parse(text: &str, dir: &Path) -> Result<Model, ModelError> {
+    let doc = Json::parse(text).map_err(ModelError::Invalid)?;
+
+    let mut buffers = Vec::new();
+    if let Some(Json::Array(items)) = doc.get("buffers") {
+        for buffer in items.iter() {
+            buffers.push(load_buffer(buffer, dir)?);
+        }
+    }
+
+    let primitive = doc.get("meshes").and_then(|m| m.at(0))
+        .and_then(|m| m.get("primitives")).and_then(|p| p.at(0))
+        .ok_or(invalid("no mesh primitives"))?;
+
+    let mode = primitive.get("mode").and_then(|m| m.as_usize()).unwrap_or(MODE_TRIANGLES);
+    if mode != MODE_TRIANGLES {
+        return Err(ModelError::Unsupported(format!("primitive mode {}", mode)));
+    }
+
+    let attributes = primitive.get("attributes").ok_or(invalid("primitive without attributes"))?;
+    let attribute = |name: &str, components: usize| -> Result<Option<Vec<f64>>, ModelError> {
+        match attributes.get(name).and_then(|a| a.as_usize()) {
+            Some(idx) => read_accessor(&doc, &buffers, idx, components).map(Some),
+            None => Ok(None)
+        }
+    };
+
+    let positions = attribute("POSITION", 3)?.ok_or(invalid("primitive without POSITION"))?;
+    let normals = attribute("NORMAL", 3)?.unwrap_or(Vec::new());
+    let uvs = attribute("TEXCOORD_0", 2)?.unwrap_or(Vec::new());
+
+    let vertices: Vec<Vec3f> = positions.chunks(3).map(|p| Vec3f::new(p[0] as f32, p[1] as f32, p[2] as f32)).collect();
+    let normals: Vec<Vec3f> = normals.chunks(3).map(|n| Vec3f::new(n[0] as f32, n[1] as f32, n[2] as f32)).collect();
+    // glTF puts the UV origin at the top left, textures here are sampled bottom-up
+    let texture_coords: Vec<Vec2f> = uvs.chunks(2).map(|t| Vec2f::new(t[0] as f32, 1.0 - t[1] as f32)).collect();
+
+    let indices: Vec<usize> = match primitive.get("indices").and_then(|i| i.as_usize()) {
+        Some(idx) => read_accessor(&doc, &buffers, idx, 1)?.into_iter().map(|i| i as usize).collect(),
+        None => (0..vertices.len()).collect()
+    };
+
+    let has_uv = !texture_coords.is_empty();
+    let has_normals = !normals.is_empty();
+    let mut faces = Vec::with_capacity(indices.len() / 3);
+    for triangle in indices.chunks(3).filter(|t| t.len() == 3) {
+        let mut face = [-1i32; 9];
+        for (c, &i) in triangle.iter().enumerate() {
+            face[c * 3] = i as i32;
+            if has_uv { face[c * 3 + 1] = i as i32; }
+            if has_normals { face[c * 3 + 2] = i as i32; }
+        }
+        faces.push(face);
+    }
+
+    Model::new(vertices, texture_coords, normals, faces)
+}
+
+pub fn load(filename: &Path) -> Result<Model, ModelError> {
+    let mut data = Vec::new();
+    File::open(filename)?.read_to_end(&mut data)?;
+
+    if data.starts_with(b"glTF") {
+        return Err(ModelError::Unsupported("binary .glb containers".to_string()));
+    }
+
+    let text = String::from_utf8(data).map_err(|_| invalid("document is not utf-8"))?;
+    parse(&text, filename.parent().unwrap_or(Path::new("")))
+}
+
+impl Model {
+    pub fn new_from_gltf(filename: &Path) -> Result<Model, ModelError> {
+        load(filename)
+    }
+}
@@ -0,0 +1,227 @@
+// Minimal JSON reader, just enough for glTF documents
+
+use std::char;
+use std::str;
+
+pub enum Json {
+    Null,
+    // glTF has no booleans the loader reads, so the value isn't kept
+    Bool,
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>)
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser { data: text.as_bytes(), pos: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.data.len() {
+            return Err(format!("unexpected data at offset {}", parser.pos));
+        }
+        Ok(value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref fields) => fields.iter().find(|f| f.0 == key).map(|f| &f.1),
+            _ => None
+        }
+    }
+
+    pub fn at(&self, idx: usize) -> Option<&Json> {
+        match *self {
+            Json::Array(ref items) => items.get(idx),
+            _ => None
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match *self {
+            Json::Number(v) if v >= 0.0 && v.fract() == 0.0 => Some(v as usize),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref s) => Some(s),
+            _ => None
+        }
+    }
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, what: &str) -> Result<T, String> {
+        Err(format!("{} at offset {}", what, self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.data.len() {
+            match self.data[self.pos] {
+                b' ' | b'\t' | b'\n' | b'\r' => self.pos += 1,
+                _ => break
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.data.get(self.pos).cloned()
+    }
+
+    fn expect(&mut self, literal: &str, value: Json) -> Result<Json, String> {
+        if self.data[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(value)
+        } else {
+            self.error("unexpected token")
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b't') => self.expect("true", Json::Bool),
+            Some(b'f') => self.expect("false", Json::Bool),
+            Some(b'n') => self.expect("null", Json::Null),
+            Some(_) => self.number(),
+            None => self.error("unexpected end of input")
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        let mut fields = Vec::new();
+        self.pos += 1;
+
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(fields));
+        }
+
+        loop {
+            if self.peek() != Some(b'"') {
+                return self.error("expected object key");
+            }
+            let key = self.string()?;
+
+            if self.peek() != Some(b':') {
+                return self.error("expected ':'");
+            }
+            self.pos += 1;
+            fields.push((key, self.value()?));
+
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => { self.pos += 1; return Ok(Json::Object(fields)); },
+                _ => return self.error("expected ',' or '}'")
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        let mut items = Vec::new();
+        self.pos += 1;
+
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => { self.pos += 1; return Ok(Json::Array(items)); },
+                _ => return self.error("expected ',' or ']'")
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = match self.data.get(self.pos..self.pos + 4) {
+            Some(d) => d,
+            None => return self.error("truncated escape")
+        };
+        let code = str::from_utf8(digits).ok().and_then(|d| u32::from_str_radix(d, 16).ok());
+        match code {
+            Some(code) => { self.pos += 4; Ok(code) },
+            None => self.error("invalid escape")
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let mut bytes = Vec::new();
+        self.pos += 1;
+
+        loop {
+            let b = match self.data.get(self.pos) {
+                Some(&b) => b,
+                None => return self.error("unterminated string")
+            };
+            self.pos += 1;
+
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    let e = match self.data.get(self.pos) {
+                        Some(&e) => e,
+                        None => return self.error("unterminated string")
+                    };
+                    self.pos += 1;
+
+                    let c = match e {
+                        b'"' => '"', b'\\' => '\\', b'/' => '/',
+                        b'b' => '\u{8}', b'f' => '\u{c}', b'n' => '\n', b'r' => '\r', b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            // Surrogate pair
+                            if (0xD800..0xDC00).contains(&code) && self.data[self.pos..].starts_with(b"\\u") {
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+                            char::from_u32(code).unwrap_or('\u{FFFD}')
+                        },
+                        _ => return self.error("invalid escape")
+                    };
+
+                    let mut buf = [0u8; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                },
+                _ => bytes.push(b)
+            }
+        }
+
+        match String::from_utf8(bytes) {
+            Ok(s) => Ok(s),
+            Err(_) => self.error("invalid utf-8 in string")
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while self.pos < self.data.len() {
+            match self.data[self.pos] {
+                b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E' => self.pos += 1,
+                _ => break
+            }
+        }
+
+        let text = str::from_utf8(&self.data[start..self.pos]).unwrap_or("");
+        match text.parse::<f64>() {
+            Ok(v) => Ok(Json::Number(v)),
+            Err(_) => { self.pos = start; self.error("invalid value") }
+        }
+    }
+}
@@ -0,0 +1,677 @@
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::{BufReader,BufWriter};
+use std::path::Path;
+use std::str::FromStr;
+use math::{Vec2f,Vec3f};
+use material::{Material,load_library};
+use tga::RgbaColor;
+use texture::TextureId;
+
+pub mod gltf;
+pub mod ply;
+mod json;
+
+// Faces declared under an `o` or `g` statement, faces preceding any such
+// statement belong to DEFAULT_GROUP
+pub struct Group {
+    pub name: String,
+    pub faces: Vec<usize>
+}
+
+pub const DEFAULT_GROUP: &str = "default";
+
+// Unique combination of position, texture coord and normal
+#[derive(Clone,Copy)]
+pub struct ModelVertex {
+    pub position: Vec3f,
+    pub texture_coord: Option<Vec2f>,
+    pub normal: Option<Vec3f>,
+    pub color: Option<RgbaColor>
+}
+
+// Single vertex buffer plus three indices per triangle
+pub struct IndexedMesh {
+    pub vertices: Vec<ModelVertex>,
+    pub indices: Vec<u32>
+}
+
+// Triangle soup the renderer can draw, faces are indexed from 0. Shared
+// between rasterizer threads
+pub trait Mesh: Sync {
+    fn face_count(&self) -> usize;
+    fn corner(&self, face: usize, corner: usize) -> ModelVertex;
+    fn face_material(&self, face: usize) -> Option<&Material>;
+    // Texture among the renderer's or the scene's drawing it
+    fn face_texture(&self, face: usize) -> Option<TextureId>;
+}
+
+pub struct Model {
+    pub vertices: Vec<Vec3f>,
+    pub normals: Vec<Vec3f>,
+    pub texture_coords: Vec<Vec2f>,
+    // Parallel to vertices when any `v x y z r g b` line was read, empty otherwise
+    pub vertex_colors: Vec<RgbaColor>,
+    // Zero-based indices per corner: [v0, vt0, vn0, v1, vt1, vn1, v2, vt2, vn2],
+    // -1 marks a missing texture coord or normal
+    pub faces: Vec<[i32;9]>,
+    pub materials: Vec<Material>,
+    // Index into materials per face, -1 when no material is in use
+    pub face_materials: Vec<i32>,
+    // Per face index of a texture added to the renderer, or to the scene
+    // with draw_scene, -1 for none. Used by faces without a material
+    pub face_textures: Vec<i32>,
+    pub groups: Vec<Group>,
+    // Parallel to normals, filled by compute_tangents. Bitangent is
+    // cross(normal, tangent) * bitangent_sign
+    pub tangents: Vec<Vec3f>,
+    pub bitangent_signs: Vec<f32>,
+    // Number of polygons with more than three corners split into triangles
+    pub triangulated_faces: usize
+}
+
+#[derive(Debug)]
+pub enum ModelError {
+    Io(io::Error),
+    // One-based line number and the offending line
+    Parse { line: usize, text: String },
+    IndexOutOfRange { face: usize, kind: &'static str, index: i32, count: usize },
+    // Structurally broken content in formats that aren't line based
+    Invalid(String),
+    Unsupported(String),
+    // Operation needs an attribute the model doesn't have
+    MissingAttribute(&'static str)
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ModelError::Io(ref e) => write!(f, "{}", e),
+            ModelError::Parse { line, ref text } => write!(f, "can't parse line {}: {:?}", line, text),
+            ModelError::IndexOutOfRange { face, kind, index, count } =>
+                write!(f, "face {} references {} {} but the file only has {}", face, kind, index, count),
+            ModelError::Invalid(ref what) => write!(f, "invalid model: {}", what),
+            ModelError::Unsupported(ref what) => write!(f, "unsupported model: {}", what),
+            ModelError::MissingAttribute(what) => write!(f, "model has no {}", what)
+        }
+    }
+}
+
+impl Error for ModelError {}
+
+impl From<io::Error> for ModelError {
+    fn from(e: io::Error) -> ModelError {
+        ModelError::Io(e)
+    }
+}
+
+// Extra values beyond arr's length are ignored, missing ones keep their current value.
+// Returns the number of values read
+fn extract<T: FromStr>(str: &str, arr: &mut [T]) -> Result<usize, ()> {
+    let mut count = 0;
+    for word in str.split_whitespace() {
+        if count >= arr.len() { break; }
+        arr[count] = FromStr::from_str(word).map_err(|_| ())?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+const ELEMENT_NAMES: [&str; 3] = ["vertex", "texture coord", "normal"];
+
+// Resolves a one-based OBJ index, negative indices count back from the
+// last element of the given kind defined so far. None when a negative index
+// reaches back past the first one
+fn resolve_index(index: i32, count: usize) -> Option<i32> {
+    let resolved = if index < 0 { count as i32 + index } else { index - 1 };
+    if resolved < 0 {
+        return None;
+    }
+    Some(resolved)
+}
+
+// Err(None) for malformed corners, the caller knows the line
+fn extract_corners(str: &str, counts: [usize; 3], face: usize, corners: &mut Vec<[i32;3]>) -> Result<(), Option<ModelError>> {
+    corners.clear();
+    for group in str.split_whitespace() {
+        // Missing texture or normal indices (v, v/vt, v//vn) are stored as -1
+        let mut corner = [-1i32; 3];
+        for (i, index) in group.split('/').enumerate() {
+            if i >= corner.len() { break; }
+            if index.is_empty() { continue; }
+
+            let v:i32 = FromStr::from_str(index).map_err(|_| None)?;
+            if v == 0 {
+                return Err(None);
+            }
+            corner[i] = resolve_index(v, counts[i]).ok_or(ModelError::IndexOutOfRange {
+                face: face,
+                kind: ELEMENT_NAMES[i],
+                index: v,
+                count: counts[i]
+            })?;
+        }
+        corners.push(corner);
+    }
+    Ok(())
+}
+
+// Finds a group by name, adding an empty one if it doesn't exist yet
+fn group_index(groups: &mut Vec<Group>, name: &str) -> usize {
+    match groups.iter().position(|g| g.name == name) {
+        Some(idx) => idx,
+        None => {
+            groups.push(Group { name: name.to_string(), faces: Vec::new() });
+            groups.len() - 1
+        }
+    }
+}
+
+impl Model {
+    // Builds a single-group model without materials from already resolved triangles,
+    // generating normals when none are given
+    pub fn new(vertices: Vec<Vec3f>, texture_coords: Vec<Vec2f>, normals: Vec<Vec3f>, faces: Vec<[i32;9]>) -> Result<Model, ModelError> {
+        let face_count = faces.len();
+        let mut model = Model {
+            vertices: vertices,
+            normals: normals,
+            texture_coords: texture_coords,
+            vertex_colors: Vec::new(),
+            faces: faces,
+            materials: Vec::new(),
+            face_materials: vec![-1; face_count],
+            face_textures: vec![-1; face_count],
+            groups: vec![Group { name: DEFAULT_GROUP.to_string(), faces: (0..face_count).collect() }],
+            tangents: Vec::new(),
+            bitangent_signs: Vec::new(),
+            triangulated_faces: 0
+        };
+        model.validate()?;
+
+        if model.normals.is_empty() && !model.faces.is_empty() {
+            model.compute_normals();
+        }
+
+        return Ok(model);
+    }
+
+    pub fn new_from_file(filename: &Path) -> Result<Model, ModelError> {
+        let file = File::open(filename)?;
+
+        // Rough guess of ~40 bytes per line, a quarter of them per vertex attribute
+        // and about half of them faces for typical triangle meshes
+        let lines_estimate = (file.metadata()?.len() / 40) as usize;
+
+        let mut vertices:Vec<Vec3f> = Vec::with_capacity(lines_estimate / 4);
+        let mut normals:Vec<Vec3f> = Vec::with_capacity(lines_estimate / 4);
+        let mut faces:Vec<[i32;9]> = Vec::with_capacity(lines_estimate / 2);
+        let mut texture_coords:Vec<Vec2f> = Vec::with_capacity(lines_estimate / 4);
+        let mut vertex_colors:Vec<RgbaColor> = Vec::new();
+        let mut triangulated_faces = 0;
+        let mut materials: Vec<Material> = Vec::new();
+        let mut face_materials: Vec<i32> = Vec::with_capacity(lines_estimate / 2);
+        let mut current_material: i32 = -1;
+        let mut groups: Vec<Group> = Vec::new();
+        let mut current_group: Option<usize> = None;
+        let dir = filename.parent().unwrap_or(Path::new(""));
+
+        let mut reader = BufReader::with_capacity(1 << 16, file);
+        let mut buffer = String::with_capacity(256);
+        let mut corners: Vec<[i32;3]> = Vec::with_capacity(8);
+        let mut line_number = 0;
+
+        loop {
+            buffer.clear();
+            if reader.read_line(&mut buffer)? == 0 {
+                break;
+            }
+            line_number += 1;
+
+            let line = buffer.trim_end_matches(['\n', '\r']);
+            let parse_error = || ModelError::Parse { line: line_number, text: line.to_string() };
+            let mut coords = [0f32; 3];
+
+            if let Some(rest) = line.strip_prefix("v ") {
+                let mut values = [0f32; 6];
+                let count = extract::<f32>(rest, &mut values).map_err(|_| parse_error())?;
+
+                // Vertices without color are white once any vertex has one
+                if count == 6 && vertex_colors.is_empty() {
+                    vertex_colors.resize(vertices.len(), RgbaColor::new(1.0, 1.0, 1.0, 1.0));
+                }
+                if count == 6 {
+                    vertex_colors.push(RgbaColor::new(values[3], values[4], values[5], 1.0));
+                } else if !vertex_colors.is_empty() {
+                    vertex_colors.push(RgbaColor::new(1.0, 1.0, 1.0, 1.0));
+                }
+
+                vertices.push(Vec3f::new(values[0], values[1], values[2]));
+
+            } else if let Some(rest) = line.strip_prefix("vt ") {
+                extract::<f32>(rest, &mut coords).map_err(|_| parse_error())?;
+                texture_coords.push(Vec2f::new(coords[0], coords[1]));
+
+            } else if let Some(rest) = line.strip_prefix("vn ") {
+                extract::<f32>(rest, &mut coords).map_err(|_| parse_error())?;
+                normals.push(Vec3f::new(coords[0], coords[1], coords[2]));
+
+            } else if let Some(rest) = line.strip_prefix("f ") {
+                let counts = [vertices.len(), texture_coords.len(), normals.len()];
+                extract_corners(rest, counts, faces.len(), &mut corners).map_err(|e| e.unwrap_or_else(parse_error))?;
+                if corners.len() > 3 { triangulated_faces += 1; }
+
+                let group = match current_group {
+                    Some(idx) => idx,
+                    None => {
+                        let idx = group_index(&mut groups, DEFAULT_GROUP);
+                        current_group = Some(idx);
+                        idx
+                    }
+                };
+
+                // Fan triangulation: (c0, c1, c2), (c0, c2, c3), ...
+                for i in 1..corners.len().saturating_sub(1) {
+                    let mut indices = [0i32; 9];
+                    for (c, corner) in [corners[0], corners[i], corners[i + 1]].iter().enumerate() {
+                        indices[c * 3..c * 3 + 3].copy_from_slice(corner);
+                    }
+                    groups[group].faces.push(faces.len());
+                    faces.push(indices);
+                    face_materials.push(current_material);
+                }
+
+            } else if line.starts_with("o ") || line.starts_with("g ") {
+                let name = line[2..].trim();
+                let name = if name.is_empty() { DEFAULT_GROUP } else { name };
+                current_group = Some(group_index(&mut groups, name));
+
+            } else if let Some(rest) = line.strip_prefix("mtllib ") {
+                for name in rest.split_whitespace() {
+                    materials.extend(load_library(&dir.join(name)));
+                }
+
+            } else if let Some(rest) = line.strip_prefix("usemtl ") {
+                let name = rest.trim();
+                current_material = match materials.iter().position(|m| m.name == name) {
+                    Some(idx) => idx as i32,
+                    None => -1
+                };
+            }
+        }
+
+        let mut model = Model {
+            vertices: vertices,
+            normals: normals,
+            faces: faces,
+            texture_coords: texture_coords,
+            vertex_colors: vertex_colors,
+            materials: materials,
+            face_textures: vec![-1; face_materials.len()],
+            face_materials: face_materials,
+            groups: groups,
+            tangents: Vec::new(),
+            bitangent_signs: Vec::new(),
+            triangulated_faces: triangulated_faces
+        };
+        model.validate()?;
+
+        if model.normals.is_empty() && !model.faces.is_empty() {
+            model.compute_normals();
+        }
+
+        return Ok(model);
+    }
+
+    // Writes vertices, texture coords, normals and faces grouped by `g` statements.
+    // Materials aren't written. Floats are printed in shortest round-trip form
+    pub fn write_to_file(&self, filename: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(filename)?);
+
+        for (i, v) in self.vertices.iter().enumerate() {
+            match self.vertex_colors.get(i) {
+                Some(c) => writeln!(out, "v {} {} {} {} {} {}", v.x, v.y, v.z, c.r, c.g, c.b)?,
+                None => writeln!(out, "v {} {} {}", v.x, v.y, v.z)?
+            }
+        }
+        for t in self.texture_coords.iter() {
+            writeln!(out, "vt {} {}", t.x, t.y)?;
+        }
+        for n in self.normals.iter() {
+            writeln!(out, "vn {} {} {}", n.x, n.y, n.z)?;
+        }
+
+        let named = self.groups.len() > 1 || self.groups.iter().any(|g| g.name != DEFAULT_GROUP);
+        for group in self.groups.iter() {
+            if named {
+                writeln!(out, "g {}", group.name)?;
+            }
+
+            for &face in group.faces.iter() {
+                write!(out, "f")?;
+                for corner in self.faces[face].chunks(3) {
+                    match (corner[1], corner[2]) {
+                        (-1, -1) => write!(out, " {}", corner[0] + 1)?,
+                        (t, -1) => write!(out, " {}/{}", corner[0] + 1, t + 1)?,
+                        (-1, n) => write!(out, " {}//{}", corner[0] + 1, n + 1)?,
+                        (t, n) => write!(out, " {}/{}/{}", corner[0] + 1, t + 1, n + 1)?
+                    }
+                }
+                writeln!(out)?;
+            }
+        }
+
+        out.flush()
+    }
+
+    // Catches forward references past the end of the file so draw_model never indexes out of range
+    fn validate(&self) -> Result<(), ModelError> {
+        let counts = [self.vertices.len(), self.texture_coords.len(), self.normals.len()];
+
+        for (f, face) in self.faces.iter().enumerate() {
+            for (i, &index) in face.iter().enumerate() {
+                let kind = i % 3;
+                let optional = kind != 0;
+                if (index == -1 && optional) || (index >= 0 && (index as usize) < counts[kind]) {
+                    continue;
+                }
+
+                return Err(ModelError::IndexOutOfRange {
+                    face: f,
+                    kind: ELEMENT_NAMES[kind],
+                    index: index + 1,
+                    count: counts[kind]
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Replaces normals with smooth per-vertex normals, the average of adjacent face
+    // normals weighted by face area. Faces are pointed at the new normals by vertex index
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vec3f::new(0.0, 0.0, 0.0); self.vertices.len()];
+
+        for face in self.faces.iter() {
+            let v0 = self.vertices[face[0] as usize];
+            let v1 = self.vertices[face[3] as usize];
+            let v2 = self.vertices[face[6] as usize];
+
+            // Cross product length is twice the area, so summing it weights by area
+            let n = (v1 - v0) ^ (v2 - v0);
+            if n * n == 0.0 {
+                continue;
+            }
+
+            for corner in 0..3 {
+                let idx = face[corner * 3] as usize;
+                normals[idx] += n;
+            }
+        }
+
+        for n in normals.iter_mut() {
+            if *n * *n > 0.0 { n.normalize(); }
+        }
+
+        for face in self.faces.iter_mut() {
+            for corner in 0..3 {
+                face[corner * 3 + 2] = face[corner * 3];
+            }
+        }
+
+        self.normals = normals;
+    }
+
+    // Computes tangent space per normal from positions and texture coords, tangents
+    // point along +U and are orthonormalized against their normal. Faces lacking
+    // texture coords or normals, or with degenerate UV mapping, don't contribute
+    pub fn compute_tangents(&mut self) -> Result<(), ModelError> {
+        if self.texture_coords.is_empty() {
+            return Err(ModelError::MissingAttribute("texture coords"));
+        }
+        if self.normals.is_empty() {
+            return Err(ModelError::MissingAttribute("normals"));
+        }
+
+        let zero = Vec3f::new(0.0, 0.0, 0.0);
+        let mut tangents = vec![zero; self.normals.len()];
+        let mut bitangents = vec![zero; self.normals.len()];
+
+        for face in 0..self.faces.len() {
+            let mut uv = [Vec2f::new(0.0, 0.0); 3];
+            let mut complete = true;
+            for (corner, coord) in uv.iter_mut().enumerate() {
+                match (self.texture_coord(face, corner), self.normal(face, corner)) {
+                    (Some(t), Some(_)) => *coord = t,
+                    _ => complete = false
+                }
+            }
+            if !complete { continue; }
+
+            let e1 = self.vertex(face, 1) - self.vertex(face, 0);
+            let e2 = self.vertex(face, 2) - self.vertex(face, 0);
+            let d1 = uv[1] - uv[0];
+            let d2 = uv[2] - uv[0];
+
+            let det = d1.x * d2.y - d2.x * d1.y;
+            if det.abs() < 1e-12 { continue; }
+
+            let r = 1.0 / det;
+            let t = (e1 * d2.y - e2 * d1.y) * r;
+            let b = (e2 * d1.x - e1 * d2.x) * r;
+
+            for corner in 0..3 {
+                let idx = self.faces[face][corner * 3 + 2] as usize;
+                tangents[idx] += t;
+                bitangents[idx] += b;
+            }
+        }
+
+        let mut signs = vec![1.0; self.normals.len()];
+        for (i, n) in self.normals.iter().enumerate() {
+            // Gram-Schmidt
+            let mut t = tangents[i] - *n * (*n * tangents[i]);
+            if t * t > 0.0 {
+                t.normalize();
+            }
+            tangents[i] = t;
+
+            if (*n ^ t) * bitangents[i] < 0.0 {
+                signs[i] = -1.0;
+            }
+        }
+
+        self.tangents = tangents;
+        self.bitangent_signs = signs;
+        Ok(())
+    }
+
+    pub fn vertex(&self, face: usize, corner: usize) -> Vec3f {
+        self.vertices[self.faces[face][corner * 3] as usize]
+    }
+
+    pub fn vertex_color(&self, face: usize, corner: usize) -> Option<RgbaColor> {
+        self.vertex_colors.get(self.faces[face][corner * 3] as usize).cloned()
+    }
+
+    pub fn texture_coord(&self, face: usize, corner: usize) -> Option<Vec2f> {
+        match self.faces[face][corner * 3 + 1] {
+            -1 => None,
+            idx => self.texture_coords.get(idx as usize).cloned()
+        }
+    }
+
+    pub fn normal(&self, face: usize, corner: usize) -> Option<Vec3f> {
+        match self.faces[face][corner * 3 + 2] {
+            -1 => None,
+            idx => self.normals.get(idx as usize).cloned()
+        }
+    }
+
+    pub fn material(&self, face: usize) -> Option<&Material> {
+        match self.face_materials[face] {
+            -1 => None,
+            idx => self.materials.get(idx as usize)
+        }
+    }
+
+    pub fn texture(&self, face: usize) -> Option<TextureId> {
+        match self.face_textures.get(face) {
+            Some(&idx) if idx >= 0 => Some(TextureId(idx as usize)),
+            _ => None
+        }
+    }
+
+    // Draws faces with texture, or without any when None
+    pub fn set_texture(&mut self, faces: &[usize], texture: Option<TextureId>) {
+        self.face_textures.resize(self.faces.len(), -1);
+        for &face in faces {
+            self.face_textures[face] = texture.map_or(-1, |t| t.0 as i32);
+        }
+    }
+
+    // set_texture for the faces of group, false when there's no such group
+    pub fn set_group_texture(&mut self, name: &str, texture: Option<TextureId>) -> bool {
+        let faces = match self.faces_for_group(name) {
+            Some(faces) => faces.to_vec(),
+            None => return false
+        };
+        self.set_texture(&faces, texture);
+        true
+    }
+
+    pub fn group_names(&self) -> Vec<&str> {
+        self.groups.iter().map(|g| g.name.as_str()).collect()
+    }
+
+    pub fn faces_for_group(&self, name: &str) -> Option<&[usize]> {
+        self.groups.iter().find(|g| g.name == name).map(|g| &g.faces[..])
+    }
+
+    // Deduplicates face corners sharing the same position, texture coord and
+    // normal indices. indices.len() is the vertex count of a naive expansion
+    pub fn build_indexed(&self) -> IndexedMesh {
+        let mut vertices = Vec::with_capacity(self.vertices.len());
+        let mut indices = Vec::with_capacity(self.faces.len() * 3);
+        let mut seen: HashMap<[i32; 3], u32> = HashMap::with_capacity(self.vertices.len());
+
+        for (f, face) in self.faces.iter().enumerate() {
+            for corner in 0..3 {
+                let key = [face[corner * 3], face[corner * 3 + 1], face[corner * 3 + 2]];
+                let idx = match seen.get(&key) {
+                    Some(&idx) => idx,
+                    None => {
+                        let idx = vertices.len() as u32;
+                        vertices.push(ModelVertex {
+                            position: self.vertex(f, corner),
+                            texture_coord: self.texture_coord(f, corner),
+                            normal: self.normal(f, corner),
+                            color: self.vertex_color(f, corner)
+                        });
+                        seen.insert(key, idx);
+                        idx
+                    }
+                };
+                indices.push(idx);
+            }
+        }
+
+        IndexedMesh { vertices: vertices, indices: indices }
+    }
+
+    // Returns (min, max) corners of the axis-aligned box enclosing all vertices
+    pub fn bounding_box(&self) -> (Vec3f, Vec3f) {
+        let mut vertices = self.vertices.iter();
+        let first = match vertices.next() {
+            Some(v) => *v,
+            None => return (Vec3f::new(0.0, 0.0, 0.0), Vec3f::new(0.0, 0.0, 0.0))
+        };
+
+        let mut min = first;
+        let mut max = first;
+        for v in vertices {
+            min = Vec3f::new(min.x.min(v.x), min.y.min(v.y), min.z.min(v.z));
+            max = Vec3f::new(max.x.max(v.x), max.y.max(v.y), max.z.max(v.z));
+        }
+
+        return (min, max);
+    }
+
+    // Centers the model at the origin and scales it uniformly so that its
+    // largest dimension spans [-1, 1]. Flat models scale by their largest
+    // non-zero extent, empty or single-point models are only recentered
+    pub fn normalize_to_unit_cube(&mut self) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        let (min, max) = self.bounding_box();
+        let center = (min + max) * 0.5;
+        let extent = max - min;
+        let largest = extent.x.max(extent.y).max(extent.z);
+        let scale = if largest > 0.0 { 2.0 / largest } else { 1.0 };
+
+        for v in self.vertices.iter_mut() {
+            *v = (*v - center) * scale;
+        }
+    }
+}
+
+impl IndexedMesh {
+    // Distinct corners stored in the vertex buffer
+    pub fn unique_vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    // Vertices an unindexed triangle list would store, three per face
+    pub fn original_vertex_count(&self) -> usize {
+        self.indices.len()
+    }
+}
+
+impl Mesh for Model {
+    fn face_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    fn corner(&self, face: usize, corner: usize) -> ModelVertex {
+        ModelVertex {
+            position: self.vertex(face, corner),
+            texture_coord: self.texture_coord(face, corner),
+            normal: self.normal(face, corner),
+            color: self.vertex_color(face, corner)
+        }
+    }
+
+    fn face_material(&self, face: usize) -> Option<&Material> {
+        self.material(face)
+    }
+
+    fn face_texture(&self, face: usize) -> Option<TextureId> {
+        self.texture(face)
+    }
+}
+
+// Drawn with the renderer's own texture and color
+impl Mesh for IndexedMesh {
+    fn face_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn corner(&self, face: usize, corner: usize) -> ModelVertex {
+        self.vertices[self.indices[face * 3 + corner] as usize]
+    }
+
+    fn face_material(&self, _face: usize) -> Option<&Material> {
+        None
+    }
+
+    fn face_texture(&self, _face: usize) -> Option<TextureId> {
+        None
+    }
+}
@@ -0,0 +1,232 @@
+// PLY loader for ascii and binary_little_endian files, reads positions with
+// optional normals and texture coords, faces are fan-triangulated
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+use std::str;
+use math::{Vec2f,Vec3f};
+use super::{Model,ModelError};
+
+#[derive(Clone,Copy,PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian
+}
+
+#[derive(Clone,Copy)]
+enum Type { I8, U8, I16, U16, I32, U32, F32, F64 }
+
+enum Property {
+    Scalar(String, Type),
+    List(String, Type, Type) // count type, item type
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>
+}
+
+fn invalid(what: &str) -> ModelError {
+    ModelError::Invalid(what.to_string())
+}
+
+fn parse_type(name: &str) -> Result<Type, ModelError> {
+    match name {
+        "char" | "int8" => Ok(Type::I8),
+        "uchar" | "uint8" => Ok(Type::U8),
+        "short" | "int16" => Ok(Type::I16),
+        "ushort" | "uint16" => Ok(Type::U16),
+        "int" | "int32" => Ok(Type::I32),
+        "uint" | "uint32" => Ok(Type::U32),
+        "float" | "float32" => Ok(Type::F32),
+        "double" | "float64" => Ok(Type::F64),
+        _ => Err(invalid(&format!("unknown property type {}", name)))
+    }
+}
+
+// Sequential access to element values in either storage format
+struct Values<'a> {
+    format: Format,
+    data: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Values<'a> {
+    fn ascii_token(&mut self) -> Result<&'a str, ModelError> {
+        while self.pos < self.data.len() && (self.data[self.pos] as char).is_whitespace() {
+            self.pos += 1;
+        }
+        let start = self.pos;
+        while self.pos < self.data.len() && !(self.data[self.pos] as char).is_whitespace() {
+            self.pos += 1;
+        }
+
+        if start == self.pos {
+            return Err(invalid("unexpected end of data"));
+        }
+        str::from_utf8(&self.data[start..self.pos]).map_err(|_| invalid("non-ascii value"))
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], ModelError> {
+        match self.data.get(self.pos..self.pos + n) {
+            Some(b) => { self.pos += n; Ok(b) },
+            None => Err(invalid("unexpected end of data"))
+        }
+    }
+
+    fn read(&mut self, ty: Type) -> Result<f64, ModelError> {
+        if self.format == Format::Ascii {
+            let token = self.ascii_token()?;
+            return token.parse::<f64>().map_err(|_| invalid(&format!("can't parse value {:?}", token)));
+        }
+
+        let le32 = |b: &[u8]| b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24;
+        Ok(match ty {
+            Type::I8 => self.bytes(1)?[0] as i8 as f64,
+            Type::U8 => self.bytes(1)?[0] as f64,
+            Type::I16 => { let b = self.bytes(2)?; (b[0] as u16 | (b[1] as u16) << 8) as i16 as f64 },
+            Type::U16 => { let b = self.bytes(2)?; (b[0] as u16 | (b[1] as u16) << 8) as f64 },
+            Type::I32 => le32(self.bytes(4)?) as i32 as f64,
+            Type::U32 => le32(self.bytes(4)?) as f64,
+            Type::F32 => f32::from_bits(le32(self.bytes(4)?)) as f64,
+            Type::F64 => {
+                let b = self.bytes(8)?;
+                let bits = (le32(&b[0..4]) as u64) | (le32(&b[4..8]) as u64) << 32;
+                f64::from_bits(bits)
+            }
+        })
+    }
+}
+
+fn parse_header(header: &str) -> Result<(Format, Vec<Element>), ModelError> {
+    let mut lines = header.lines();
+    if lines.next().map(|l| l.trim()) != Some("ply") {
+        return Err(invalid("missing ply magic"));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.first().cloned() {
+            Some("format") => {
+                format = match words.get(1).cloned() {
+                    Some("ascii") => Some(Format::Ascii),
+                    Some("binary_little_endian") => Some(Format::BinaryLittleEndian),
+                    Some(other) => return Err(ModelError::Unsupported(format!("ply format {}", other))),
+                    None => return Err(invalid("empty format line"))
+                };
+            },
+            Some("element") if words.len() == 3 => {
+                let count = words[2].parse::<usize>().map_err(|_| invalid(line))?;
+                elements.push(Element { name: words[1].to_string(), count: count, properties: Vec::new() });
+            },
+            Some("property") => {
+                let element = elements.last_mut().ok_or(invalid("property before element"))?;
+                let property = match words.len() {
+                    3 => Property::Scalar(words[2].to_string(), parse_type(words[1])?),
+                    5 if words[1] == "list" => Property::List(words[4].to_string(), parse_type(words[2])?, parse_type(words[3])?),
+                    _ => return Err(invalid(line))
+                };
+                element.properties.push(property);
+            },
+            Some("comment") | Some("obj_info") | None => {},
+            Some(_) => return Err(invalid(line))
+        }
+    }
+
+    match format {
+        Some(format) => Ok((format, elements)),
+        None => Err(invalid("missing format line"))
+    }
+}
+
+pub fn parse(data: &[u8]) -> Result<Model, ModelError> {
+    let marker = b"end_header";
+    let end = data.windows(marker.len()).position(|w| w == marker).ok_or(invalid("missing end_header"))?;
+    let header = str::from_utf8(&data[..end]).map_err(|_| invalid("non-ascii header"))?;
+    let (format, elements) = parse_header(header)?;
+
+    // Body starts after the line break following end_header
+    let mut body = end + marker.len();
+    while body < data.len() && data[body] != b'\n' { body += 1; }
+    let mut values = Values { format: format, data: data, pos: body + 1 };
+
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+    let mut texture_coords = Vec::new();
+    let mut faces = Vec::new();
+
+    for element in elements.iter() {
+        for _ in 0..element.count {
+            let mut p = [0f32; 3];
+            let mut n = [0f32; 3];
+            let mut t = [0f32; 2];
+            let mut has_normal = false;
+            let mut has_uv = false;
+            let mut indices: Vec<i32> = Vec::new();
+
+            for property in element.properties.iter() {
+                match *property {
+                    Property::Scalar(ref name, ty) => {
+                        let v = values.read(ty)? as f32;
+                        match name.as_str() {
+                            "x" => p[0] = v, "y" => p[1] = v, "z" => p[2] = v,
+                            "nx" => { n[0] = v; has_normal = true },
+                            "ny" => n[1] = v,
+                            "nz" => n[2] = v,
+                            "u" | "s" | "texture_u" => { t[0] = v; has_uv = true },
+                            "v" | "t" | "texture_v" => t[1] = v,
+                            _ => {}
+                        }
+                    },
+                    Property::List(ref name, count_ty, item_ty) => {
+                        let count = values.read(count_ty)? as usize;
+                        for _ in 0..count {
+                            let idx = values.read(item_ty)? as i32;
+                            if name == "vertex_indices" || name == "vertex_index" {
+                                indices.push(idx);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if element.name == "vertex" {
+                vertices.push(Vec3f::new(p[0], p[1], p[2]));
+                if has_normal { normals.push(Vec3f::new(n[0], n[1], n[2])); }
+                if has_uv { texture_coords.push(Vec2f::new(t[0], t[1])); }
+            } else if element.name == "face" {
+                for i in 1..indices.len().saturating_sub(1) {
+                    faces.push([indices[0], indices[i], indices[i + 1]]);
+                }
+            }
+        }
+    }
+
+    // Attributes are per vertex, so every corner index doubles as texture coord and normal index
+    let has_uv = !texture_coords.is_empty();
+    let has_normals = !normals.is_empty();
+    let faces = faces.iter().map(|triangle| {
+        let mut face = [-1i32; 9];
+        for (c, &i) in triangle.iter().enumerate() {
+            face[c * 3] = i;
+            if has_uv { face[c * 3 + 1] = i; }
+            if has_normals { face[c * 3 + 2] = i; }
+        }
+        face
+    }).collect();
+
+    Model::new(vertices, texture_coords, normals, faces)
+}
+
+impl Model {
+    pub fn new_from_ply(filename: &Path) -> Result<Model, ModelError> {
+        let mut data = Vec::new();
+        File::open(filename)?.read_to_end(&mut data)?;
+        parse(&data)
+    }
+}
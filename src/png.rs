@@ -0,0 +1,92 @@
+// Minimal PNG encoder, 8 bit RGB or RGBA with filter type 0 scanlines
+// wrapped in stored (uncompressed) deflate blocks
+
+use std::io;
+use std::io::prelude::*;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+const MAX_STORED_BLOCK: usize = 65535;
+
+fn crc32(data: &[u8], mut crc: u32) -> u32 {
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xEDB88320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for chunk in data.chunks(5552) {
+        for &v in chunk {
+            a += v as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+fn push_u32(data: &mut Vec<u8>, v: u32) {
+    data.extend(&[(v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8]);
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], payload: &[u8]) -> io::Result<()> {
+    let mut data = Vec::with_capacity(payload.len() + 12);
+    push_u32(&mut data, payload.len() as u32);
+    data.extend(kind);
+    data.extend(payload);
+    let crc = crc32(&data[4..], 0xFFFFFFFF) ^ 0xFFFFFFFF;
+    push_u32(&mut data, crc);
+    writer.write_all(&data)
+}
+
+// zlib stream of stored deflate blocks
+fn zlib_stored(raw: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(raw.len() + raw.len() / MAX_STORED_BLOCK * 5 + 16);
+    data.extend(&[0x78, 0x01]);
+
+    let mut blocks = raw.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        data.extend(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        data.push(if last { 1 } else { 0 });
+        data.extend(&[len as u8, (len >> 8) as u8, !len as u8, (!len >> 8) as u8]);
+        data.extend(block);
+    }
+
+    push_u32(&mut data, adler32(raw));
+    data
+}
+
+// Writes width x height pixels of `channels` (3 or 4) bytes each, rows top to bottom
+pub fn encode<W: Write>(mut writer: W, width: u32, height: u32, channels: u8, pixels: &[u8]) -> io::Result<()> {
+    let stride = width as usize * channels as usize;
+    assert_eq!(pixels.len(), stride * height as usize);
+
+    let mut ihdr = Vec::with_capacity(13);
+    push_u32(&mut ihdr, width);
+    push_u32(&mut ihdr, height);
+    // bit depth, color type 2 (RGB) or 6 (RGBA), compression, filter, interlace
+    ihdr.extend(&[8, if channels == 4 { 6 } else { 2 }, 0, 0, 0]);
+
+    // Every scanline is prefixed by its filter type, 0 leaves it as is
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+    for row in pixels.chunks(stride) {
+        raw.push(0);
+        raw.extend(row);
+    }
+
+    writer.write_all(&SIGNATURE)?;
+    write_chunk(&mut writer, b"IHDR", &ihdr)?;
+    write_chunk(&mut writer, b"IDAT", &zlib_stored(&raw))?;
+    write_chunk(&mut writer, b"IEND", &[])?;
+    writer.flush()
+}
@@ -0,0 +1,95 @@
+// Interactive window showing a model re-rendered every frame, built with the
+// preview feature. Dragging with the left button orbits the camera around
+// the model, the scroll wheel zooms and Esc closes the window
+
+use std::f32::consts::PI;
+use std::time::Duration;
+use minifb::{Window,WindowOptions,Key,MouseButton,MouseMode};
+use renderer::Renderer;
+use model::Model;
+use math::{Vec3f,Mat4};
+use tga::RgbaColor;
+
+// Radians turned per pixel dragged
+const ROTATE_SPEED: f32 = 0.01;
+// Radians per frame the model spins until the first drag
+const SPIN_SPEED: f32 = 0.01;
+const MIN_DISTANCE: f32 = 1.5;
+const MAX_DISTANCE: f32 = 20.0;
+
+// Camera on a sphere around the origin where the fitted model sits
+#[derive(Clone,Copy,Debug)]
+pub struct Orbit {
+    // Around the y axis, 0 looks from +z like the default camera
+    pub yaw: f32,
+    // Above the xz plane, kept short of the poles where up flips
+    pub pitch: f32,
+    pub distance: f32
+}
+
+impl Orbit {
+    pub fn new(distance: f32) -> Orbit {
+        return Orbit { yaw: 0.0, pitch: 0.0, distance: distance };
+    }
+
+    pub fn rotate(&mut self, dx: f32, dy: f32) {
+        let limit = PI / 2.0 - 0.01;
+        self.yaw -= dx * ROTATE_SPEED;
+        self.pitch = (self.pitch + dy * ROTATE_SPEED).max(-limit).min(limit);
+    }
+
+    // Positive steps move closer
+    pub fn zoom(&mut self, steps: f32) {
+        self.distance = (self.distance * 0.9f32.powf(steps)).max(MIN_DISTANCE).min(MAX_DISTANCE);
+    }
+
+    pub fn eye(&self) -> Vec3f {
+        Vec3f::new(self.yaw.sin() * self.pitch.cos(), self.pitch.sin(), self.yaw.cos() * self.pitch.cos()) * self.distance
+    }
+
+    // Points renderer at the origin from the orbit's eye with perspective
+    pub fn apply(&self, renderer: &mut Renderer) {
+        renderer.set_camera(self.eye(), Vec3f::new(0.0, 0.0, 0.0), Vec3f::new(0.0, 1.0, 0.0));
+        renderer.set_camera_distance(self.distance);
+    }
+}
+
+// Opens a window the size of renderer's output and redraws model in it
+// until the window is closed. The renderer's other settings are kept, its
+// camera is replaced by the orbit
+pub fn run(renderer: &mut Renderer, model: &Model, title: &str) -> Result<(), minifb::Error> {
+    let (width, height) = renderer.output_size();
+    let mut window = Window::new(title, width as usize, height as usize, WindowOptions::default())?;
+    window.limit_update_rate(Some(Duration::from_millis(16)));
+
+    let mut orbit = Orbit::new(3.0);
+    let mut buffer = Vec::new();
+    let mut last_mouse: Option<(f32, f32)> = None;
+    let mut spinning = true;
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        let mouse = window.get_mouse_pos(MouseMode::Discard);
+        if window.get_mouse_down(MouseButton::Left) {
+            if let (Some((x0, y0)), Some((x1, y1))) = (last_mouse, mouse) {
+                orbit.rotate(x1 - x0, y1 - y0);
+            }
+            last_mouse = mouse;
+            spinning = false;
+        } else {
+            last_mouse = None;
+        }
+        if let Some((_, scroll)) = window.get_scroll_wheel() {
+            orbit.zoom(scroll.signum());
+        }
+        if spinning {
+            orbit.yaw += SPIN_SPEED;
+        }
+
+        orbit.apply(renderer);
+        renderer.clear(RgbaColor::new(0.0, 0.0, 0.0, 1.0));
+        renderer.draw_model(model, &Mat4::identity());
+        renderer.to_rgb32(&mut buffer);
+        window.update_with_buffer(&buffer, width as usize, height as usize)?;
+    }
+    Ok(())
+}
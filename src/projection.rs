@@ -0,0 +1,38 @@
+// Camera projections for Renderer::set_projection. The view transform leaves
+// what the camera looks at around the origin, see Renderer::set_camera, so
+// both are set up for a camera on the +z axis looking down -z
+
+use std::f32;
+use math::{Vec3f,Mat4};
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Projection {
+    // Vertical field of view in radians. The camera sits where the field of
+    // view spans [-1, 1] at z = 0, 1 / tan(fov / 2) from the origin, and near
+    // and far are distances in front of it
+    Perspective { fov: f32, near: f32, far: f32 },
+    // Box in view space in front of a camera at the origin, so a negative
+    // near reaches behind it. It's fitted to the viewport like the default
+    // projection, left -1, right 1, bottom -1, top 1, near -1 and far 1, with
+    // the box on the shorter side and more showing along the longer one
+    Orthographic { left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32 }
+}
+
+impl Projection {
+    // Panics on an empty view volume or a field of view outside (0, pi)
+    pub fn matrix(&self) -> Mat4 {
+        match *self {
+            Projection::Perspective { fov, near, far } => {
+                assert!(fov > 0.0 && fov < f32::consts::PI, "field of view must be between 0 and pi");
+                assert!(near > 0.0 && far > near, "near must be positive and less than far");
+                let distance = 1.0 / (fov / 2.0).tan();
+                Mat4::perspective(fov, 1.0, near, far) * Mat4::translation(Vec3f::new(0.0, 0.0, -distance))
+            },
+            Projection::Orthographic { left, right, bottom, top, near, far } => {
+                assert!(left != right && bottom != top, "view box must have an area");
+                assert!(near != far, "near and far must differ");
+                Mat4::orthographic(left, right, bottom, top, near, far)
+            }
+        }
+    }
+}
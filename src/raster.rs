@@ -0,0 +1,311 @@
+// Triangle rasterization into a rectangle of pixels, either the whole
+// framebuffer or a tile of it owned by a worker thread
+
+use math::{Vec2f,Vec3f,barycentric_mix};
+use math::geometry::edge_function;
+use framebuffer::{BlendMode,DepthFunc,blend};
+use shader::Shader;
+use tga::RgbaColor;
+use std::ops::AddAssign;
+
+pub const TILE_SIZE: i32 = 64;
+
+// Which pixels a triangle covers is decided on its corners snapped to 1/16
+// of a pixel, 28.4 fixed point, so the decision is exact
+const SUBPIXEL_BITS: i64 = 4;
+
+// Farthest from the origin a corner may be, in pixels. Snapped corners take
+// at most 2^29, so edge vectors and offsets from a corner at most 2^30, their
+// products in edge functions 2^60 and the differences of those 2^61, exact
+// in i64. Clipping keeps model faces within the viewport, so only triangles
+// given straight to Renderer::triangle can reach farther, those are skipped
+pub const MAX_COORDINATE: f32 = (1 << 25) as f32;
+
+// Screen position in fixed point
+#[derive(Clone,Copy)]
+struct Fixed {
+    x: i64,
+    y: i64
+}
+
+impl Fixed {
+    fn snap(p: Vec3f) -> Option<Fixed> {
+        // Also false for NaN
+        if !(p.x.abs() <= MAX_COORDINATE && p.y.abs() <= MAX_COORDINATE) {
+            return None;
+        }
+        let steps = (1 << SUBPIXEL_BITS) as f32;
+        Some(Fixed { x: (p.x * steps).round() as i64, y: (p.y * steps).round() as i64 })
+    }
+
+    fn pixel_center(x: i32, y: i32) -> Fixed {
+        let half = 1 << (SUBPIXEL_BITS - 1);
+        Fixed { x: ((x as i64) << SUBPIXEL_BITS) + half, y: ((y as i64) << SUBPIXEL_BITS) + half }
+    }
+}
+
+// Twice the signed area of a, b, p, exact
+fn fixed_edge(a: Fixed, b: Fixed, p: Fixed) -> i64 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+// Pixel bounds, min inclusive and max exclusive
+#[derive(Clone,Copy)]
+pub struct Rect {
+    pub x0: i32,
+    pub y0: i32,
+    pub x1: i32,
+    pub y1: i32
+}
+
+// How fragments are written, copied from the renderer
+#[derive(Clone,Copy)]
+pub struct RasterState {
+    pub blend_mode: BlendMode,
+    pub depth_func: DepthFunc,
+    // Whether fragments passing the depth test update it
+    pub depth_write: bool,
+    // Whether blended fragments update depth too
+    pub translucent_depth_write: bool,
+    // Only updates depth, used for shadow map passes
+    pub depth_only: bool
+}
+
+// color and depth are row-major with stride pixels per row, starting at
+// framebuffer pixel (x, y). Nothing outside clip is drawn
+pub struct Target<'a> {
+    pub x: i32,
+    pub y: i32,
+    pub stride: i32,
+    pub color: &'a mut [RgbaColor],
+    pub depth: &'a mut [f32],
+    pub clip: Rect
+}
+
+// What happened to the pixel centers rasterize found inside triangles
+#[derive(Clone,Copy,Debug,Default)]
+pub struct Fragments {
+    // Passed the depth test
+    pub passed: usize,
+    // Failed it, usually behind what was already drawn
+    pub rejected: usize,
+    // Passed but the shader returned None
+    pub discarded: usize
+}
+
+impl AddAssign for Fragments {
+    fn add_assign(&mut self, other: Fragments) {
+        self.passed += other.passed;
+        self.rejected += other.rejected;
+        self.discarded += other.discarded;
+    }
+}
+
+// Screen triangle plus the face and corner weights the shader needs
+#[derive(Clone,Copy)]
+pub struct Triangle {
+    pub face: usize,
+    pub p: [Vec3f; 3],
+    pub bary: [Vec3f; 3],
+    // 1 / clip space w of the corners, for perspective-correct weights
+    pub inv_w: [f32; 3]
+}
+
+impl Triangle {
+    pub fn screen(&self) -> [Vec2f; 3] {
+        [Vec2f::new(self.p[0].x, self.p[0].y), Vec2f::new(self.p[1].x, self.p[1].y), Vec2f::new(self.p[2].x, self.p[2].y)]
+    }
+
+    fn snapped(&self) -> Option<[Fixed; 3]> {
+        match (Fixed::snap(self.p[0]), Fixed::snap(self.p[1]), Fixed::snap(self.p[2])) {
+            (Some(a), Some(b), Some(c)) => Some([a, b, c]),
+            _ => None
+        }
+    }
+
+    // No area left once snapped, rasterize won't draw anything
+    pub fn is_degenerate(&self) -> bool {
+        match self.snapped() {
+            Some(s) => fixed_edge(s[0], s[1], s[2]) == 0,
+            None => false
+        }
+    }
+
+    // Pixels of clip rasterize may touch, None when the bounding box misses
+    // clip or a corner lies beyond MAX_COORDINATE
+    pub fn bounds(&self, clip: Rect) -> Option<Rect> {
+        self.snapped()?;
+        let p = &self.p;
+        let xmin = p[0].x.min(p[1].x).min(p[2].x).floor().max(clip.x0 as f32) as i32;
+        let ymin = p[0].y.min(p[1].y).min(p[2].y).floor().max(clip.y0 as f32) as i32;
+        let xmax = p[0].x.max(p[1].x).max(p[2].x).ceil().min((clip.x1 - 1) as f32) as i32;
+        let ymax = p[0].y.max(p[1].y).max(p[2].y).ceil().min((clip.y1 - 1) as f32) as i32;
+        if xmin > xmax || ymin > ymax {
+            return None;
+        }
+        Some(Rect { x0: xmin, y0: ymin, x1: xmax + 1, y1: ymax + 1 })
+    }
+}
+
+// Whether pixel centers exactly on the edge from a to b belong to the
+// triangle, the top-left rule: with y down the edge has to be a left edge,
+// the triangle to its right, or a horizontal top edge with the triangle
+// below. Neighbours see a shared edge from opposite sides, so exactly one
+// of them owns it. sign is that of the triangle's area
+fn owns_edge(a: Fixed, b: Fixed, sign: i64) -> bool {
+    let (inward_x, inward_y) = ((a.y - b.y) * sign, (b.x - a.x) * sign);
+    inward_x > 0 || (inward_x == 0 && inward_y > 0)
+}
+
+// Fills triangle, calling shader.fragment with the pixel's weights of the
+// face corners for every pixel center inside that passes the depth test.
+// Inside is decided on the snapped corners, see Fixed, so triangles move
+// across pixels in steps of 1/16 and neighbours sharing an edge cover every
+// pixel along it exactly once. Centers on an edge are drawn only if the
+// triangle owns it, see owns_edge. The weights come from the unsnapped
+// corners and are perspective-correct: the screen space ones are divided
+// by w and renormalized, unless w is the same at every corner as with
+// orthographic projection. Depth is z / w which is affine in screen space,
+// so it's interpolated linearly either way
+pub fn rasterize<S: Shader>(target: &mut Target, state: &RasterState, shader: &S, triangle: &Triangle) -> Fragments {
+    let mut fragments = Fragments::default();
+    let (s, bounds) = match (triangle.snapped(), triangle.bounds(target.clip)) {
+        (Some(s), Some(bounds)) => (s, bounds),
+        _ => return fragments
+    };
+    let fixed_area = fixed_edge(s[0], s[1], s[2]);
+    if fixed_area == 0 {
+        return fragments;
+    }
+    let sign = fixed_area.signum();
+    let owned = [owns_edge(s[1], s[2], sign), owns_edge(s[2], s[0], sign), owns_edge(s[0], s[1], sign)];
+
+    // Snapping can leave area where the unsnapped corners had none, the
+    // snapped ones give the weights then
+    let screen = triangle.screen();
+    let (a, b, c) = (screen[0], screen[1], screen[2]);
+    let area = edge_function(a, b, c);
+    let (p, bary, inv_w) = (&triangle.p, &triangle.bary, &triangle.inv_w);
+    let perspective = inv_w[0] != inv_w[1] || inv_w[1] != inv_w[2];
+
+    for y in bounds.y0..bounds.y1 {
+        for x in bounds.x0..bounds.x1 {
+            // Sampled at the pixel center, multiplying by the sign of the
+            // area makes the edge functions positive inside for either winding
+            let center = Fixed::pixel_center(x, y);
+            let e = [fixed_edge(s[1], s[2], center) * sign, fixed_edge(s[2], s[0], center) * sign, fixed_edge(s[0], s[1], center) * sign];
+            if e[0] < 0 || e[1] < 0 || e[2] < 0 {
+                continue;
+            }
+            if (e[0] == 0 && !owned[0]) || (e[1] == 0 && !owned[1]) || (e[2] == 0 && !owned[2]) {
+                continue;
+            }
+
+            let w = if area != 0.0 {
+                let center = Vec2f::new(x as f32 + 0.5, y as f32 + 0.5);
+                Vec3f::new(edge_function(b, c, center), edge_function(c, a, center), edge_function(a, b, center)) / area
+            } else {
+                Vec3f::new(e[0] as f32, e[1] as f32, e[2] as f32) / (fixed_area * sign) as f32
+            };
+
+            let idx = ((x - target.x) + target.stride * (y - target.y)) as usize;
+            let z = barycentric_mix(p[0].z, p[1].z, p[2].z, w);
+
+            if !state.depth_func.passes(z, target.depth[idx]) {
+                fragments.rejected += 1;
+                continue;
+            }
+            fragments.passed += 1;
+
+            if state.depth_only {
+                if state.depth_write { target.depth[idx] = z; }
+                continue;
+            }
+
+            let w = if perspective {
+                let w = Vec3f::new(w.x * inv_w[0], w.y * inv_w[1], w.z * inv_w[2]);
+                w / (w.x + w.y + w.z)
+            } else {
+                w
+            };
+            let c = match shader.fragment(bary[0] * w.x + bary[1] * w.y + bary[2] * w.z) {
+                Some(c) => c,
+                None => {
+                    fragments.discarded += 1;
+                    continue;
+                }
+            };
+
+            let blended = match state.blend_mode {
+                BlendMode::Replace => false,
+                BlendMode::AlphaBlend => c.a < 1.0,
+                BlendMode::Additive => true
+            };
+
+            if state.depth_write && (!blended || state.translucent_depth_write) { target.depth[idx] = z; }
+            let mode = if blended { state.blend_mode } else { BlendMode::Replace };
+            target.color[idx] = blend(target.color[idx], &c, mode);
+        }
+    }
+    fragments
+}
+
+// Copy of a rectangle of the framebuffer a single thread draws into
+pub struct Tile {
+    pub rect: Rect,
+    color: Vec<RgbaColor>,
+    depth: Vec<f32>,
+    // Indices of the triangles overlapping the tile, in submission order
+    pub triangles: Vec<usize>,
+    pub fragments: Fragments
+}
+
+impl Tile {
+    // Copies rect out of framebuffer color and depth with stride pixels per row
+    pub fn new(rect: Rect, color: &[RgbaColor], depth: &[f32], stride: i32) -> Tile {
+        let mut tile_color = Vec::with_capacity(((rect.x1 - rect.x0) * (rect.y1 - rect.y0)) as usize);
+        let mut tile_depth = Vec::with_capacity(tile_color.capacity());
+        for y in rect.y0..rect.y1 {
+            let row = (rect.x0 + stride * y) as usize..(rect.x1 + stride * y) as usize;
+            tile_color.extend_from_slice(&color[row.clone()]);
+            tile_depth.extend_from_slice(&depth[row]);
+        }
+        return Tile { rect: rect, color: tile_color, depth: tile_depth, triangles: Vec::new(), fragments: Fragments::default() };
+    }
+
+    pub fn write_back(&self, color: &mut [RgbaColor], depth: &mut [f32], stride: i32) {
+        let width = (self.rect.x1 - self.rect.x0) as usize;
+        for (i, y) in (self.rect.y0..self.rect.y1).enumerate() {
+            let row = (self.rect.x0 + stride * y) as usize..(self.rect.x1 + stride * y) as usize;
+            color[row.clone()].copy_from_slice(&self.color[i * width..(i + 1) * width]);
+            depth[row].copy_from_slice(&self.depth[i * width..(i + 1) * width]);
+        }
+    }
+
+    // Draws the binned triangles. The shader's vertex stage runs again for
+    // each face, so the shader holds that face's varyings
+    pub fn rasterize<S: Shader>(&mut self, shader: &mut S, triangles: &[Triangle], state: &RasterState) {
+        let rect = self.rect;
+        let mut target = Target {
+            x: rect.x0,
+            y: rect.y0,
+            stride: rect.x1 - rect.x0,
+            color: &mut self.color,
+            depth: &mut self.depth,
+            clip: rect
+        };
+
+        let mut current = None;
+        for &i in self.triangles.iter() {
+            let triangle = &triangles[i];
+            if current != Some(triangle.face) {
+                for corner in 0..3 {
+                    shader.vertex(triangle.face, corner);
+                }
+                current = Some(triangle.face);
+            }
+            shader.begin_triangle(&triangle.screen(), &triangle.bary);
+            self.fragments += rasterize(&mut target, state, shader, triangle);
+        }
+    }
+}
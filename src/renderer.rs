@@ -0,0 +1,1788 @@
+// Renderer and the state it draws with
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::f32;
+use std::f32::consts::PI;
+use std::fmt;
+use std::mem;
+use std::path::{Path,PathBuf};
+use std::thread;
+
+use tga::{TgaImage,TgaError,RgbaColor,Filter,Wrap};
+use model::{Model,Mesh,IndexedMesh};
+use texture::{Texture,TextureId};
+use framebuffer::{Framebuffer,FramebufferError,BlendMode,DepthFunc,color_to_linear,depth_to_image};
+use math::{Vec2f,Vec3f,Vec4f,Mat3,Mat4,lerp};
+use math::geometry::polygon_area_2d;
+use clip::{ClipVertex,clip_polygon,clip_segment};
+use shadow::ShadowMap;
+use shader::{Shader,StandardShader,Shading,Vertex};
+use light::Light;
+use projection::Projection;
+use kernel::Kernel;
+use text;
+use environment::Environment;
+use fog::{Fog,FogMode};
+use depth::DepthBuffer;
+use target::RenderTarget;
+use scene::Scene;
+use raster::{self,Rect,RasterState,Target,Triangle,Tile,TILE_SIZE};
+pub use raster::Fragments;
+
+// Pixels between the image corner and turntable frame numbers
+const FRAME_LABEL_MARGIN: i32 = 2;
+
+// Pixel rectangle NDC is mapped onto, nothing outside it is drawn
+#[derive(Clone,Copy)]
+struct Viewport {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32
+}
+
+impl Viewport {
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+
+    // Cohen–Sutherland region code of a point against the viewport grown by
+    // margin pixels, 0 inside. A segment whose endpoints share a bit lies
+    // entirely outside
+    fn outcode(&self, x: f32, y: f32, margin: f32) -> u8 {
+        let mut code = 0;
+        if x < self.x as f32 - margin { code |= 1; } else if x >= (self.x + self.width) as f32 + margin { code |= 2; }
+        if y < self.y as f32 - margin { code |= 4; } else if y >= (self.y + self.height) as f32 + margin { code |= 8; }
+        code
+    }
+
+    fn misses_segment(&self, x0: f32, y0: f32, x1: f32, y1: f32, margin: f32) -> bool {
+        self.outcode(x0, y0, margin) & self.outcode(x1, y1, margin) != 0
+    }
+}
+
+// A turntable frame that couldn't be written, the frames before it were
+#[derive(Debug)]
+pub struct FrameError {
+    // Starting at 1 like the file names
+    pub frame: u32,
+    pub path: PathBuf,
+    pub error: TgaError
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot write frame {} to {}: {}", self.frame, self.path.display(), self.error)
+    }
+}
+
+impl Error for FrameError {}
+
+// Vertex order of front faces as seen on screen, OBJ files are usually
+// counter-clockwise but some exporters write clockwise faces
+#[derive(Clone,Copy,PartialEq)]
+pub enum Winding {
+    CounterClockwise,
+    Clockwise
+}
+
+// How draw_points colors vertices
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum PointColor {
+    // The current color
+    Uniform,
+    // World space normal mapped from [-1, 1] to [0, 1] per channel, the
+    // current color for vertices without one
+    Normal,
+    // Grayscale, white at the near end of the view volume
+    Depth
+}
+
+// Work done since the last clear or reset_stats. Faces come from the model
+// path and Renderer::triangle, each submitted one ends up drawn, culled,
+// clipped or degenerate
+#[derive(Clone,Copy,Debug,Default)]
+pub struct RenderStats {
+    pub submitted: usize,
+    pub drawn: usize,
+    // Back-facing
+    pub culled: usize,
+    // Entirely outside the view
+    pub clipped: usize,
+    // No area left on screen
+    pub degenerate: usize,
+    pub fragments: Fragments
+}
+
+pub struct Renderer {
+    target: RenderTarget,
+    viewport: Viewport,
+    diffuse: Option<Texture>,
+    // Indexed by models' face textures
+    textures: Vec<Texture>,
+    // Specular exponents in the red channel, 0-255
+    specular: Option<Texture>,
+    specular_intensity: f32,
+    glow: Option<Texture>,
+    // Blur of the glow buffer, no bloom when empty
+    bloom_passes: Vec<Kernel>,
+    // Light every fragment gets regardless of its normal
+    ambient: f32,
+    depth_func: DepthFunc,
+    depth_write: bool,
+    // Stored depths of the near and far end of NDC z
+    depth_range: (f32, f32),
+    color: RgbaColor,
+    shading: Shading,
+    filter: Filter,
+    wrap: Wrap,
+    blend_mode: BlendMode,
+    translucent_depth_write: bool,
+    alpha_test: f32,
+    toon_thresholds: Vec<f32>,
+    // Depth difference to a neighbour that makes a pixel part of an outline
+    outline: Option<f32>,
+    // Shade in linear space, decoding textures from and encoding output to sRGB
+    gamma: bool,
+    dither: bool,
+    // Applied to the resolved image by to_image and to_rgb32
+    post_passes: Vec<Kernel>,
+    model_center: Vec3f,
+    model_scale: f32,
+    lights: Vec<Light>,
+    two_sided_lighting: bool,
+    shadow: Option<ShadowMap>,
+    // Scales diffuse and specular light of shadowed fragments
+    shadow_factor: f32,
+    environment: Option<Environment>,
+    // How much of the environment model surfaces mirror
+    reflectivity: f32,
+    fog: Option<Fog>,
+    // Number of steps [-1, 1] depth is quantized to in the z-buffer
+    depth_levels: f32,
+    // Only updates the z-buffer, used for shadow map passes
+    depth_only: bool,
+    // Applied to fitted model coordinates, normals go through normal_matrix
+    model_transform: Mat4,
+    normal_matrix: Mat3,
+    // World to camera transform, lighting stays in world space
+    view: Mat4,
+    backface_culling: bool,
+    front_face: Winding,
+    stats: RenderStats,
+    // Color render_turntable writes frame numbers in, None leaves them out
+    frame_label: Option<RgbaColor>,
+    wireframe_depth_bias: Option<f32>,
+    point_color: PointColor,
+    // Clip space transform applied after fitting the model, identity keeps
+    // the orthographic mapping of [-1, 1] onto the screen
+    projection: Mat4,
+    threads: usize
+}
+
+impl Renderer {
+    pub fn new(width: i32, height: i32) -> Renderer {
+        Renderer::from_target(RenderTarget::new(width, height))
+    }
+
+    // new with supersampling set, failing instead of aborting when the
+    // buffers take too much memory
+    pub fn try_new(width: i32, height: i32, supersampling: u32) -> Result<Renderer, FramebufferError> {
+        RenderTarget::try_new(width, height, supersampling).map(Renderer::from_target)
+    }
+
+    fn from_target(target: RenderTarget) -> Renderer {
+        let viewport = Viewport { x: 0, y: 0, width: target.framebuffer.width, height: target.framebuffer.height };
+
+        return Renderer {
+            target: target,
+            viewport: viewport,
+            depth_func: DepthFunc::GreaterEqual,
+            depth_write: true,
+            depth_range: (1.0, 0.0),
+            diffuse: None,
+            textures: Vec::new(),
+            specular: None,
+            specular_intensity: 0.6,
+            glow: None,
+            bloom_passes: Vec::new(),
+            ambient: 0.0,
+            color: RgbaColor::new(1.0, 1.0, 1.0, 1.0),
+            shading: Shading::Flat,
+            filter: Filter::Nearest,
+            wrap: Wrap::Repeat,
+            blend_mode: BlendMode::AlphaBlend,
+            translucent_depth_write: false,
+            alpha_test: 0.0,
+            toon_thresholds: Vec::new(),
+            outline: None,
+            gamma: false,
+            dither: false,
+            post_passes: Vec::new(),
+            model_center: Vec3f::new(0.0, 0.0, 0.0),
+            model_scale: 1.0,
+            lights: vec![Light::default()],
+            two_sided_lighting: true,
+            shadow: None,
+            shadow_factor: 0.3,
+            environment: None,
+            reflectivity: 0.0,
+            fog: None,
+            depth_levels: 255.0,
+            depth_only: false,
+            model_transform: Mat4::identity(),
+            normal_matrix: Mat3::identity(),
+            view: Mat4::identity(),
+            backface_culling: true,
+            front_face: Winding::CounterClockwise,
+            stats: RenderStats::default(),
+            frame_label: None,
+            wireframe_depth_bias: None,
+            point_color: PointColor::Uniform,
+            projection: Mat4::identity(),
+            threads: 1
+        };
+    }
+
+    pub fn set_diffuse(&mut self, diffuse: Texture) {
+        self.diffuse = Some(diffuse);
+    }
+
+    // Keeps image, with mipmaps, for the faces of models set to the returned
+    // id with Model::set_texture. Faces share the one copy
+    pub fn add_texture(&mut self, image: TgaImage) -> TextureId {
+        let mut texture = Texture::new(image);
+        texture.generate_mipmaps();
+        self.textures.push(texture);
+        TextureId(self.textures.len() - 1)
+    }
+
+    pub fn set_specular_map(&mut self, map: TgaImage) {
+        self.specular = Some(Texture::new(map));
+    }
+
+    // Color added to lit model fragments from map at their texture
+    // coordinates, lights or not, for parts that give off light
+    pub fn set_glow_map(&mut self, map: TgaImage) {
+        self.glow = Some(Texture::new(map));
+    }
+
+    pub fn remove_glow_map(&mut self) {
+        self.glow = None;
+    }
+
+    // Non-empty passes turn on bloom: the glow of visible model fragments is
+    // drawn into a buffer of its own as well, which to_image blurs with the
+    // passes in order and adds to the color for a halo. The kernels work at
+    // framebuffer resolution, so halos narrow when supersampling unless
+    // wider ones are given. An empty slice turns bloom off. Turning it on
+    // allocates the glow buffer, on error bloom stays as it was
+    pub fn set_bloom(&mut self, passes: &[Kernel]) -> Result<(), FramebufferError> {
+        if passes.is_empty() {
+            self.target.glow = None;
+        } else {
+            self.target.try_alloc_glow()?;
+        }
+        self.bloom_passes = passes.to_vec();
+        Ok(())
+    }
+
+    // Weight of the specular highlight relative to diffuse
+    pub fn set_specular_intensity(&mut self, intensity: f32) {
+        self.specular_intensity = intensity;
+    }
+
+    pub fn set_ambient(&mut self, ambient: f32) {
+        self.ambient = ambient;
+    }
+
+    // Replaces all lights with a white directional one travelling along dir
+    pub fn set_light_dir(&mut self, dir: Vec3f) {
+        self.set_light(dir, RgbaColor::new(1.0, 1.0, 1.0, 1.0), 1.0);
+    }
+
+    // Replaces all lights with a single directional one
+    pub fn set_light(&mut self, dir: Vec3f, color: RgbaColor, intensity: f32) {
+        self.lights = vec![Light::directional(dir, color, intensity)];
+    }
+
+    // Contributions of all lights are summed, then added to ambient before
+    // scaling the surface color
+    pub fn add_light(&mut self, light: Light) {
+        self.lights.push(light);
+    }
+
+    // Leaves only ambient light
+    pub fn clear_lights(&mut self) {
+        self.lights.clear();
+    }
+
+    // Whether lights reach surfaces facing away from them, on by default. Off
+    // gives a terminator where surfaces turn away from a light
+    pub fn set_two_sided_lighting(&mut self, enabled: bool) {
+        self.two_sided_lighting = enabled;
+    }
+
+    // light_view maps world space to the light's clip space, usually a lookat
+    // from the light with [-1, 1] covering the scene. Before drawing, each
+    // draw_model call renders the model's depth from the light into a cleared
+    // resolution x resolution map, so models shadow themselves, and draw_scene
+    // renders all its nodes into it so they shadow each other. Fragments
+    // behind the stored depth by more than bias (in light space NDC units)
+    // are shadowed
+    pub fn enable_shadows(&mut self, light_view: Mat4, resolution: i32, bias: f32) {
+        self.shadow = Some(ShadowMap::new(light_view, resolution, bias));
+    }
+
+    pub fn disable_shadows(&mut self) {
+        self.shadow = None;
+    }
+
+    // Fraction of direct light shadowed fragments still get
+    pub fn set_shadow_factor(&mut self, factor: f32) {
+        self.shadow_factor = factor;
+    }
+
+    // Equirectangular image of the surroundings, see Environment. From then
+    // on clear and clear_color fill the background with what the camera sees
+    // of it instead of a flat color. An orthographic camera looks in a single
+    // direction and sees one color of it, set_camera_distance for a
+    // perspective background
+    pub fn set_environment(&mut self, image: TgaImage) {
+        self.environment = Some(Environment::new(image));
+    }
+
+    pub fn remove_environment(&mut self) {
+        self.environment = None;
+    }
+
+    // Fraction of a model surface's color replaced by the environment
+    // reflected about its normal, 0 by default. Only with an environment set
+    pub fn set_reflectivity(&mut self, reflectivity: f32) {
+        self.reflectivity = reflectivity;
+    }
+
+    // Blends lit and textured fragments toward color by their distance in
+    // front of the camera in world units, measured from the camera of a
+    // perspective projection or from the front of the view volume, z = 1 after
+    // the view transform by default, when orthographic. Depth stays as it is
+    pub fn set_fog(&mut self, color: RgbaColor, mode: FogMode) {
+        self.fog = Some(Fog { color: color, mode: mode });
+    }
+
+    pub fn remove_fog(&mut self) {
+        self.fog = None;
+    }
+
+    // Row of view_depth, see StandardShader
+    fn view_depth(&self) -> Vec4f {
+        // Perspective projections have w = 0 at the camera on the z axis,
+        // parallel ones start at their near plane, where NDC z is 1
+        let m = &self.projection.m;
+        let eye = if m[3][2] < 0.0 { m[3][3] / -m[3][2] } else { (1.0 - m[2][3]) / m[2][2] };
+        let v = &self.view.m[2];
+        Vec4f::new(-v[0], -v[1], -v[2], eye - v[3])
+    }
+
+    // Light space depth for debugging, white is closest to the light
+    pub fn shadow_map_image(&self) -> Option<TgaImage> {
+        self.shadow.as_ref().map(|s| s.to_image())
+    }
+
+    pub fn set_shading(&mut self, shading: Shading) {
+        self.shading = shading;
+    }
+
+    // Diffuse intensities where Shading::Toon bands start, ascending. Band k
+    // of the thresholds.len() + 1 gets k / thresholds.len() of the light.
+    // Empty, the default, makes the bands equally wide
+    pub fn set_toon_thresholds(&mut self, thresholds: Vec<f32>) {
+        self.toon_thresholds = thresholds;
+    }
+
+    // Some(threshold) blackens drawn pixels next to nothing drawn or to a
+    // pixel farther away by more than threshold, in the units of
+    // set_depth_range, outlining silhouettes and creases in the output
+    pub fn set_outline(&mut self, threshold: Option<f32>) {
+        self.outline = threshold;
+    }
+
+    pub fn set_texture_sampling(&mut self, filter: Filter, wrap: Wrap) {
+        self.filter = filter;
+        self.wrap = wrap;
+    }
+
+    // Starts another frame with the same renderer: fills the framebuffer with
+    // color, forgets all depths, the shadow map's included, and resets the
+    // statistics
+    pub fn clear(&mut self, color: RgbaColor) {
+        self.clear_color(color);
+        self.clear_depth();
+        if let Some(ref mut shadow) = self.shadow {
+            shadow.clear();
+        }
+        self.reset_stats();
+    }
+
+    // Fills the framebuffer with color, or with the environment when one is set
+    pub fn clear_color(&mut self, color: RgbaColor) {
+        if let Some(ref mut glow) = self.target.glow {
+            glow.clear(RgbaColor::new(0.0, 0.0, 0.0, 0.0));
+        }
+        let environment = match self.environment {
+            Some(ref environment) => environment,
+            None => return self.target.framebuffer.clear(color)
+        };
+
+        let rays = self.camera_rays();
+        let width = self.target.framebuffer.width;
+        let gamma = self.gamma;
+        for (i, pixel) in self.target.framebuffer.pixels_mut().iter_mut().enumerate() {
+            let (x, y) = ((i as i32 % width) as f32 + 0.5, (i as i32 / width) as f32 + 0.5);
+            let c = environment.sample(rays(x, y));
+            *pixel = if gamma { color_to_linear(c) } else { c };
+        }
+    }
+
+    pub fn clear_depth(&mut self) {
+        self.target.depth.clear();
+    }
+
+    // Model faces are rasterized in 64x64 tiles on this many threads, the
+    // output is the same for any count. 1 draws directly into the framebuffer
+    pub fn set_threads(&mut self, threads: usize) {
+        assert!(threads > 0, "thread count must be positive");
+        self.threads = threads;
+    }
+
+    // Renders color and depth at factor times the resolution in both
+    // directions, averaged down by to_image. 2 and 4 are the usual choices,
+    // 1 renders at output resolution. Reallocates and so clears both
+    // buffers, on error nothing is changed
+    pub fn set_supersampling(&mut self, factor: u32) -> Result<(), FramebufferError> {
+        assert!(factor > 0, "supersampling factor must be positive");
+        let (width, height) = self.output_size();
+        let mut target = RenderTarget::try_new(width, height, factor)?;
+        if !self.bloom_passes.is_empty() {
+            target.try_alloc_glow()?;
+        }
+
+        let (old, new) = (self.target.supersampling(), factor as i32);
+        let vp = self.viewport;
+        self.viewport = Viewport { x: vp.x / old * new, y: vp.y / old * new, width: vp.width / old * new, height: vp.height / old * new };
+        self.target = target;
+        Ok(())
+    }
+
+    // Bytes taken by the framebuffer, z-buffer and glow buffer
+    pub fn memory_usage(&self) -> usize {
+        self.target.memory_usage()
+    }
+
+    // Size of the images to_image returns
+    pub fn output_size(&self) -> (i32, i32) {
+        (self.target.width(), self.target.height())
+    }
+
+    // Draws into target from now on, with the viewport covering it, and
+    // returns the target drawn into so far. Supersampling follows the
+    // target's, everything else carries over
+    pub fn set_target(&mut self, target: RenderTarget) -> RenderTarget {
+        self.viewport = Viewport { x: 0, y: 0, width: target.framebuffer.width, height: target.framebuffer.height };
+        mem::replace(&mut self.target, target)
+    }
+
+    // Runs draw with target set, then puts the current target and viewport
+    // back and returns target with what draw left in it
+    pub fn with_target<F: FnOnce(&mut Renderer)>(&mut self, target: RenderTarget, draw: F) -> RenderTarget {
+        let viewport = self.viewport;
+        let previous = self.set_target(target);
+        draw(self);
+        let target = self.set_target(previous);
+        self.viewport = viewport;
+        target
+    }
+
+    pub fn target(&self) -> &RenderTarget {
+        &self.target
+    }
+
+    // Alpha below 1.0 makes triangles translucent
+    pub fn set_color(&mut self, color: RgbaColor) {
+        self.color = color;
+    }
+
+    // AlphaBlend only blends fragments with alpha below 1.0, opaque ones replace
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.blend_mode = mode;
+    }
+
+    // Fragments are drawn where func(fragment depth, stored depth) holds. The
+    // default GreaterEqual draws the closest fragment, and the last of equally
+    // close ones
+    pub fn set_depth_test(&mut self, func: DepthFunc) {
+        self.depth_func = func;
+    }
+
+    // Whether fragments passing the depth test update the z-buffer, on by default
+    pub fn set_depth_write(&mut self, enabled: bool) {
+        self.depth_write = enabled;
+    }
+
+    // Depths stored for the near and far end of the view volume, everything
+    // in between is mapped linearly. (1, 0) by default, (0, 1) with
+    // DepthFunc::Less for the smaller is closer convention
+    pub fn set_depth_range(&mut self, near: f32, far: f32) {
+        self.depth_range = (near, far);
+    }
+
+    // Whether blended fragments update the z-buffer after passing the depth test
+    pub fn set_translucent_depth_write(&mut self, enabled: bool) {
+        self.translucent_depth_write = enabled;
+    }
+
+    // Fragments whose texel alpha times base color alpha is below threshold
+    // are discarded without touching the z-buffer, for cutouts like leaves
+    // and fences. 0 turns it off, the default
+    pub fn set_alpha_test(&mut self, threshold: f32) {
+        self.alpha_test = threshold;
+    }
+
+    // Enabling decodes sRGB textures and colors to linear before shading and
+    // encodes the output back to sRGB. Off by default, values are used as stored
+    pub fn set_gamma_correction(&mut self, enabled: bool) {
+        self.gamma = enabled;
+    }
+
+    // Ordered dithering when quantizing the framebuffer in to_image
+    pub fn set_dithering(&mut self, enabled: bool) {
+        self.dither = enabled;
+    }
+
+    // Kernels run in order over every image to_image and to_rgb32 hand out,
+    // at output resolution and on linear colors when gamma correcting. The
+    // framebuffer itself is left alone, an empty slice turns them off
+    pub fn post_process(&mut self, passes: &[Kernel]) {
+        self.post_passes = passes.to_vec();
+    }
+
+    // Framebuffer as it's written out, None when that's the framebuffer as is
+    fn output_framebuffer(&self) -> Option<Framebuffer> {
+        let mut output = match self.target.glow {
+            Some(ref glow) if !self.bloom_passes.is_empty() => {
+                let mut halo = glow.convolve(&self.bloom_passes[0]);
+                for kernel in self.bloom_passes[1..].iter() {
+                    halo = halo.convolve(kernel);
+                }
+                let mut bloomed = self.target.framebuffer.clone();
+                bloomed.add(&halo);
+                Some(bloomed)
+            },
+            _ => None
+        };
+        if let Some(threshold) = self.outline {
+            output = Some(self.outlined(output.as_ref().unwrap_or(&self.target.framebuffer), threshold));
+        }
+        if self.target.supersampling() > 1 {
+            output = Some(output.as_ref().unwrap_or(&self.target.framebuffer).resolve(self.target.supersampling()));
+        }
+        for kernel in self.post_passes.iter() {
+            output = Some(output.as_ref().unwrap_or(&self.target.framebuffer).convolve(kernel));
+        }
+        output
+    }
+
+    // Copy of the framebuffer with the outline of set_outline drawn in black,
+    // supersampling pixels wide so it's about one output pixel
+    fn outlined(&self, base: &Framebuffer, threshold: f32) -> Framebuffer {
+        let mut output = base.clone();
+        let n = self.target.supersampling();
+        let black = RgbaColor::new(0.0, 0.0, 0.0, 1.0);
+        for y in 0..self.target.depth.height {
+            for x in 0..self.target.depth.width {
+                let z = self.target.depth.data()[(x + self.target.depth.width * y) as usize];
+                if !z.is_finite() {
+                    continue;
+                }
+
+                let edge = (1..(n + 1)).any(|d| [(d, 0), (-d, 0), (0, d), (0, -d)].iter().any(|&(dx, dy)| {
+                    match self.target.depth.get(x + dx, y + dy) {
+                        Some(neighbour) => !neighbour.is_finite() || ((z - neighbour).abs() > threshold && self.closer(z, neighbour) == z),
+                        None => false
+                    }
+                }));
+                if edge {
+                    output.set_pixel(x, y, &black);
+                }
+            }
+        }
+        output
+    }
+
+    // 8 bit copy of the framebuffer for writing to a file
+    pub fn to_image(&self) -> TgaImage {
+        match self.output_framebuffer() {
+            Some(output) => output.to_image(self.gamma, self.dither),
+            None => self.target.framebuffer.to_image(self.gamma, self.dither)
+        }
+    }
+
+    // The pixels of to_image as 0x00RRGGBB for showing in a window, written
+    // into buffer so it can be reused from frame to frame
+    pub fn to_rgb32(&self, buffer: &mut Vec<u32>) {
+        match self.output_framebuffer() {
+            Some(output) => output.to_rgb32(self.gamma, self.dither, buffer),
+            None => self.target.framebuffer.to_rgb32(self.gamma, self.dither, buffer)
+        }
+    }
+
+    // Whichever of two stored depths is closer to the viewer
+    fn closer(&self, a: f32, b: f32) -> f32 {
+        let (near, far) = self.depth_range;
+        if !a.is_finite() {
+            b
+        } else if !b.is_finite() {
+            a
+        } else if near > far {
+            a.max(b)
+        } else {
+            a.min(b)
+        }
+    }
+
+    // Closest depth per output pixel, -inf where nothing was drawn
+    fn resolved_depth(&self) -> Vec<f32> {
+        let n = self.target.supersampling();
+        if n == 1 {
+            return self.target.depth.data().to_vec();
+        }
+
+        let (width, height) = self.output_size();
+        let mut depth = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let mut z = f32::NEG_INFINITY;
+                for sy in 0..n {
+                    for sx in 0..n {
+                        z = self.closer(z, self.target.depth.data()[(x * n + sx + self.target.depth.width * (y * n + sy)) as usize]);
+                    }
+                }
+                depth.push(z);
+            }
+        }
+        depth
+    }
+
+    // The z-buffer at framebuffer resolution, supersampling times the
+    // output size, for occlusion queries
+    pub fn depth_buffer(&self) -> &DepthBuffer {
+        &self.target.depth
+    }
+
+    // Z-buffer remapped to grayscale, see depth_to_image
+    pub fn depth_image(&self) -> TgaImage {
+        let (width, height) = self.output_size();
+        depth_to_image(&self.resolved_depth(), width, height)
+    }
+
+    // Stored depth, None outside the output image and where nothing was
+    // drawn. The closest sample when supersampling
+    pub fn depth_at(&self, x: i32, y: i32) -> Option<f32> {
+        let n = self.target.supersampling();
+        let (width, height) = self.output_size();
+        if x < 0 || y < 0 || x >= width || y >= height {
+            return None;
+        }
+
+        let mut z = f32::NEG_INFINITY;
+        for sy in 0..n {
+            for sx in 0..n {
+                z = self.closer(z, self.target.depth.data()[(x * n + sx + self.target.depth.width * (y * n + sy)) as usize]);
+            }
+        }
+        Some(z).filter(|z| z.is_finite())
+    }
+
+    // Screen space ambient occlusion from the z-buffer. Marches samples rays of
+    // radius pixels from every covered pixel and averages how much of the
+    // hemisphere the steepest depth rise along each leaves open. White is
+    // unoccluded, background pixels are black
+    pub fn ambient_occlusion(&self, samples: u32, radius: f32) -> TgaImage {
+        let (width, height) = self.output_size();
+        let zbuffer = self.resolved_depth();
+        let half_pi = f32::consts::PI / 2.0;
+        // Depth in pixels, so slopes are measured in the same units both
+        // ways. Positive towards the viewer
+        let (near, far) = self.depth_range;
+        let depth_scale = (self.viewport.width.min(self.viewport.height) / self.target.supersampling()) as f32 / (near - far);
+        let mut image = TgaImage::new(width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let z = zbuffer[(x + width * y) as usize];
+                if !z.is_finite() {
+                    continue;
+                }
+
+                let mut open = 0.0;
+                for s in 0..samples {
+                    let angle = s as f32 * 2.0 * f32::consts::PI / samples as f32;
+                    let (dy, dx) = angle.sin_cos();
+
+                    let mut max_elevation = 0f32;
+                    let mut t = 1.0;
+                    while t <= radius {
+                        let sx = (x as f32 + 0.5 + dx * t).floor() as i32;
+                        let sy = (y as f32 + 0.5 + dy * t).floor() as i32;
+                        if sx < 0 || sy < 0 || sx >= width || sy >= height {
+                            break;
+                        }
+
+                        let sz = zbuffer[(sx + width * sy) as usize];
+                        if sz.is_finite() {
+                            max_elevation = max_elevation.max(((sz - z) * depth_scale / t).atan());
+                        }
+                        t += 1.0;
+                    }
+                    open += half_pi - max_elevation;
+                }
+
+                let v = if samples > 0 { open / (half_pi * samples as f32) } else { 1.0 };
+                image.set_pixel(x, y, &RgbaColor::new(v, v, v, 1.0));
+            }
+        }
+        image
+    }
+
+    pub fn set_model_transform(&mut self, transform: Mat4) {
+        self.model_transform = transform;
+        self.normal_matrix = transform.normal_matrix();
+    }
+
+    // Skips model triangles facing away from the camera, on by default
+    pub fn set_backface_culling(&mut self, enabled: bool) {
+        self.backface_culling = enabled;
+    }
+
+    pub fn set_front_face(&mut self, winding: Winding) {
+        self.front_face = winding;
+    }
+
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = RenderStats::default();
+    }
+
+    // Clipped to the output image. The shorter side spans NDC [-1, 1] and the
+    // longer one proportionally more, so models keep their proportions
+    pub fn set_viewport(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        let (output_width, output_height) = self.output_size();
+        let x0 = x.max(0);
+        let y0 = y.max(0);
+        let x1 = (x + width).min(output_width);
+        let y1 = (y + height).min(output_height);
+        assert!(x1 > x0 && y1 > y0, "viewport must overlap the framebuffer");
+
+        let n = self.target.supersampling();
+        self.viewport = Viewport { x: x0 * n, y: y0 * n, width: (x1 - x0) * n, height: (y1 - y0) * n };
+    }
+
+    // Looks from eye at center. The view is moved back so center ends up at the
+    // origin as with the default camera on +z, keeping the fitted model inside
+    // the depth range. For perspective, set_camera_distance to the eye's distance
+    pub fn set_camera(&mut self, eye: Vec3f, center: Vec3f, up: Vec3f) {
+        let distance = (eye - center).length();
+        self.view = Mat4::translation(Vec3f::new(0.0, 0.0, distance)) * Mat4::lookat(eye, center, up);
+    }
+
+    // Perspective from a camera on the z axis at distance from the center of
+    // the fitted model, which spans [-1, 1]
+    pub fn set_camera_distance(&mut self, distance: f32) {
+        self.projection = Mat4::simple_projection(distance);
+    }
+
+    // Back to the default parallel projection
+    pub fn set_orthographic(&mut self) {
+        self.projection = Mat4::identity();
+    }
+
+    // Replaces the projection of set_camera_distance or set_orthographic.
+    // Models, the view and the viewport stay as they are
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection.matrix();
+    }
+
+    // Centers model at the origin and scales it uniformly to fill [-1, 1] with a small margin
+    pub fn fit_model(&mut self, model: &Model) {
+        let (min, max) = model.bounding_box();
+        let extent = max - min;
+        let largest = extent.x.max(extent.y).max(extent.z);
+
+        self.model_center = (min + max) * 0.5;
+        self.model_scale = if largest > 0.0 { 0.95 * 2.0 / largest } else { 1.0 };
+    }
+
+    // Lines are one framebuffer pixel wide, thinner than an output pixel
+    // when supersampling
+    pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        let n = self.target.supersampling();
+        let (x0, y0, x1, y1) = (x0 * n, y0 * n, x1 * n, y1 * n);
+        if self.viewport.misses_segment(x0 as f32, y0 as f32, x1 as f32, y1 as f32, 0.0) {
+            return;
+        }
+        if x0 == x1 && y0 == y1 {
+            if self.viewport.contains(x0, y0) {
+                self.target.framebuffer.set_pixel(x0, y0, &self.color);
+            }
+            return;
+        }
+        let dx:i32 = x1 - x0;
+        let dy:i32 = y1 - y0;
+
+        let steps = std::cmp::max(dx.abs(), dy.abs());
+        let xs = (dx.abs() as f32 / steps as f32).abs();
+        let ys = (dy.abs() as f32 / steps as f32).abs();
+        let xis:i32 = dx.signum();
+        let yis:i32 = dy.signum();
+        let mut xa = 0f32;
+        let mut ya = 0f32;
+        let mut x = x0;
+        let mut y = y0;
+
+        for _ in 0..steps {
+            if self.viewport.contains(x, y) {
+                self.target.framebuffer.set_pixel(x, y, &self.color);
+            }
+
+            xa += xs;
+            if xa > 0.5 { x += xis; xa -= 1.0 }
+            ya += ys;
+            if ya > 0.5 { y += yis; ya -= 1.0 }
+        }
+
+        if self.viewport.contains(x, y) {
+            self.target.framebuffer.set_pixel(x, y, &self.color);
+        }
+    }
+
+    // Fills the width wide rectangle centered on the segment, with flat ends
+    // at the endpoints. Pixels count when their center is inside, the far
+    // side excluded, so a horizontal line through pixel centers covers
+    // exactly width rows
+    pub fn line_thick(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, width: f32) {
+        let n = self.target.supersampling() as f32;
+        let (a, b, half) = (Vec2f::new(x0 * n, y0 * n), Vec2f::new(x1 * n, y1 * n), width * n / 2.0);
+        if self.viewport.misses_segment(a.x, a.y, b.x, b.y, half + 1.0) {
+            return;
+        }
+
+        let d = b - a;
+        let length = (d.x * d.x + d.y * d.y).sqrt();
+        if length == 0.0 {
+            return;
+        }
+        let dir = d * (1.0 / length);
+
+        let vp = self.viewport;
+        let xmin = (a.x.min(b.x) - half).floor().max(vp.x as f32) as i32;
+        let ymin = (a.y.min(b.y) - half).floor().max(vp.y as f32) as i32;
+        let xmax = (a.x.max(b.x) + half).ceil().min((vp.x + vp.width - 1) as f32) as i32;
+        let ymax = (a.y.max(b.y) + half).ceil().min((vp.y + vp.height - 1) as f32) as i32;
+
+        for y in ymin..(ymax + 1) {
+            for x in xmin..(xmax + 1) {
+                let p = Vec2f::new(x as f32 + 0.5, y as f32 + 0.5) - a;
+                let along = p.x * dir.x + p.y * dir.y;
+                let across = p.y * dir.x - p.x * dir.y;
+                if along >= 0.0 && along < length && across >= -half && across < half {
+                    self.target.framebuffer.set_pixel(x, y, &self.color);
+                }
+            }
+        }
+    }
+
+    // Blends current color over the framebuffer pixel proportionally to coverage
+    fn plot_aa(&mut self, x: i32, y: i32, coverage: f32) {
+        if !self.viewport.contains(x, y) {
+            return;
+        }
+
+        let mut c = self.color;
+        c.a *= coverage;
+        self.target.framebuffer.set_pixel_blend(x, y, &c);
+    }
+
+    // Xiaolin Wu's anti-aliased line
+    pub fn line_aa(&mut self, x0: f32, y0: f32, x1: f32, y1: f32) {
+        let n = self.target.supersampling() as f32;
+        let (x0, y0, x1, y1) = (x0 * n, y0 * n, x1 * n, y1 * n);
+        // Coverage reaches the pixel past the line's rounded position
+        if self.viewport.misses_segment(x0, y0, x1, y1, 2.0) {
+            return;
+        }
+        if x0.round() == x1.round() && y0.round() == y1.round() {
+            self.plot_aa(x0.round() as i32, y0.round() as i32, 1.0);
+            return;
+        }
+
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        let (mut x0, mut y0, mut x1, mut y1) = if steep { (y0, x0, y1, x1) } else { (x0, y0, x1, y1) };
+        if x0 > x1 {
+            std::mem::swap(&mut x0, &mut x1);
+            std::mem::swap(&mut y0, &mut y1);
+        }
+
+        let dx = x1 - x0;
+        let dy = y1 - y0;
+        let gradient = if dx == 0.0 { 1.0 } else { dy / dx };
+
+        let fpart = |v: f32| v - v.floor();
+
+        // Plots in the non-swapped coordinate space
+        let plot = |r: &mut Renderer, x: i32, y: i32, c: f32| {
+            if steep { r.plot_aa(y, x, c) } else { r.plot_aa(x, y, c) }
+        };
+
+        // First endpoint
+        let xend = x0.round();
+        let yend = y0 + gradient * (xend - x0);
+        let xgap = 1.0 - fpart(x0 + 0.5);
+        let xpxl0 = xend as i32;
+        let ypxl0 = yend.floor() as i32;
+        plot(self, xpxl0, ypxl0, (1.0 - fpart(yend)) * xgap);
+        plot(self, xpxl0, ypxl0 + 1, fpart(yend) * xgap);
+        let mut intery = yend + gradient;
+
+        // Second endpoint
+        let xend = x1.round();
+        let yend = y1 + gradient * (xend - x1);
+        let xgap = fpart(x1 + 0.5);
+        let xpxl1 = xend as i32;
+        let ypxl1 = yend.floor() as i32;
+        plot(self, xpxl1, ypxl1, (1.0 - fpart(yend)) * xgap);
+        plot(self, xpxl1, ypxl1 + 1, fpart(yend) * xgap);
+
+        for x in (xpxl0 + 1)..xpxl1 {
+            let y = intery.floor();
+            plot(self, x, y as i32, 1.0 - (intery - y));
+            plot(self, x, y as i32 + 1, intery - y);
+            intery += gradient;
+        }
+    }
+
+    // Framebuffer pixels x0 to x1, not including x1, of row y clipped to the
+    // viewport. Wide integers so scaled coordinates far outside can't overflow
+    fn span(&mut self, y: i64, x0: i64, x1: i64, color: &RgbaColor) {
+        let vp = self.viewport;
+        if y < vp.y as i64 || y >= (vp.y + vp.height) as i64 {
+            return;
+        }
+        let (x0, x1) = (x0.max(vp.x as i64), x1.min((vp.x + vp.width) as i64));
+        for x in x0..x1 {
+            self.target.framebuffer.set_pixel(x as i32, y as i32, color);
+        }
+    }
+
+    // The 2D primitives below draw over whatever is in the framebuffer,
+    // without testing or writing depth, and are clipped to the viewport.
+    // Coordinates are output pixels, scaled when supersampling while
+    // outlines stay one framebuffer pixel wide like line
+
+    // Pixels x to x + w - 1 and y to y + h - 1
+    pub fn fill_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: RgbaColor) {
+        if w <= 0 || h <= 0 {
+            return;
+        }
+        let n = self.target.supersampling() as i64;
+        let (x0, y0) = (x as i64 * n, y as i64 * n);
+        let (x1, y1) = ((x as i64 + w as i64) * n, (y as i64 + h as i64) * n);
+        let vp = self.viewport;
+        for row in y0.max(vp.y as i64)..y1.min((vp.y + vp.height) as i64) {
+            self.span(row, x0, x1, &color);
+        }
+    }
+
+    // Border pixels of fill_rect's rectangle
+    pub fn draw_rect(&mut self, x: i32, y: i32, w: i32, h: i32, color: RgbaColor) {
+        if w <= 0 || h <= 0 {
+            return;
+        }
+        let n = self.target.supersampling() as i64;
+        let (x0, y0) = (x as i64 * n, y as i64 * n);
+        let (x1, y1) = ((x as i64 + w as i64) * n, (y as i64 + h as i64) * n);
+        self.span(y0, x0, x1, &color);
+        self.span(y1 - 1, x0, x1, &color);
+        let vp = self.viewport;
+        for row in (y0 + 1).max(vp.y as i64)..(y1 - 1).min((vp.y + vp.height) as i64) {
+            self.span(row, x0, x0 + 1, &color);
+            self.span(row, x1 - 1, x1, &color);
+        }
+    }
+
+    // Steps of the midpoint circle algorithm through the octant from
+    // (r, 0) to the diagonal, calling f with every (x, y) on the way
+    fn midpoint_circle<F: FnMut(&mut Renderer, i64, i64)>(&mut self, r: i64, mut f: F) {
+        let (mut x, mut y, mut d) = (r, 0, 1 - r);
+        while x >= y {
+            f(self, x, y);
+            y += 1;
+            if d < 0 {
+                d += 2 * y + 1;
+            } else {
+                x -= 1;
+                d += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    // Center in framebuffer pixels and radius scaled for supersampling, None
+    // for negative radii
+    fn scaled_circle(&self, cx: i32, cy: i32, r: i32) -> Option<(i64, i64, i64)> {
+        if r < 0 {
+            return None;
+        }
+        let n = self.target.supersampling() as i64;
+        Some((cx as i64 * n + n / 2, cy as i64 * n + n / 2, r as i64 * n))
+    }
+
+    // Disc of radius r pixels around pixel (cx, cy), about pi r^2 pixels
+    pub fn fill_circle(&mut self, cx: i32, cy: i32, r: i32, color: RgbaColor) {
+        let (cx, cy, r) = match self.scaled_circle(cx, cy, r) {
+            Some(circle) => circle,
+            None => return
+        };
+        let vp = self.viewport;
+        if cx + r < vp.x as i64 || cx - r >= (vp.x + vp.width) as i64 || cy + r < vp.y as i64 || cy - r >= (vp.y + vp.height) as i64 {
+            return;
+        }
+        self.midpoint_circle(r, |renderer, x, y| {
+            renderer.span(cy + y, cx - x, cx + x + 1, &color);
+            renderer.span(cy - y, cx - x, cx + x + 1, &color);
+            renderer.span(cy + x, cx - y, cx + y + 1, &color);
+            renderer.span(cy - x, cx - y, cx + y + 1, &color);
+        });
+    }
+
+    // Outline of fill_circle's disc
+    pub fn draw_circle(&mut self, cx: i32, cy: i32, r: i32, color: RgbaColor) {
+        let (cx, cy, r) = match self.scaled_circle(cx, cy, r) {
+            Some(circle) => circle,
+            None => return
+        };
+        let vp = self.viewport;
+        if cx + r < vp.x as i64 || cx - r >= (vp.x + vp.width) as i64 || cy + r < vp.y as i64 || cy - r >= (vp.y + vp.height) as i64 {
+            return;
+        }
+        self.midpoint_circle(r, |renderer, x, y| {
+            for &(px, py) in [(x, y), (y, x), (-y, x), (-x, y), (-x, -y), (-y, -x), (y, -x), (x, -y)].iter() {
+                renderer.span(cy + py, cx + px, cx + px + 1, &color);
+            }
+        });
+    }
+
+    // Scanline fill with the even-odd rule, so self-intersecting and concave
+    // polygons work as well as convex ones. Pixels count when their center is
+    // inside, the right and top sides excluded like triangles. Polygons with
+    // fewer than 3 or non-finite points draw nothing
+    pub fn fill_polygon(&mut self, points: &[Vec2f], color: RgbaColor) {
+        if points.len() < 3 || points.iter().any(|p| !p.x.is_finite() || !p.y.is_finite()) {
+            return;
+        }
+        let n = self.target.supersampling() as f32;
+        let points: Vec<Vec2f> = points.iter().map(|&p| p * n).collect();
+        let (ymin, ymax) = points.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), p| (lo.min(p.y), hi.max(p.y)));
+
+        let vp = self.viewport;
+        let y0 = (ymin - 0.5).ceil().max(vp.y as f32) as i32;
+        let y1 = (ymax - 0.5).ceil().min((vp.y + vp.height) as f32) as i32;
+        let mut crossings = Vec::new();
+        for y in y0..y1 {
+            // Edges cross the row's center line going up or down, half open
+            // so a vertex on it counts once
+            let center = y as f32 + 0.5;
+            crossings.clear();
+            for (i, a) in points.iter().enumerate() {
+                let b = points[(i + 1) % points.len()];
+                if (a.y <= center) != (b.y <= center) {
+                    crossings.push(a.x + (center - a.y) / (b.y - a.y) * (b.x - a.x));
+                }
+            }
+            crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+            for pair in crossings.chunks(2) {
+                if pair.len() == 2 {
+                    // Centers from pair[0] up to pair[1], as floats clamped
+                    // to the viewport before the cast
+                    let clamp = |x: f32| (x - 0.5).ceil().max(vp.x as f32 - 1.0).min((vp.x + vp.width) as f32) as i64;
+                    self.span(y as i64, clamp(pair[0]), clamp(pair[1]), &color);
+                }
+            }
+        }
+    }
+
+    // Text in the built-in 8x8 font with its top left pixel at x, y, every
+    // font pixel drawn as a scale x scale square. Lines go down the image,
+    // y decreasing, one glyph height apart. Clipped like fill_rect
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, color: RgbaColor, scale: u32) {
+        let scale = scale as i64;
+        let (mut line, mut column) = (0, 0);
+        for c in text.chars() {
+            if c == '\n' {
+                line += 1;
+                column = 0;
+                continue;
+            }
+
+            let left = x as i64 + column * text::GLYPH_WIDTH as i64 * scale;
+            let top = y as i64 - line * text::GLYPH_HEIGHT as i64 * scale;
+            column += 1;
+            for (row, bits) in text::glyph(c).iter().enumerate() {
+                for bit in 0..text::GLYPH_WIDTH {
+                    let (px, py) = (left + bit as i64 * scale, top - (row as i64 + 1) * scale + 1);
+                    // Anything out of i32 is offscreen
+                    if bits & (1 << bit) != 0 && px as i32 as i64 == px && py as i32 as i64 == py {
+                        self.fill_rect(px as i32, py as i32, scale as i32, scale as i32, color);
+                    }
+                }
+            }
+        }
+    }
+
+    // Screen space triangle shaded like draw_model with the renderer's diffuse
+    // texture. Vertex z is tested against the z-buffer as is
+    pub fn triangle(&mut self, v0: Vertex, v1: Vertex, v2: Vertex) {
+        let diffuse = self.diffuse.take();
+        let specular = self.specular.take();
+        let shadow = self.shadow.take();
+        {
+            let mut shader = self.standard_shader(None, diffuse.as_ref(), specular.as_ref(), shadow.as_ref());
+            shader.vertices = [v0, v1, v2];
+            shader.terms = vec![[v0.i, v1.i, v2.i]; self.lights.len()];
+            shader.texture = diffuse.as_ref();
+
+            let n = self.target.supersampling() as f32;
+            let scale = |p: Vec3f| Vec3f::new(p.x * n, p.y * n, p.z);
+            let triangle = Triangle {
+                face: 0,
+                p: [scale(v0.p), scale(v1.p), scale(v2.p)],
+                bary: [Vec3f::new(1.0, 0.0, 0.0), Vec3f::new(0.0, 1.0, 0.0), Vec3f::new(0.0, 0.0, 1.0)],
+                inv_w: [1.0; 3]
+            };
+            self.stats.submitted += 1;
+            if triangle.bounds(self.viewport_rect()).is_none() {
+                self.stats.clipped += 1;
+            } else if triangle.is_degenerate() {
+                self.stats.degenerate += 1;
+            } else {
+                self.stats.drawn += 1;
+                shader.begin_triangle(&triangle.screen(), &triangle.bary);
+                self.rasterize(&shader, &triangle);
+            }
+        }
+        self.diffuse = diffuse;
+        self.specular = specular;
+        self.shadow = shadow;
+    }
+
+    fn raster_state(&self) -> RasterState {
+        RasterState {
+            blend_mode: self.blend_mode,
+            depth_func: self.depth_func,
+            depth_write: self.depth_write,
+            translucent_depth_write: self.translucent_depth_write,
+            depth_only: self.depth_only
+        }
+    }
+
+    fn viewport_rect(&self) -> Rect {
+        let vp = self.viewport;
+        Rect { x0: vp.x, y0: vp.y, x1: vp.x + vp.width, y1: vp.y + vp.height }
+    }
+
+    // Rasterizes straight into the framebuffer
+    fn rasterize<S: Shader>(&mut self, shader: &S, triangle: &Triangle) {
+        let state = self.raster_state();
+        let mut target = Target {
+            x: 0,
+            y: 0,
+            stride: self.target.framebuffer.width,
+            clip: self.viewport_rect(),
+            color: self.target.framebuffer.pixels_mut(),
+            depth: self.target.depth.data_mut()
+        };
+        self.stats.fragments += raster::rasterize(&mut target, &state, shader, triangle);
+    }
+
+    // Draws model placed by transform, applied after fitting and on top of
+    // set_model_transform. Normals go through the matching normal matrix.
+    // Models drawn one after another share the z-buffer, so drawing the same
+    // model with different transforms renders instances that occlude each other
+    pub fn draw_model(&mut self, model: &Model, transform: &Mat4) {
+        self.draw_model_faces(model, transform, 0..model.faces.len());
+    }
+
+    // draw_model restricted to the faces of the named groups or objects, only
+    // those cast shadows
+    pub fn draw_model_groups(&mut self, model: &Model, transform: &Mat4, groups: &[&str]) {
+        let faces: Vec<usize> = model.groups.iter()
+            .filter(|g| groups.contains(&g.name.as_str()))
+            .flat_map(|g| g.faces.iter().cloned())
+            .collect();
+        self.draw_model_faces(model, transform, faces.into_iter());
+    }
+
+    fn draw_model_faces<I: Iterator<Item=usize> + Clone>(&mut self, model: &Model, transform: &Mat4, faces: I) {
+        let previous = self.model_transform;
+        self.set_model_transform(*transform * previous);
+        if let Some(ref mut shadow) = self.shadow {
+            shadow.clear();
+        }
+        if self.shadow.is_some() {
+            self.render_shadow_pass(model, faces.clone());
+        }
+        self.draw_standard(model, faces);
+        self.set_model_transform(previous);
+    }
+
+    // Draws every node of scene like draw_model with the node's transform,
+    // texture, color and shading in place of the renderer's, which are left
+    // as they were. Nodes share the depth buffer, clear_color set clears
+    // both buffers first. With shadows every node goes into the shadow map
+    // before any is drawn, so nodes shadow each other
+    pub fn draw_scene(&mut self, scene: &Scene) {
+        if let Some(color) = scene.clear_color {
+            self.clear(color);
+        }
+
+        let (color, shading, previous) = (self.color, self.shading, self.model_transform);
+        if let Some(ref mut shadow) = self.shadow {
+            shadow.clear();
+        }
+        if self.shadow.is_some() {
+            for node in scene.nodes() {
+                self.set_model_transform(node.transform * previous);
+                self.render_shadow_pass(&node.model, 0..node.model.faces.len());
+            }
+        }
+        for node in scene.nodes() {
+            self.color = node.material.color;
+            self.shading = node.material.shading.unwrap_or(shading);
+            self.set_model_transform(node.transform * previous);
+            let diffuse = node.material.diffuse.map(|id| scene.texture(id));
+            self.draw_standard_with(&*node.model, 0..node.model.faces.len(), diffuse, scene.textures());
+        }
+        self.color = color;
+        self.shading = shading;
+        self.set_model_transform(previous);
+    }
+
+    // Some(color) has render_turntable write the frame number into the top
+    // left corner of every frame
+    pub fn set_frame_label(&mut self, color: Option<RgbaColor>) {
+        self.frame_label = color;
+    }
+
+    // Renders frames images of model turning once around the y axis, 360 / frames
+    // degrees apart, as out_dir/output_0001.tga onwards. Every frame starts with
+    // clear, so the buffers are reused. Stops at the first frame that fails
+    pub fn render_turntable(&mut self, model: &Model, frames: u32, out_dir: &Path) -> Result<(), FrameError> {
+        for frame in 0..frames {
+            let angle = 2.0 * PI * frame as f32 / frames as f32;
+            self.clear(RgbaColor::new(0.0, 0.0, 0.0, 0.0));
+            self.draw_model(model, &Mat4::rotation_y(angle));
+            if let Some(color) = self.frame_label {
+                let (_, height) = self.output_size();
+                self.draw_text(FRAME_LABEL_MARGIN, height - 1 - FRAME_LABEL_MARGIN, &(frame + 1).to_string(), color, 1);
+            }
+
+            let path = out_dir.join(format!("output_{:04}.tga", frame + 1));
+            if let Err(e) = self.to_image().save(&path) {
+                return Err(FrameError { frame: frame + 1, path: path, error: e });
+            }
+        }
+        Ok(())
+    }
+
+    // Draws model through a custom shader, which is responsible for the
+    // model's placement, lighting and texturing. Culling, clipping, the
+    // viewport, depth test and blending work as for draw_model. With more
+    // than one thread every thread draws with its own clone of shader and
+    // calls vertex again for the faces it rasterizes
+    pub fn draw_model_with_shader<S: Shader + Clone + Send>(&mut self, model: &Model, shader: &mut S) {
+        self.draw_faces(shader, 0..model.faces.len());
+    }
+
+    // Transform custom shaders can use to match the renderer's camera
+    pub fn view_projection(&self) -> Mat4 {
+        self.projection * self.view
+    }
+
+    // Draws face edges with the current color, projected like draw_model.
+    // Shared edges are drawn twice
+    pub fn draw_model_wireframe(&mut self, model: &Model) {
+        let extent = self.clip_extent();
+        let mut shader = self.standard_shader(Some(model), None, None, None);
+
+        for face in 0..model.faces.len() {
+            let mut clip_coords = [Vec4f::new(0.0, 0.0, 0.0, 1.0); 3];
+            for (i, coords) in clip_coords.iter_mut().enumerate() {
+                *coords = shader.vertex(face, i);
+            }
+
+            for i in 0..3 {
+                if let Some((a, b)) = clip_segment(clip_coords[i], clip_coords[(i + 1) % 3], extent) {
+                    let a = self.to_screen(a.to_vec3());
+                    let b = self.to_screen(b.to_vec3());
+                    self.depth_line(a, b);
+                }
+            }
+        }
+    }
+
+    // Some(bias) hides wireframe and point pixels farther than the z-buffer
+    // by more than bias, in the units of set_depth_range, for overlaying a
+    // filled render
+    pub fn set_wireframe_depth_test(&mut self, bias: Option<f32>) {
+        self.wireframe_depth_bias = bias;
+    }
+
+    pub fn set_point_color(&mut self, color: PointColor) {
+        self.point_color = color;
+    }
+
+    // Draws every vertex of model, placed like draw_model, as a size x size
+    // square, or a disc of that diameter from size 4 up. Vertices behind the
+    // camera or beyond the near and far planes are skipped. Depth tested like
+    // the wireframe and doesn't write the z-buffer
+    pub fn draw_points(&mut self, model: &Model, size: i32) {
+        let shader = self.standard_shader(Some(model), None, None, None);
+        let view_projection = self.projection * self.view;
+
+        // Normal of the first face corner using each vertex
+        let mut normals = vec![None; model.vertices.len()];
+        for face in model.faces.iter() {
+            for corner in 0..3 {
+                let (v, n) = (face[corner * 3] as usize, face[corner * 3 + 2]);
+                if normals[v].is_none() && n >= 0 {
+                    normals[v] = model.normals.get(n as usize).cloned();
+                }
+            }
+        }
+
+        let size = size.max(1) * self.target.supersampling();
+        for (i, &vertex) in model.vertices.iter().enumerate() {
+            let clip = view_projection * shader.world_position(vertex).to_point();
+            let ndc = clip.to_vec3();
+            if clip.w <= 0.0 || ndc.z < -1.0 || ndc.z > 1.0 {
+                continue;
+            }
+            let p = self.to_screen(ndc);
+            if self.viewport.misses_segment(p.x, p.y, p.x, p.y, size as f32) {
+                continue;
+            }
+
+            let color = match (self.point_color, normals[i]) {
+                (PointColor::Normal, Some(n)) => {
+                    let n = (self.normal_matrix * n).normalized();
+                    RgbaColor::new(n.x * 0.5 + 0.5, n.y * 0.5 + 0.5, n.z * 0.5 + 0.5, 1.0)
+                },
+                (PointColor::Depth, _) => {
+                    let v = (ndc.z + 1.0) / 2.0;
+                    RgbaColor::new(v, v, v, 1.0)
+                },
+                _ => self.color
+            };
+            self.splat(p, size, &color);
+        }
+    }
+
+    // Whether the wireframe depth test hides a line or point pixel at depth z
+    fn overlay_hidden(&self, x: i32, y: i32, z: f32) -> bool {
+        match self.wireframe_depth_bias {
+            Some(bias) => {
+                let stored = self.target.depth.data()[(x + self.target.depth.width * y) as usize];
+                let biased = self.closer(z + bias, z - bias);
+                self.closer(biased, stored) != biased
+            },
+            None => false
+        }
+    }
+
+    // Square or disc of size framebuffer pixels around p, at p's depth
+    fn splat(&mut self, p: Vec3f, size: i32, color: &RgbaColor) {
+        let (x0, y0) = (p.x.floor() as i32 - (size - 1) / 2, p.y.floor() as i32 - (size - 1) / 2);
+        let center = (size - 1) as f32 / 2.0;
+        let radius = size as f32 / 2.0;
+        for j in 0..size {
+            for i in 0..size {
+                let (dx, dy) = (i as f32 - center, j as f32 - center);
+                if size >= 4 && dx * dx + dy * dy > radius * radius {
+                    continue;
+                }
+                let (x, y) = (x0 + i, y0 + j);
+                if !self.viewport.contains(x, y) {
+                    continue;
+                }
+
+                if self.overlay_hidden(x, y, p.z) {
+                    continue;
+                }
+                self.target.framebuffer.set_pixel(x, y, color);
+            }
+        }
+    }
+
+    // Line between screen space points interpolating depth, doesn't write the z-buffer
+    fn depth_line(&mut self, a: Vec3f, b: Vec3f) {
+        if self.viewport.misses_segment(a.x, a.y, b.x, b.y, 0.0) {
+            return;
+        }
+        let steps = (b.x - a.x).abs().max((b.y - a.y).abs()).ceil().max(1.0) as i32;
+        for step in 0..(steps + 1) {
+            let p = lerp(a, b, step as f32 / steps as f32);
+            let (x, y) = (p.x.floor() as i32, p.y.floor() as i32);
+            if !self.viewport.contains(x, y) {
+                continue;
+            }
+
+            if self.overlay_hidden(x, y, p.z) {
+                continue;
+            }
+            self.target.framebuffer.set_pixel(x, y, &self.color);
+        }
+    }
+
+    // Depth of the model's faces seen from the light, through a square
+    // depth-only renderer using the same model placement
+    fn render_shadow_pass<I: Iterator<Item=usize>>(&mut self, model: &Model, faces: I) {
+        let mut shadow = match self.shadow.take() {
+            Some(shadow) => shadow,
+            None => return
+        };
+
+        let mut pass = Renderer::new(shadow.size, shadow.size);
+        pass.depth_only = true;
+        pass.depth_levels = (1 << 20) as f32;
+        pass.threads = self.threads;
+        pass.backface_culling = false;
+        pass.view = shadow.light_view;
+        pass.model_center = self.model_center;
+        pass.model_scale = self.model_scale;
+        pass.model_transform = self.model_transform;
+        pass.normal_matrix = self.normal_matrix;
+        pass.draw_standard(model, faces);
+
+        for y in 0..shadow.size {
+            for x in 0..shadow.size {
+                let z = pass.target.depth.data()[(x + shadow.size * y) as usize];
+                if z.is_finite() {
+                    shadow.store(x, y, z * 2.0 - 1.0);
+                }
+            }
+        }
+        self.shadow = Some(shadow);
+    }
+
+    // Draws a deduplicated mesh with the renderer's own diffuse texture and color
+    pub fn draw_indexed(&mut self, mesh: &IndexedMesh) {
+        self.draw_standard(mesh, 0..mesh.face_count());
+    }
+
+    // StandardShader with the renderer's current settings
+    fn standard_shader<'a>(&self, mesh: Option<&'a dyn Mesh>, diffuse: Option<&'a Texture>, specular: Option<&'a Texture>, shadow: Option<&'a ShadowMap>) -> StandardShader<'a> {
+        let empty = Vertex { p: Vec3f::new(0.0, 0.0, 0.0), t: Vec2f::new(0.0, 0.0), i: 0.0, n: Vec3f::new(0.0, 0.0, 0.0), world: Vec3f::new(0.0, 0.0, 0.0), c: self.color };
+        return StandardShader {
+            mesh: mesh,
+            shading: self.shading,
+            color: self.color,
+            model_center: self.model_center,
+            model_scale: self.model_scale,
+            model_transform: self.model_transform,
+            normal_matrix: self.normal_matrix,
+            view_projection: self.projection * self.view,
+            lights: self.lights.clone(),
+            two_sided: self.two_sided_lighting,
+            to_eye: Vec3f::new(self.view.m[2][0], self.view.m[2][1], self.view.m[2][2]).normalized(),
+            diffuse: diffuse,
+            textures: &[],
+            specular: specular,
+            specular_intensity: self.specular_intensity,
+            glow: None,
+            glow_only: false,
+            ambient: self.ambient,
+            shadow: shadow,
+            shadow_factor: self.shadow_factor,
+            environment: None,
+            reflectivity: self.reflectivity,
+            fog: self.fog.map(|fog| Fog { color: if self.gamma { color_to_linear(fog.color) } else { fog.color }, mode: fog.mode }),
+            view_depth: self.view_depth(),
+            filter: self.filter,
+            wrap: self.wrap,
+            gamma: self.gamma,
+            alpha_test: self.alpha_test,
+            toon_thresholds: self.toon_thresholds.clone(),
+            vertices: [empty; 3],
+            clip: [Vec4f::new(0.0, 0.0, 0.0, 1.0); 3],
+            terms: Vec::new(),
+            texture: None,
+            level: 0
+        };
+    }
+
+    // Draws faces of mesh through StandardShader. Translucent faces, see
+    // StandardShader::is_translucent, are held back until the opaque ones are
+    // drawn and then drawn farthest first by the view space depth of their
+    // centroids. That only sorts them within the call, models with
+    // translucent parts should be drawn last
+    fn draw_standard<I: Iterator<Item=usize>>(&mut self, mesh: &dyn Mesh, faces: I) {
+        let diffuse = self.diffuse.take();
+        let textures = mem::take(&mut self.textures);
+        self.draw_standard_with(mesh, faces, diffuse.as_ref(), &textures);
+        self.diffuse = diffuse;
+        self.textures = textures;
+    }
+
+    // draw_standard with diffuse in place of the renderer's texture and face
+    // textures indexing textures
+    fn draw_standard_with<I: Iterator<Item=usize>>(&mut self, mesh: &dyn Mesh, faces: I, diffuse: Option<&Texture>, textures: &[Texture]) {
+        let specular = self.specular.take();
+        let shadow = self.shadow.take();
+        let environment = self.environment.take();
+        let glow = self.glow.take();
+        let faces: Vec<usize> = faces.collect();
+        {
+            let mut shader = self.standard_shader(Some(mesh), diffuse, specular.as_ref(), shadow.as_ref());
+            shader.environment = environment.as_ref();
+            shader.glow = glow.as_ref();
+            shader.textures = textures;
+            let (opaque, translucent): (Vec<usize>, Vec<usize>) = faces.iter().partition(|&&face| !shader.is_translucent(face));
+            self.draw_faces(&mut shader, opaque.into_iter());
+
+            if !translucent.is_empty() {
+                // Looking down -z, so the farthest have the smallest z
+                let mut sorted: Vec<(f32, usize)> = translucent.into_iter().map(|face| {
+                    let centroid = (0..3).fold(Vec3f::new(0.0, 0.0, 0.0), |sum, corner| sum + shader.world_position(mesh.corner(face, corner).position)) / 3.0;
+                    ((self.view * centroid).z, face)
+                }).collect();
+                sorted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+                self.draw_faces(&mut shader, sorted.into_iter().map(|(_, face)| face));
+            }
+
+            if !self.bloom_passes.is_empty() && !self.depth_only {
+                self.draw_glow(&mut shader, &faces);
+            }
+        }
+        self.glow = glow;
+        self.specular = specular;
+        self.shadow = shadow;
+        self.environment = environment;
+    }
+
+    // Draws the glow of faces into the target's glow buffer where they're
+    // what the depth buffer says is visible, after they were drawn. Faces
+    // without glow draw black, hiding the glow of what they cover
+    fn draw_glow(&mut self, shader: &mut StandardShader, faces: &[usize]) {
+        // Targets swapped in by set_target get theirs here, those without
+        // room for one draw no glow
+        if self.target.try_alloc_glow().is_err() {
+            return;
+        }
+        let mut glow = self.target.glow.take().unwrap();
+        mem::swap(&mut self.target.framebuffer, &mut glow);
+
+        // The drawn fragments pass with their depth already stored
+        let (depth_func, depth_write, blend_mode, stats) = (self.depth_func, self.depth_write, self.blend_mode, self.stats);
+        self.depth_func = match depth_func {
+            DepthFunc::Greater => DepthFunc::GreaterEqual,
+            DepthFunc::Less => DepthFunc::LessEqual,
+            func => func
+        };
+        self.depth_write = false;
+        self.blend_mode = BlendMode::Replace;
+        shader.glow_only = true;
+        self.draw_faces(shader, faces.iter().cloned());
+
+        shader.glow_only = false;
+        self.depth_func = depth_func;
+        self.depth_write = depth_write;
+        self.blend_mode = blend_mode;
+        self.stats = stats;
+        mem::swap(&mut self.target.framebuffer, &mut glow);
+        self.target.glow = Some(glow);
+    }
+
+    // World space direction the camera looks through framebuffer position
+    // (x, y), found by unprojecting two depths of it
+    fn camera_rays(&self) -> impl Fn(f32, f32) -> Vec3f {
+        let inverse = (self.projection * self.view).inverse().unwrap_or(Mat4::identity());
+        let vp = self.viewport;
+        let (aspect_x, aspect_y) = self.aspect();
+        let (half_width, half_height) = (vp.width as f32 / 2.0, vp.height as f32 / 2.0);
+        let (x0, y0) = (vp.x as f32, vp.y as f32);
+
+        move |x: f32, y: f32| {
+            let ndc_x = ((x - x0) / half_width - 1.0) / aspect_x;
+            let ndc_y = ((y - y0) / half_height - 1.0) / aspect_y;
+            let far = (inverse * Vec4f::new(ndc_x, ndc_y, 0.0, 1.0)).to_vec3();
+            let near = (inverse * Vec4f::new(ndc_x, ndc_y, 0.5, 1.0)).to_vec3();
+            far - near
+        }
+    }
+
+    // Squeezes NDC along the longer side of the viewport so it isn't stretched
+    fn aspect(&self) -> (f32, f32) {
+        let vp = self.viewport;
+        if vp.width > vp.height {
+            (vp.height as f32 / vp.width as f32, 1.0)
+        } else {
+            (1.0, vp.width as f32 / vp.height as f32)
+        }
+    }
+
+    // NDC extents visible in the viewport, for clipping
+    fn clip_extent(&self) -> Vec2f {
+        let (aspect_x, aspect_y) = self.aspect();
+        Vec2f::new(1.0 / aspect_x, 1.0 / aspect_y)
+    }
+
+    // Framebuffer position and z-buffer depth of a point in NDC. NDC z runs
+    // from -1 far to 1 near and is quantized to depth_levels steps first
+    fn to_screen(&self, v: Vec3f) -> Vec3f {
+        let vp = self.viewport;
+        let half_width = (vp.width as f32) / 2.0;
+        let half_height = (vp.height as f32) / 2.0;
+        let (near, far) = self.depth_range;
+        let (aspect_x, aspect_y) = self.aspect();
+        let closeness = ((v.z + 1.0) * self.depth_levels / 2.0).floor() / self.depth_levels;
+
+        Vec3f::new(
+            vp.x as f32 + (v.x * aspect_x + 1.0) * half_width,
+            vp.y as f32 + (v.y * aspect_y + 1.0) * half_height,
+            far + (near - far) * closeness
+        )
+    }
+
+    // Runs the vertex stage for face, clips and culls it and returns the
+    // screen space triangles covering what's left
+    fn project_face<S: Shader>(&mut self, shader: &mut S, face: usize, extent: Vec2f) -> Vec<Triangle> {
+        let mut triangle = [ClipVertex { position: Vec4f::new(0.0, 0.0, 0.0, 1.0), bary: Vec3f::new(0.0, 0.0, 0.0) }; 3];
+        for i in 0..3 {
+            let mut bary = Vec3f::new(0.0, 0.0, 0.0);
+            bary[i] = 1.0;
+            triangle[i] = ClipVertex { position: shader.vertex(face, i), bary: bary };
+        }
+        self.stats.submitted += 1;
+
+        let polygon = clip_polygon(&triangle, extent);
+        if polygon.len() < 3 {
+            self.stats.clipped += 1;
+            return Vec::new();
+        }
+
+        let ndc: Vec<Vec3f> = polygon.iter().map(|v| v.position.to_vec3()).collect();
+        if self.backface_culling {
+            let points: Vec<Vec2f> = ndc.iter().map(|v| Vec2f::new(v.x, v.y)).collect();
+            let area = polygon_area_2d(&points);
+            let front = match self.front_face {
+                Winding::CounterClockwise => area > 0.0,
+                Winding::Clockwise => area < 0.0
+            };
+            if !front {
+                self.stats.culled += 1;
+                return Vec::new();
+            }
+        }
+
+        let screen: Vec<Vec3f> = ndc.iter().map(|v| self.to_screen(*v)).collect();
+
+        // Clipped polygons are convex, a fan covers them
+        let triangles: Vec<Triangle> = (1..(polygon.len() - 1)).map(|i| Triangle {
+            face: face,
+            p: [screen[0], screen[i], screen[i + 1]],
+            bary: [polygon[0].bary, polygon[i].bary, polygon[i + 1].bary],
+            inv_w: [1.0 / polygon[0].position.w, 1.0 / polygon[i].position.w, 1.0 / polygon[i + 1].position.w]
+        }).collect();
+        if triangles.iter().all(|t| t.is_degenerate()) {
+            self.stats.degenerate += 1;
+            return Vec::new();
+        }
+        self.stats.drawn += 1;
+        triangles
+    }
+
+    fn draw_faces<S: Shader + Clone + Send, I: Iterator<Item=usize>>(&mut self, shader: &mut S, faces: I) {
+        if self.threads > 1 {
+            self.draw_faces_tiled(shader, faces);
+            return;
+        }
+
+        let extent = self.clip_extent();
+        for face in faces {
+            for triangle in self.project_face(shader, face, extent) {
+                shader.begin_triangle(&triangle.screen(), &triangle.bary);
+                self.rasterize(shader, &triangle);
+            }
+        }
+    }
+
+    // Projects every face first, then bins the triangles to the tiles their
+    // bounding boxes overlap. Each thread draws a copy of its tiles in
+    // submission order, so results don't depend on scheduling
+    fn draw_faces_tiled<S: Shader + Clone + Send, I: Iterator<Item=usize>>(&mut self, shader: &mut S, faces: I) {
+        let extent = self.clip_extent();
+        let mut triangles = Vec::new();
+        for face in faces {
+            triangles.extend(self.project_face(shader, face, extent));
+        }
+
+        let vp = self.viewport_rect();
+        let stride = self.target.framebuffer.width;
+        let columns = (vp.x1 - vp.x0 + TILE_SIZE - 1) / TILE_SIZE;
+        let mut tiles = Vec::new();
+        for y in (vp.y0..vp.y1).step_by(TILE_SIZE as usize) {
+            for x in (vp.x0..vp.x1).step_by(TILE_SIZE as usize) {
+                let rect = Rect { x0: x, y0: y, x1: (x + TILE_SIZE).min(vp.x1), y1: (y + TILE_SIZE).min(vp.y1) };
+                tiles.push(Tile::new(rect, self.target.framebuffer.pixels(), self.target.depth.data(), stride));
+            }
+        }
+
+        for (i, triangle) in triangles.iter().enumerate() {
+            if let Some(bounds) = triangle.bounds(vp) {
+                for row in ((bounds.y0 - vp.y0) / TILE_SIZE)..((bounds.y1 - 1 - vp.y0) / TILE_SIZE + 1) {
+                    for column in ((bounds.x0 - vp.x0) / TILE_SIZE)..((bounds.x1 - 1 - vp.x0) / TILE_SIZE + 1) {
+                        tiles[(column + columns * row) as usize].triangles.push(i);
+                    }
+                }
+            }
+        }
+
+        // Interleaved so every thread gets a share of the busy middle
+        let mut groups: Vec<Vec<Tile>> = (0..self.threads).map(|_| Vec::new()).collect();
+        for (i, tile) in tiles.into_iter().enumerate() {
+            groups[i % self.threads].push(tile);
+        }
+
+        let state = self.raster_state();
+        let triangles = &triangles;
+        thread::scope(|scope| {
+            for group in groups.iter_mut() {
+                let mut shader = shader.clone();
+                scope.spawn(move || {
+                    for tile in group.iter_mut() {
+                        tile.rasterize(&mut shader, triangles, &state);
+                    }
+                });
+            }
+        });
+
+        for tile in groups.iter().flat_map(|g| g.iter()) {
+            tile.write_back(self.target.framebuffer.pixels_mut(), self.target.depth.data_mut(), stride);
+            self.stats.fragments += tile.fragments;
+        }
+    }
+}
+
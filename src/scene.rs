@@ -0,0 +1,87 @@
+// Models placed with their own transforms and looks, drawn together by
+// Renderer::draw_scene. Models are shared through Rc and textures are held
+// once by the scene, so nodes reuse both without copying
+
+use std::rc::Rc;
+use model::Model;
+use texture::{Texture,TextureId};
+use shader::Shading;
+use tga::RgbaColor;
+use math::Mat4;
+
+// Handles handed out by Scene, only meaningful for the scene that made them
+#[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
+pub struct NodeId(usize);
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub struct NodeMaterial {
+    // Untextured when None, materials of the model's own faces still apply
+    // and their face textures index the scene's
+    pub diffuse: Option<TextureId>,
+    pub color: RgbaColor,
+    // The renderer's shading when None
+    pub shading: Option<Shading>
+}
+
+impl Default for NodeMaterial {
+    // White and untextured
+    fn default() -> NodeMaterial {
+        NodeMaterial { diffuse: None, color: RgbaColor::new(1.0, 1.0, 1.0, 1.0), shading: None }
+    }
+}
+
+pub struct Node {
+    pub model: Rc<Model>,
+    // Applied like draw_model's transform
+    pub transform: Mat4,
+    pub material: NodeMaterial
+}
+
+#[derive(Default)]
+pub struct Scene {
+    nodes: Vec<Node>,
+    textures: Vec<Texture>,
+    // draw_scene clears to it first when set
+    pub clear_color: Option<RgbaColor>
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    pub fn add_texture(&mut self, texture: Texture) -> TextureId {
+        self.textures.push(texture);
+        TextureId(self.textures.len() - 1)
+    }
+
+    pub fn texture(&self, id: TextureId) -> &Texture {
+        &self.textures[id.0]
+    }
+
+    pub fn textures(&self) -> &[Texture] {
+        &self.textures
+    }
+
+    // Nodes are drawn in the order they were added
+    pub fn add(&mut self, model: Rc<Model>, transform: Mat4, material: NodeMaterial) -> NodeId {
+        self.nodes.push(Node { model: model, transform: transform, material: material });
+        NodeId(self.nodes.len() - 1)
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+
+    pub fn set_transform(&mut self, id: NodeId, transform: Mat4) {
+        self.nodes[id.0].transform = transform;
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+}
@@ -0,0 +1,373 @@
+// Programmable stages of the triangle pipeline. For every face the renderer
+// calls vertex for corners 0, 1 and 2 in that order, clips and culls the
+// result and then calls fragment for each covered pixel passing the depth
+// test. Whatever vertex computes for fragment (texture coords, normals and
+// so on) is kept in the shader's own fields
+
+use math::{Vec2f,Vec3f,Vec4f,Mat3,Mat4,barycentric_mix};
+use math::geometry::triangle_area_2d;
+use model::{Mesh,ModelVertex};
+use texture::{Texture,TextureId};
+use tga::{RgbaColor,Filter,Wrap};
+use framebuffer::color_to_linear;
+use shadow::ShadowMap;
+use light::{Light,LightKind};
+use environment::Environment;
+use fog::Fog;
+
+pub trait Shader {
+    // Clip space position of a face corner. After the divide x and y in
+    // [-1, 1] span the shorter side of the viewport and z in [-1, 1] the
+    // depth range, larger z being closer
+    fn vertex(&mut self, face: usize, corner: usize) -> Vec4f;
+
+    // Called before the fragments of every triangle the face is rasterized
+    // as, clipping can split a face into several. screen holds the pixel
+    // coordinates of the triangle's corners and bary their weights relative
+    // to the face's corners
+    fn begin_triangle(&mut self, _screen: &[Vec2f; 3], _bary: &[Vec3f; 3]) {}
+
+    // Color at the given weights of the face's corners, None discards the pixel
+    fn fragment(&self, bary: Vec3f) -> Option<RgbaColor>;
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Shading {
+    Flat,
+    Gouraud,
+    // Lights every pixel with the interpolated vertex normal
+    Phong,
+    // Like Phong with each light's diffuse term snapped to one of bands
+    // levels, see toon_band
+    Toon { bands: u32 }
+}
+
+// Diffuse intensity in [0, 1] snapped to the lower end of the equal-width band
+// it falls in, spread so the first of bands comes out 0 and the last 1. With
+// many bands it approaches the intensity itself
+pub fn toon_band(intensity: f32, bands: u32) -> f32 {
+    if bands < 2 {
+        return 1.0;
+    }
+    let band = (intensity * bands as f32).floor().max(0.0).min((bands - 1) as f32);
+    band / (bands - 1) as f32
+}
+
+// Same with the bands starting at the given ascending intensities instead,
+// thresholds.len() + 1 bands with the first starting at 0
+pub fn toon_band_at(intensity: f32, thresholds: &[f32]) -> f32 {
+    if thresholds.is_empty() {
+        return 1.0;
+    }
+    let band = thresholds.iter().take_while(|&&t| intensity >= t).count();
+    band as f32 / thresholds.len() as f32
+}
+
+#[derive(Clone,Copy)]
+pub struct Vertex {
+    pub p: Vec3f, // screen position for Renderer::triangle, NDC otherwise
+    pub t: Vec2f,
+    pub i: f32, // diffuse term of every light, only used by Renderer::triangle
+    pub n: Vec3f, // world space normal, interpolated for Phong shading
+    pub world: Vec3f, // world space position, for shadow lookups
+    pub c: RgbaColor // color, used when not textured
+}
+
+// Base color of faces naming a texture that isn't there
+pub const MISSING_TEXTURE_COLOR: RgbaColor = RgbaColor { r: 1.0, g: 0.0, b: 1.0, a: 1.0 };
+
+// What draw_model does: the renderer's lights with the selected shading,
+// the face's material, its texture or the renderer's diffuse texture, an optional
+// specular map and shadows
+#[derive(Clone)]
+pub struct StandardShader<'a> {
+    // None when vertices are given in screen space
+    pub mesh: Option<&'a dyn Mesh>,
+    pub shading: Shading,
+    pub color: RgbaColor,
+    pub model_center: Vec3f,
+    pub model_scale: f32,
+    pub model_transform: Mat4,
+    pub normal_matrix: Mat3,
+    pub view_projection: Mat4,
+    pub lights: Vec<Light>,
+    // Lights reach surfaces facing away from them as well
+    pub two_sided: bool,
+    // Towards the viewer in world space. Treated as constant across the
+    // triangle, exact for orthographic projection
+    pub to_eye: Vec3f,
+    pub diffuse: Option<&'a Texture>,
+    // What the mesh's face textures index
+    pub textures: &'a [Texture],
+    pub specular: Option<&'a Texture>,
+    pub specular_intensity: f32,
+    // Light the surface gives off regardless of the lights, added to the lit color
+    pub glow: Option<&'a Texture>,
+    // Only the glow, opaque and black without a glow map, for the renderer's
+    // bloom pass
+    pub glow_only: bool,
+    pub ambient: f32,
+    pub shadow: Option<&'a ShadowMap>,
+    pub shadow_factor: f32,
+    pub environment: Option<&'a Environment>,
+    // Fraction of the color replaced by the reflected environment
+    pub reflectivity: f32,
+    // Applied last, by the depth view_depth gives
+    pub fog: Option<Fog>,
+    // Distance of a world position in front of the camera is
+    // view_depth * position.to_point()
+    pub view_depth: Vec4f,
+    pub filter: Filter,
+    pub wrap: Wrap,
+    pub gamma: bool,
+    // Fragments whose base color alpha is below this are discarded
+    pub alpha_test: f32,
+    // Where toon bands start, the bands of Shading::Toon are equally wide
+    // when empty
+    pub toon_thresholds: Vec<f32>,
+    // Current face, set by vertex
+    pub vertices: [Vertex; 3],
+    pub clip: [Vec4f; 3],
+    // Per light, its signed diffuse term at each corner for Flat and Gouraud
+    pub terms: Vec<[f32; 3]>,
+    pub texture: Option<&'a Texture>,
+    // Mip level of the triangle being rasterized
+    pub level: usize
+}
+
+impl<'a> StandardShader<'a> {
+    // Model coordinates after fitting and the model transform
+    pub fn world_position(&self, position: Vec3f) -> Vec3f {
+        let p = (position - self.model_center) * self.model_scale;
+        if self.model_transform != Mat4::identity() {
+            return self.model_transform * p;
+        }
+        p
+    }
+
+    // Whether face can come out partly see-through: its base color, material
+    // or a vertex color has alpha below 1, or its texture has see-through
+    // texels and alpha testing is off. Always false without a mesh
+    pub fn is_translucent(&self, face: usize) -> bool {
+        let mesh = match self.mesh {
+            Some(mesh) => mesh,
+            None => return false
+        };
+        let corners = [mesh.corner(face, 0), mesh.corner(face, 1), mesh.corner(face, 2)];
+        let (color, texture) = self.face_base(mesh, face);
+        let textured = corners.iter().all(|c: &ModelVertex| c.texture_coord.is_some());
+
+        color.a < 1.0
+            || corners.iter().any(|c| c.color.is_some_and(|c| c.a < 1.0))
+            || (textured && self.alpha_test <= 0.0 && texture.is_some_and(|t| !t.is_opaque()))
+    }
+
+    // Material Kd color and texture take precedence over the face's texture,
+    // which takes precedence over the renderer's own
+    fn face_base(&self, mesh: &'a dyn Mesh, face: usize) -> (RgbaColor, Option<&'a Texture>) {
+        if let Some(m) = mesh.face_material(face) {
+            return (m.diffuse_color, m.diffuse_map.as_ref());
+        }
+        match mesh.face_texture(face) {
+            Some(TextureId(idx)) => match self.textures.get(idx) {
+                Some(texture) => (self.color, Some(texture)),
+                None => (MISSING_TEXTURE_COLOR, None)
+            },
+            None => (self.color, self.diffuse)
+        }
+    }
+
+    // How much of a diffuse term lights the surface
+    fn diffuse(&self, term: f32) -> f32 {
+        if self.two_sided { term.abs() } else { (-term).max(0.0) }
+    }
+
+    fn load_face(&mut self, mesh: &'a dyn Mesh, face: usize) {
+        let corners = [mesh.corner(face, 0), mesh.corner(face, 1), mesh.corner(face, 2)];
+
+        let mut world_coords = [Vec3f::new(0.0, 0.0, 0.0); 3];
+        for i in 0..3 {
+            world_coords[i] = self.world_position(corners[i].position);
+        }
+
+        let face_normal = ((world_coords[2] - world_coords[0]) ^ (world_coords[1] - world_coords[0])).normalized();
+        let centroid = (world_coords[0] + world_coords[1] + world_coords[2]) / 3.0;
+        let textured = corners.iter().all(|c: &ModelVertex| c.texture_coord.is_some());
+
+        let (color, texture) = self.face_base(mesh, face);
+        self.texture = if textured { texture } else { None };
+        self.terms.clear();
+
+        for i in 0..3 {
+            // Faces without vertex normals fall back to the face normal
+            let normal = match corners[i].normal {
+                Some(n) if self.model_transform != Mat4::identity() => (self.normal_matrix * n).normalized(),
+                Some(n) => n,
+                None => face_normal
+            };
+            for (k, light) in self.lights.iter().enumerate() {
+                let term = match self.shading {
+                    Shading::Gouraud | Shading::Phong | Shading::Toon { .. } => {
+                        let (dir, intensity) = light.incident(world_coords[i]);
+                        dir * normal * intensity
+                    },
+                    Shading::Flat => {
+                        let (dir, intensity) = light.incident(centroid);
+                        dir * face_normal * intensity
+                    }
+                };
+                if i == 0 {
+                    self.terms.push([0.0; 3]);
+                }
+                self.terms[k][i] = term;
+            }
+
+            self.clip[i] = self.view_projection * world_coords[i].to_point();
+            self.vertices[i] = Vertex {
+                p: self.clip[i].to_vec3(),
+                t: corners[i].texture_coord.unwrap_or(Vec2f::new(0.0, 0.0)),
+                i: 0.0,
+                n: normal,
+                world: world_coords[i],
+                // Vertex colors modulate the base color
+                c: match corners[i].color {
+                    Some(vc) => RgbaColor::new(vc.r * color.r, vc.g * color.g, vc.b * color.b, vc.a * color.a),
+                    None => color
+                }
+            };
+        }
+    }
+}
+
+impl<'a> Shader for StandardShader<'a> {
+    fn vertex(&mut self, face: usize, corner: usize) -> Vec4f {
+        if corner == 0 {
+            if let Some(mesh) = self.mesh {
+                self.load_face(mesh, face);
+            }
+        }
+        self.clip[corner]
+    }
+
+    // Mip level from how many texels the triangle covers per screen pixel
+    fn begin_triangle(&mut self, screen: &[Vec2f; 3], bary: &[Vec3f; 3]) {
+        self.level = match self.texture {
+            Some(tex) => {
+                let v = &self.vertices;
+                let mut uv = [Vec2f::new(0.0, 0.0); 3];
+                for i in 0..3 {
+                    uv[i] = v[0].t * bary[i].x + v[1].t * bary[i].y + v[2].t * bary[i].z;
+                }
+                tex.select_level(triangle_area_2d(uv[0], uv[1], uv[2]), triangle_area_2d(screen[0], screen[1], screen[2]))
+            },
+            None => 0
+        };
+    }
+
+    fn fragment(&self, w: Vec3f) -> Option<RgbaColor> {
+        let v = &self.vertices;
+        let color = barycentric_mix(v[0].c, v[1].c, v[2].c, w);
+        let mut c = match self.texture {
+            Some(tex) => {
+                let tp = barycentric_mix(v[0].t, v[1].t, v[2].t, w);
+                let mut texel = tex.sample(tp.x, tp.y, self.level, self.filter, self.wrap);
+                if self.gamma { texel = color_to_linear(texel); }
+                texel.a *= color.a;
+                texel
+            },
+            None => color
+        };
+        if c.a < self.alpha_test {
+            return None;
+        }
+
+        let glow = match self.glow {
+            Some(map) => {
+                let tp = barycentric_mix(v[0].t, v[1].t, v[2].t, w);
+                let texel = map.sample(tp.x, tp.y, 0, self.filter, self.wrap);
+                if self.gamma { color_to_linear(texel) } else { texel }
+            },
+            None => RgbaColor::new(0.0, 0.0, 0.0, 0.0)
+        };
+        if self.glow_only {
+            return Some(RgbaColor::new(glow.r, glow.g, glow.b, 1.0));
+        }
+
+        // Per channel sum of diffuse and specular light, scaled and clamped
+        // together with ambient at the end so highlights saturate instead of wrapping
+        let exponent = match self.specular {
+            Some(map) => {
+                let tp = barycentric_mix(v[0].t, v[1].t, v[2].t, w);
+                Some(map.sample(tp.x, tp.y, 0, self.filter, self.wrap).r * 255.0)
+            },
+            None => None
+        };
+        let needs_position = self.shadow.is_some() || self.fog.is_some() || self.lights.iter().any(|l| matches!(l.kind, LightKind::Point { .. }));
+        let world = if needs_position { barycentric_mix(v[0].world, v[1].world, v[2].world, w) } else { v[0].world };
+
+        let mut direct = Vec3f::new(0.0, 0.0, 0.0);
+        for (k, light) in self.lights.iter().enumerate() {
+            let (dir, intensity) = light.incident(world);
+            let diffuse = match self.shading {
+                Shading::Phong => self.diffuse(dir * barycentric_mix(v[0].n, v[1].n, v[2].n, w).normalized() * intensity),
+                Shading::Toon { bands } => {
+                    let term = self.diffuse(dir * barycentric_mix(v[0].n, v[1].n, v[2].n, w).normalized() * intensity).min(1.0);
+                    if self.toon_thresholds.is_empty() { toon_band(term, bands) } else { toon_band_at(term, &self.toon_thresholds) }
+                },
+                _ => {
+                    let t = &self.terms[k];
+                    self.diffuse(barycentric_mix(t[0], t[1], t[2], w))
+                }
+            };
+            let specular = match exponent {
+                Some(exponent) => {
+                    let to_light = -dir.normalized();
+                    let mut n = barycentric_mix(v[0].n, v[1].n, v[2].n, w).normalized();
+                    let facing = n * to_light >= 0.0;
+                    // Lit from either side when two sided, like the diffuse term
+                    if !facing && self.two_sided { n = -n; }
+                    let r = n * (2.0 * (n * to_light)) - to_light;
+                    if facing || self.two_sided {
+                        self.specular_intensity * (r * self.to_eye).max(0.0).powf(exponent) * intensity
+                    } else {
+                        0.0
+                    }
+                },
+                None => 0.0
+            };
+            direct += light_color(light) * (diffuse + specular);
+        }
+
+        let shadowed = match self.shadow {
+            Some(map) => map.is_shadowed(world),
+            None => false
+        };
+        let direct = if shadowed { direct * self.shadow_factor } else { direct };
+        c = RgbaColor::new(
+            c.r * (self.ambient + direct.x) + glow.r,
+            c.g * (self.ambient + direct.y) + glow.g,
+            c.b * (self.ambient + direct.z) + glow.b,
+            c.a
+        ).clamp();
+
+        if let Some(environment) = self.environment {
+            if self.reflectivity > 0.0 {
+                // View direction mirrored about the normal
+                let n = barycentric_mix(v[0].n, v[1].n, v[2].n, w).normalized();
+                let r = n * (2.0 * (n * self.to_eye)) - self.to_eye;
+                let mut reflected = environment.sample(r);
+                if self.gamma { reflected = color_to_linear(reflected); }
+                reflected.a = c.a;
+                c = c.lerp(reflected, self.reflectivity);
+            }
+        }
+        if let Some(fog) = self.fog {
+            c = fog.apply(c, self.view_depth * world.to_point());
+        }
+        Some(c)
+    }
+}
+
+fn light_color(light: &Light) -> Vec3f {
+    Vec3f::new(light.color.r, light.color.g, light.color.b)
+}
@@ -0,0 +1,51 @@
+use tga::TgaImage;
+use framebuffer::DepthFunc;
+use depth::DepthBuffer;
+use math::{Vec3f,Mat4};
+
+// Depth of the scene as seen from the light. light_view maps world space to
+// the light's clip space where [-1, 1] covers the map, larger z is closer to
+// the light like in the main z-buffer
+pub struct ShadowMap {
+    pub light_view: Mat4,
+    pub size: i32,
+    // Added to the fragment depth before comparing, trades acne for
+    // shadows detaching from their casters
+    pub bias: f32,
+    // Light space NDC depth per texel, -inf where nothing was drawn
+    depth: DepthBuffer
+}
+
+impl ShadowMap {
+    pub fn new(light_view: Mat4, size: i32, bias: f32) -> ShadowMap {
+        assert!(size > 0, "shadow map size must be positive");
+        return ShadowMap { light_view: light_view, size: size, bias: bias, depth: DepthBuffer::new(size, size) };
+    }
+
+    pub fn clear(&mut self) {
+        self.depth.clear();
+    }
+
+    // Keeps the closer of the stored and given depth
+    pub fn store(&mut self, x: i32, y: i32, depth: f32) {
+        self.depth.test_and_set(x, y, depth, DepthFunc::Greater);
+    }
+
+    // Whether something between p and the light occludes it. Points outside
+    // the map are never in shadow
+    pub fn is_shadowed(&self, p: Vec3f) -> bool {
+        let l = self.light_view * p;
+        let half = self.size as f32 / 2.0;
+        let x = ((l.x + 1.0) * half).floor() as i32;
+        let y = ((l.y + 1.0) * half).floor() as i32;
+        match self.depth.get(x, y) {
+            Some(z) => z > l.z + self.bias,
+            None => false
+        }
+    }
+
+    // Grayscale view for debugging, white is closest to the light
+    pub fn to_image(&self) -> TgaImage {
+        self.depth.to_grayscale_image()
+    }
+}
@@ -0,0 +1,94 @@
+// Color and depth buffers a Renderer draws into. Every renderer starts with
+// its own, Renderer::set_target swaps in others so one renderer can draw
+// offscreen images and textures to use in later passes
+
+use std::mem::size_of_val;
+use framebuffer::{Framebuffer,FramebufferError,buffer_size,BYTES_PER_PIXEL,MAX_BYTES};
+use depth::DepthBuffer;
+use texture::Texture;
+use tga::TgaImage;
+
+pub struct RenderTarget {
+    // Both supersampling times the output size in each direction
+    pub framebuffer: Framebuffer,
+    pub depth: DepthBuffer,
+    // Glow of what's visible, drawn alongside the color while the renderer
+    // has bloom on and allocated then
+    pub glow: Option<Framebuffer>,
+    supersampling: i32
+}
+
+impl RenderTarget {
+    pub fn new(width: i32, height: i32) -> RenderTarget {
+        let framebuffer = Framebuffer::new(width, height);
+        let depth = DepthBuffer::new(width, height);
+        return RenderTarget { framebuffer: framebuffer, depth: depth, glow: None, supersampling: 1 };
+    }
+
+    // width x height output pixels of supersampling x supersampling samples
+    // each, failing instead of aborting when that's too much memory
+    pub fn try_new(width: i32, height: i32, supersampling: u32) -> Result<RenderTarget, FramebufferError> {
+        assert!(supersampling > 0, "supersampling factor must be positive");
+        let (samples_x, samples_y) = (width as usize * supersampling as usize, height as usize * supersampling as usize);
+        buffer_size(samples_x, samples_y)?;
+        if samples_x > i32::MAX as usize || samples_y > i32::MAX as usize {
+            return Err(FramebufferError::TooLarge { width: samples_x, height: samples_y, bytes: None });
+        }
+
+        let framebuffer = Framebuffer::try_new(samples_x as i32, samples_y as i32)?;
+        let depth = DepthBuffer::try_new(samples_x as i32, samples_y as i32)?;
+        return Ok(RenderTarget { framebuffer: framebuffer, depth: depth, glow: None, supersampling: supersampling as i32 });
+    }
+
+    // Output size, what to_image returns
+    pub fn width(&self) -> i32 {
+        self.framebuffer.width / self.supersampling
+    }
+
+    pub fn height(&self) -> i32 {
+        self.framebuffer.height / self.supersampling
+    }
+
+    pub fn supersampling(&self) -> i32 {
+        self.supersampling
+    }
+
+    // Bytes taken by both buffers and the glow buffer if there's one
+    pub fn memory_usage(&self) -> usize {
+        let glow = self.glow.as_ref().map_or(0, |glow| size_of_val(glow.pixels()));
+        self.depth.data().len() * BYTES_PER_PIXEL + glow
+    }
+
+    // Allocates the glow buffer unless there is one, failing like try_new
+    // when that takes the target over MAX_BYTES
+    pub fn try_alloc_glow(&mut self) -> Result<(), FramebufferError> {
+        if self.glow.is_some() {
+            return Ok(());
+        }
+
+        let (width, height) = (self.framebuffer.width, self.framebuffer.height);
+        let bytes = self.memory_usage() + size_of_val(self.framebuffer.pixels());
+        if bytes > MAX_BYTES {
+            return Err(FramebufferError::TooLarge { width: width as usize, height: height as usize, bytes: Some(bytes) });
+        }
+        self.glow = Some(Framebuffer::try_new(width, height)?);
+        Ok(())
+    }
+
+    // Color at output resolution as drawn, without the outline and post
+    // processing Renderer::to_image adds. gamma encodes to sRGB
+    pub fn to_image(&self, gamma: bool, dither: bool) -> TgaImage {
+        if self.supersampling > 1 {
+            return self.framebuffer.resolve(self.supersampling).to_image(gamma, dither);
+        }
+        self.framebuffer.to_image(gamma, dither)
+    }
+
+    // to_image as a texture with mipmaps. Pass the gamma correction of the
+    // renderer that samples it, which decodes textures from sRGB
+    pub fn to_texture(&self, gamma: bool) -> Texture {
+        let mut texture = Texture::new(self.to_image(gamma, false));
+        texture.generate_mipmaps();
+        texture
+    }
+}
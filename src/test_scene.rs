@@ -0,0 +1,47 @@
+// Small procedural scene for regression tests, rendered from coordinates
+// given here so it needs no asset files
+
+use model::Model;
+use renderer::Renderer;
+use shader::Shading;
+use tga::{TgaImage,RgbaColor};
+use math::{Vec3f,Mat4};
+
+// Three vertex colored triangles crossing each other at different depths, the
+// last one tilted so depth varies across it and it cuts through the others
+pub fn model() -> Model {
+    let vertices = vec![
+        Vec3f::new(-0.9, -0.8, -0.2), Vec3f::new(0.5, -0.6, -0.2), Vec3f::new(-0.3, 0.9, -0.2),
+        Vec3f::new(-0.5, -0.2, 0.1), Vec3f::new(0.9, -0.1, 0.1), Vec3f::new(0.2, 0.8, 0.1),
+        Vec3f::new(-0.8, 0.3, -0.6), Vec3f::new(0.7, -0.9, 0.6), Vec3f::new(0.6, 0.6, -0.1)
+    ];
+    let faces = vec![
+        [0, -1, -1, 1, -1, -1, 2, -1, -1],
+        [3, -1, -1, 4, -1, -1, 5, -1, -1],
+        [6, -1, -1, 7, -1, -1, 8, -1, -1]
+    ];
+    let mut model = Model::new(vertices, Vec::new(), Vec::new(), faces).unwrap();
+    model.vertex_colors = vec![
+        RgbaColor::new(1.0, 0.0, 0.0, 1.0), RgbaColor::new(1.0, 0.5, 0.0, 1.0), RgbaColor::new(1.0, 0.0, 0.5, 1.0),
+        RgbaColor::new(0.0, 1.0, 0.0, 1.0), RgbaColor::new(0.0, 1.0, 0.5, 1.0), RgbaColor::new(0.5, 1.0, 0.0, 1.0),
+        RgbaColor::new(0.0, 0.0, 1.0, 1.0), RgbaColor::new(0.0, 0.5, 1.0, 1.0), RgbaColor::new(1.0, 1.0, 1.0, 1.0)
+    ];
+    model
+}
+
+// Draws the scene with Gouraud shading, gamma correction and without
+// culling, over whatever else the renderer is set up with
+pub fn draw(renderer: &mut Renderer) {
+    renderer.set_shading(Shading::Gouraud);
+    renderer.set_gamma_correction(true);
+    renderer.set_backface_culling(false);
+    renderer.draw_model(&model(), &Mat4::identity());
+}
+
+// The scene on its own, width x height with threads rasterizing it
+pub fn render(width: i32, height: i32, threads: usize) -> TgaImage {
+    let mut renderer = Renderer::new(width, height);
+    renderer.set_threads(threads);
+    draw(&mut renderer);
+    renderer.to_image()
+}
@@ -0,0 +1,66 @@
+use tga::{TgaImage,RgbaColor,Filter,Wrap};
+
+// Index of a texture held by a Scene or a Renderer, only meaningful for the
+// one that handed it out
+#[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
+pub struct TextureId(pub usize);
+
+// Image with an optional chain of successively halved mip levels, level 0 is
+// the original image
+pub struct Texture {
+    levels: Vec<TgaImage>,
+    // No texel has alpha below 1
+    opaque: bool
+}
+
+impl Texture {
+    pub fn new(image: TgaImage) -> Texture {
+        let opaque = image.is_opaque();
+        return Texture { levels: vec![image], opaque: opaque };
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.opaque
+    }
+
+    // Box filters down to 1x1, replacing any previously generated levels
+    pub fn generate_mipmaps(&mut self) {
+        self.levels.truncate(1);
+        loop {
+            let next = {
+                let last = &self.levels[self.levels.len() - 1];
+                if last.width == 1 && last.height == 1 { break; }
+                last.downsample()
+            };
+            self.levels.push(next);
+        }
+    }
+
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    // Out of range levels return the smallest one
+    pub fn level(&self, level: usize) -> &TgaImage {
+        &self.levels[level.min(self.levels.len() - 1)]
+    }
+
+    pub fn sample(&self, u: f32, v: f32, level: usize, filter: Filter, wrap: Wrap) -> RgbaColor {
+        self.level(level).sample(u, v, filter, wrap)
+    }
+
+    // Picks the level where one texel covers about one pixel, given a
+    // triangle's area in UV space and in screen pixels
+    pub fn select_level(&self, uv_area: f32, screen_area: f32) -> usize {
+        let base = &self.levels[0];
+        let texels = uv_area.abs() * (base.width * base.height) as f32;
+        let pixels = screen_area.abs();
+        if pixels == 0.0 || texels <= pixels {
+            return 0;
+        }
+
+        // Each level divides the texel count by four
+        let level = (0.5 * (texels / pixels).log2()).floor() as usize;
+        level.min(self.levels.len() - 1)
+    }
+}
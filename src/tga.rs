@@ -1,15 +1,22 @@
 use std::default::Default;
+use std::error::Error;
+use std::fmt;
+use std::io;
 use std::io::prelude::*;
 use std::fs::File;
-use std::io::BufReader;
-use std::ops::{Add,Mul};
+use std::io::{BufReader,BufWriter};
+use std::ops::{Add,Sub,Mul,Div,MulAssign,DivAssign};
 use std::path::Path;
+use png;
+use math::Lerp;
+use kernel::Kernel;
+use framebuffer::{blend,BlendMode};
 
 macro_rules! clamp(
     ($a:expr, $min:expr, $max:expr) => ($a.min($max).max($min));
 );
 
-#[derive(Clone,Copy)]
+#[derive(Clone,Copy,PartialEq,Debug)]
 pub struct RgbaColor {
     pub r: f32,
     pub g: f32,
@@ -18,6 +25,13 @@ pub struct RgbaColor {
 }
 
 impl RgbaColor {
+    pub const WHITE: RgbaColor = RgbaColor { r: 1.0, g: 1.0, b: 1.0, a: 1.0 };
+    pub const BLACK: RgbaColor = RgbaColor { r: 0.0, g: 0.0, b: 0.0, a: 1.0 };
+    pub const RED: RgbaColor = RgbaColor { r: 1.0, g: 0.0, b: 0.0, a: 1.0 };
+    pub const GREEN: RgbaColor = RgbaColor { r: 0.0, g: 1.0, b: 0.0, a: 1.0 };
+    pub const BLUE: RgbaColor = RgbaColor { r: 0.0, g: 0.0, b: 1.0, a: 1.0 };
+    pub const TRANSPARENT: RgbaColor = RgbaColor { r: 0.0, g: 0.0, b: 0.0, a: 0.0 };
+
     #[inline(always)]
     pub fn new(r: f32, g: f32, b: f32, a: f32) -> RgbaColor {
         RgbaColor {r: r, g: g, b: b, a: a}
@@ -27,6 +41,43 @@ impl RgbaColor {
         RgbaColor::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0)
     }
 
+    // 0xRRGGBBAA
+    pub fn from_hex(hex: u32) -> RgbaColor {
+        RgbaColor::new_from_u8((hex >> 24) as u8, (hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+    }
+
+    // 0xRRGGBBAA with each channel clamped and rounded to 8 bits
+    pub fn to_u32(&self) -> u32 {
+        let q = |v: f32| (clamp!(v, 0.0, 1.0) * 255.0).round() as u32;
+        q(self.r) << 24 | q(self.g) << 16 | q(self.b) << 8 | q(self.a)
+    }
+
+    // Interpolates all four channels, t outside [0, 1] extrapolates
+    #[inline(always)]
+    pub fn lerp(self, other: RgbaColor, t: f32) -> RgbaColor {
+        RgbaColor::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t
+        )
+    }
+
+    // The operators clamp to [0, 1] like the 8 bit output does, these keep
+    // values above 1.0 for accumulating light. Alpha is left as in self
+    pub fn add_unclamped(self, rhs: RgbaColor) -> RgbaColor {
+        RgbaColor::new(self.r + rhs.r, self.g + rhs.g, self.b + rhs.b, self.a)
+    }
+
+    pub fn scale_unclamped(self, rhs: f32) -> RgbaColor {
+        RgbaColor::new(self.r * rhs, self.g * rhs, self.b * rhs, self.a)
+    }
+
+    pub fn approx_eq(&self, other: &RgbaColor, epsilon: f32) -> bool {
+        (self.r - other.r).abs() <= epsilon && (self.g - other.g).abs() <= epsilon &&
+            (self.b - other.b).abs() <= epsilon && (self.a - other.a).abs() <= epsilon
+    }
+
     pub fn clamp(&mut self) -> RgbaColor {
         self.r = clamp!(self.r, 0.0, 1.0);
         self.g = clamp!(self.g, 0.0, 1.0);
@@ -36,6 +87,7 @@ impl RgbaColor {
     }
 }
 
+// Scales the color channels, clamps the result and keeps alpha
 impl Mul<f32> for RgbaColor {
     type Output = RgbaColor;
 
@@ -50,6 +102,64 @@ impl Mul<f32> for RgbaColor {
     }
 }
 
+impl Lerp for RgbaColor {
+    #[inline(always)]
+    fn lerp(self, other: RgbaColor, t: f32) -> RgbaColor {
+        RgbaColor::lerp(self, other, t)
+    }
+}
+
+// Component-wise modulation of all four channels, not clamped
+impl Mul<RgbaColor> for RgbaColor {
+    type Output = RgbaColor;
+
+    #[inline(always)]
+    fn mul(self, rhs: RgbaColor) -> RgbaColor {
+        RgbaColor::new(self.r * rhs.r, self.g * rhs.g, self.b * rhs.b, self.a * rhs.a)
+    }
+}
+
+impl MulAssign<f32> for RgbaColor {
+    fn mul_assign(&mut self, rhs: f32) {
+        *self = *self * rhs;
+    }
+}
+
+impl MulAssign<RgbaColor> for RgbaColor {
+    fn mul_assign(&mut self, rhs: RgbaColor) {
+        *self = *self * rhs;
+    }
+}
+
+// Like Mul<f32>, clamps the result and keeps alpha
+impl Div<f32> for RgbaColor {
+    type Output = RgbaColor;
+
+    #[inline(always)]
+    fn div(self, rhs: f32) -> RgbaColor {
+        let mut c = RgbaColor::new(self.r / rhs, self.g / rhs, self.b / rhs, self.a);
+        c.clamp()
+    }
+}
+
+impl DivAssign<f32> for RgbaColor {
+    fn div_assign(&mut self, rhs: f32) {
+        *self = *self / rhs;
+    }
+}
+
+// Like Add, clamps the result and keeps the left hand alpha
+impl Sub<RgbaColor> for RgbaColor {
+    type Output = RgbaColor;
+
+    #[inline(always)]
+    fn sub(self, rhs: RgbaColor) -> RgbaColor {
+        let mut result = RgbaColor::new(self.r - rhs.r, self.g - rhs.g, self.b - rhs.b, self.a);
+        result.clamp()
+    }
+}
+
+// Adds the color channels, clamps the result and keeps the left hand alpha
 impl Add<RgbaColor> for RgbaColor {
     type Output = RgbaColor;
 
@@ -60,8 +170,8 @@ impl Add<RgbaColor> for RgbaColor {
     }
 }
 
-#[derive(Default)]
-pub struct TgaPixel {
+#[derive(Default,Clone,Copy,PartialEq)]
+struct TgaPixel {
     r: u8,
     g: u8,
     b: u8,
@@ -82,6 +192,201 @@ impl TgaPixel {
     }
 }
 
+// Reads one color stored as 15/16 bit (A)RGB 5-5-5, 24 bit BGR or 32 bit BGRA
+fn read_color<R: Read>(reader: &mut R, bits: u8) -> io::Result<RgbaColor> {
+    let mut buf = [0u8; 4];
+    let len = (bits as usize).div_ceil(8);
+    reader.read_exact(&mut buf[..len])?;
+
+    if bits == 15 || bits == 16 {
+        let v = buf[0] as u16 | (buf[1] as u16) << 8;
+        let scale = |c: u16| ((c & 0x1F) * 255 / 31) as u8;
+        let a = if bits == 16 && v & 0x8000 == 0 { 0 } else { 255 };
+        return Ok(RgbaColor::new_from_u8(scale(v >> 10), scale(v >> 5), scale(v), a));
+    }
+
+    let a = if bits == 32 { buf[3] } else { 255 };
+    Ok(RgbaColor::new_from_u8(buf[2], buf[1], buf[0], a))
+}
+
+#[derive(Debug)]
+pub enum TgaError {
+    Io(io::Error),
+    // Data ended before the header or all pixels were read
+    UnexpectedEof,
+    UnsupportedFormat { data_type: u8, bpp: u8 },
+    InvalidDimensions { width: i32, height: i32 },
+    // Color mapped pixel pointing outside the color map
+    InvalidColorIndex(usize),
+    // Malformed header in the text based formats
+    Invalid(String),
+    // Rectangle not fully inside the image
+    OutOfBounds { x: i32, y: i32, width: i32, height: i32 }
+}
+
+impl fmt::Display for TgaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TgaError::Io(ref e) => write!(f, "{}", e),
+            TgaError::UnexpectedEof => write!(f, "unexpected end of TGA data"),
+            TgaError::UnsupportedFormat { data_type, bpp } =>
+                write!(f, "unsupported TGA data type {} with {} bits per pixel", data_type, bpp),
+            TgaError::InvalidDimensions { width, height } => write!(f, "invalid TGA dimensions {}x{}", width, height),
+            TgaError::InvalidColorIndex(index) => write!(f, "color map index {} out of range", index),
+            TgaError::Invalid(ref what) => write!(f, "invalid image: {}", what),
+            TgaError::OutOfBounds { x, y, width, height } =>
+                write!(f, "rectangle {}x{} at ({}, {}) is outside the image", width, height, x, y)
+        }
+    }
+}
+
+impl Error for TgaError {}
+
+impl From<io::Error> for TgaError {
+    fn from(e: io::Error) -> TgaError {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => TgaError::UnexpectedEof,
+            _ => TgaError::Io(e)
+        }
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Filter {
+    Nearest,
+    // Weighted blend of the four texels around the sample point
+    Bilinear
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Wrap {
+    Repeat,
+    ClampToEdge
+}
+
+impl Wrap {
+    #[inline(always)]
+    fn apply(self, i: i32, size: i32) -> i32 {
+        match self {
+            Wrap::Repeat => ((i % size) + size) % size,
+            Wrap::ClampToEdge => clamp!(i, 0, size - 1)
+        }
+    }
+}
+
+#[derive(Default,Clone,Copy)]
+pub struct TgaWriteOptions {
+    // Run-length encode pixel data (data type 10)
+    pub rle: bool,
+    // Write 32 bpp BGRA instead of 24 bpp BGR
+    pub alpha: bool
+}
+
+// Differing pixels listed by DiffResult
+const DIFF_SAMPLES: usize = 10;
+
+// Outcome of TgaImage::diff, in 8 bit channel values
+pub struct DiffResult {
+    pub tolerance: u8,
+    // Pixels with a channel off by more than tolerance
+    pub differing: usize,
+    // Largest channel difference anywhere
+    pub max_delta: u8,
+    // The first few differing pixels in row order, (x, y, largest channel difference)
+    pub pixels: Vec<(i32, i32, u8)>,
+    // Per-channel absolute differences, opaque, when asked for
+    pub image: Option<TgaImage>
+}
+
+impl DiffResult {
+    pub fn is_match(&self) -> bool {
+        self.differing == 0
+    }
+}
+
+impl fmt::Display for DiffResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} pixels differ by more than {}, by up to {}", self.differing, self.tolerance, self.max_delta)?;
+        for (i, &(x, y, delta)) in self.pixels.iter().enumerate() {
+            write!(f, "{} ({}, {}) by {}", if i == 0 { ":" } else { "," }, x, y, delta)?;
+        }
+        if self.differing > self.pixels.len() {
+            write!(f, ", ...")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DiffResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+fn push_pixel(data: &mut Vec<u8>, p: &TgaPixel, alpha: bool) {
+    data.extend(&[p.b, p.g, p.r]);
+    if alpha { data.push(p.a); }
+}
+
+// Appends one scanline as RLE packets, runs of two or more identical pixels
+// become run packets, everything else goes into raw packets. Packets hold at
+// most 128 pixels and never span scanlines
+fn encode_rle_row(row: &[TgaPixel], data: &mut Vec<u8>, alpha: bool) {
+    let run_length = |start: usize| {
+        let mut n = 1;
+        while start + n < row.len() && n < 128 && row[start + n] == row[start] { n += 1; }
+        n
+    };
+
+    let mut i = 0;
+    while i < row.len() {
+        let run = run_length(i);
+        if run > 1 {
+            data.push(0x80 | (run - 1) as u8);
+            push_pixel(data, &row[i], alpha);
+            i += run;
+            continue;
+        }
+
+        let mut n = 1;
+        while i + n < row.len() && n < 128 && run_length(i + n) == 1 { n += 1; }
+        data.push((n - 1) as u8);
+        for p in row[i..i + n].iter() {
+            push_pixel(data, p, alpha);
+        }
+        i += n;
+    }
+}
+
+// Next whitespace separated PPM/PAM header token, skipping comments. Consumes
+// the single whitespace byte following the token
+fn read_token<R: Read>(reader: &mut R) -> Result<String, TgaError> {
+    let mut token = String::new();
+    let mut comment = false;
+    loop {
+        let mut b = [0u8];
+        reader.read_exact(&mut b)?;
+        let c = b[0] as char;
+
+        if comment {
+            comment = c != '\n';
+        } else if c == '#' && token.is_empty() {
+            comment = true;
+        } else if c.is_ascii_whitespace() {
+            if !token.is_empty() { return Ok(token); }
+        } else {
+            token.push(c);
+        }
+    }
+}
+
+fn parse_header_value(token: &str) -> Result<i32, TgaError> {
+    match token.parse::<i32>() {
+        Ok(v) => Ok(v),
+        Err(_) => Err(TgaError::Invalid(format!("expected a number, got {:?}", token)))
+    }
+}
+
 pub struct TgaImage {
     pub width: i32,
     pub height: i32,
@@ -101,106 +406,516 @@ impl TgaImage {
         return TgaImage { width: width, height: height, pixels: pixels };
     }
 
+    // Row-major offset of a pixel, None when outside the image
+    #[inline(always)]
+    pub fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((x + self.width * y) as usize)
+    }
+
     pub fn set_pixel(&mut self, x: i32, y: i32, color: &RgbaColor) {
-        match self.pixels.get_mut((x + self.height * y) as usize) {
+        let idx = match self.index(x, y) {
+            Some(idx) => idx,
+            None => return
+        };
+
+        match self.pixels.get_mut(idx) {
             Some(pixel) => pixel.set_color(color),
             None => return
         };
     }
 
+    // Source-over compositing of color onto the stored pixel using color's alpha
+    pub fn set_pixel_blend(&mut self, x: i32, y: i32, color: &RgbaColor) {
+        let idx = match self.index(x, y) {
+            Some(idx) => idx,
+            None => return
+        };
+
+        let pixel = &mut self.pixels[idx];
+        let c = blend(pixel.get_color(), color, BlendMode::AlphaBlend);
+        pixel.set_color(&c);
+    }
+
+    // Coordinates outside the image are clamped to the nearest edge pixel
     pub fn get_pixel(&self, x: i32, y: i32) -> RgbaColor {
-        match self.pixels.get((x + self.height * y) as usize) {
-            Some(pixel) => { pixel.get_color() },
-            None => { panic!("Can't read pixel at x: {}, y: {}", x, y) }
-        }
+        let x = clamp!(x, 0, self.width - 1);
+        let y = clamp!(y, 0, self.height - 1);
+        self.pixels[(x + self.width * y) as usize].get_color()
     }
 
-    pub fn write_to_file(&self, filename: &Path) {
-        let mut file = match File::create(filename) {
-            Err(e) => panic!("couldn't create {}: {:?}", filename.display(), e),
-            Ok(file) => file
+    // Texture lookup with (0, 0) at the bottom left and (1, 1) at the top right
+    // corner, texel centers sit at half texel offsets
+    pub fn sample(&self, u: f32, v: f32, filter: Filter, wrap: Wrap) -> RgbaColor {
+        let x = u * self.width as f32;
+        let y = v * self.height as f32;
+        let texel = |tx: i32, ty: i32| {
+            let idx = wrap.apply(tx, self.width) + wrap.apply(ty, self.height) * self.width;
+            self.pixels[idx as usize].get_color()
         };
 
-        let mut data = Vec::<u8>::with_capacity((3 * self.width * self.height + 20) as usize);
-        
-        // TGA Header, data type 2, 24 bytes per pixel
-        data.extend(&[0,0,2,0,0,0,0,0,0,0,0,0]);
-        data.push((self.width & 0xFF) as u8);
-        data.push((self.width >> 8 & 0xFF) as u8);
-        data.push((self.height & 0xFF) as u8);
-        data.push((self.height >> 8 & 0xFF) as u8);
-        data.push(24);
-        data.push(0);
+        match filter {
+            Filter::Nearest => texel(x.floor() as i32, y.floor() as i32),
+            Filter::Bilinear => {
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i32, y0 as i32);
 
-        // pixel data
-        for p in self.pixels.iter() {
-            data.push(p.b);
-            data.push(p.g);
-            data.push(p.r);
+                let bottom = texel(x0, y0).lerp(texel(x0 + 1, y0), fx);
+                let top = texel(x0, y0 + 1).lerp(texel(x0 + 1, y0 + 1), fx);
+                bottom.lerp(top, fy)
+            }
         }
+    }
 
-        file.write_all(&data[..]);
+    // Whether every pixel has full alpha
+    pub fn is_opaque(&self) -> bool {
+        self.pixels.iter().all(|p| p.a == 255)
     }
 
-    pub fn new_from_file(filename: &Path) -> TgaImage {
-        let mut file = match File::open(filename) {
-            Err(e) => panic!("couldn't open {}: {:?}", filename.display(), e),
-            Ok(file) => file
-        };
-        
-        let mut buffer = Vec::<u8>::new();
-        file.read_to_end(&mut buffer);
-        
-        let mut data = buffer.into_iter();
-
-        let id_len = data.next().unwrap();
-        let color_map_type = data.next().unwrap();
-        let data_type = data.next().unwrap();
-        
-        for _ in 0..5 { let _ = data.next().unwrap(); } // skip color map info
-        
-        let x_origin = data.next().unwrap() | (data.next().unwrap() << 2);
-        let y_origin = data.next().unwrap() | (data.next().unwrap() << 2);
-        let width:i32 = (data.next().unwrap() as i32) | ((data.next().unwrap() as i32) << 8);
-        let height:i32 = (data.next().unwrap() as i32) | ((data.next().unwrap() as i32) << 8);
-        let bpp = data.next().unwrap();
-        let img_desc = data.next().unwrap();
-
-        if (color_map_type != 0) {
-            panic!("Can't read files with color map");
-        }
-
-        for _ in 0..id_len { let _ = data.next().unwrap(); }
-        // TODO: Read/skip color map data?
+    // Half size copy (rounded down, at least 1x1) where each pixel is the rounded
+    // average of the source pixels it covers. Odd sizes fold the last row or
+    // column into the neighbouring output pixel
+    pub fn downsample(&self) -> TgaImage {
+        let w = (self.width / 2).max(1);
+        let h = (self.height / 2).max(1);
+        let mut pixels = Vec::with_capacity((w * h) as usize);
 
-        let mut image = TgaImage::new(width, height);
-        let mut pixel:i32 = 0;
-
-        while pixel < width * height {
-            let packet = data.next().unwrap();
-            let count = (packet & 127) + 1;
-
-            if packet & 128 > 0 {
-                let b = data.next().unwrap();
-                let g = data.next().unwrap();
-                let r = data.next().unwrap();
-                
-                for _ in 0..count {
-                    image.set_pixel(pixel % width, pixel / width, &RgbaColor::new_from_u8(r, g, b, 255));
-                    pixel += 1;
+        for y in 0..h {
+            let (sy0, sy1) = (y * self.height / h, (y + 1) * self.height / h);
+            for x in 0..w {
+                let (sx0, sx1) = (x * self.width / w, (x + 1) * self.width / w);
+                let mut sum = [0u32; 4];
+                for sy in sy0..sy1 {
+                    for p in self.pixels[(sy * self.width + sx0) as usize..(sy * self.width + sx1) as usize].iter() {
+                        sum[0] += p.r as u32;
+                        sum[1] += p.g as u32;
+                        sum[2] += p.b as u32;
+                        sum[3] += p.a as u32;
+                    }
+                }
+
+                let n = ((sx1 - sx0) * (sy1 - sy0)) as u32;
+                let avg = |v: u32| ((v + n / 2) / n) as u8;
+                pixels.push(TgaPixel { r: avg(sum[0]), g: avg(sum[1]), b: avg(sum[2]), a: avg(sum[3]) });
+            }
+        }
+
+        return TgaImage { width: w, height: h, pixels: pixels };
+    }
+
+    // Convolved with a width x width kernel, width odd, clamping to the edges
+    pub fn convolve(&self, kernel: &[f32], width: usize) -> TgaImage {
+        self.apply_kernel(&Kernel::new(kernel.to_vec(), width))
+    }
+
+    pub fn apply_kernel(&self, kernel: &Kernel) -> TgaImage {
+        let colors: Vec<RgbaColor> = self.pixels.iter().map(|p| p.get_color()).collect();
+        let mut image = TgaImage::new(self.width, self.height);
+        for (pixel, c) in image.pixels.iter_mut().zip(kernel.apply(&colors, self.width, self.height)) {
+            pixel.set_color(&c);
+        }
+        image
+    }
+
+    // Exact comparison without a difference image, see diff_with
+    pub fn diff(&self, other: &TgaImage) -> DiffResult {
+        self.diff_with(other, 0, false)
+    }
+
+    // Compares every channel of every pixel. Images of different sizes are
+    // compared over the larger extent, pixels only one of them has differ by
+    // 255 in every channel
+    pub fn diff_with(&self, other: &TgaImage, tolerance: u8, with_image: bool) -> DiffResult {
+        let (width, height) = (self.width.max(other.width), self.height.max(other.height));
+        let mut result = DiffResult { tolerance: tolerance, differing: 0, max_delta: 0, pixels: Vec::new(), image: None };
+        let mut image = if with_image { Some(TgaImage::new(width, height)) } else { None };
+
+        for y in 0..height {
+            for x in 0..width {
+                let delta = match (self.index(x, y), other.index(x, y)) {
+                    (Some(a), Some(b)) => {
+                        let (a, b) = (self.pixels[a], other.pixels[b]);
+                        let d = |a: u8, b: u8| (a as i32 - b as i32).unsigned_abs() as u8;
+                        TgaPixel { r: d(a.r, b.r), g: d(a.g, b.g), b: d(a.b, b.b), a: d(a.a, b.a) }
+                    },
+                    _ => TgaPixel { r: 255, g: 255, b: 255, a: 255 }
+                };
+                let largest = delta.r.max(delta.g).max(delta.b).max(delta.a);
+                result.max_delta = result.max_delta.max(largest);
+                if largest > tolerance {
+                    result.differing += 1;
+                    if result.pixels.len() < DIFF_SAMPLES {
+                        result.pixels.push((x, y, largest));
+                    }
                 }
+                if let Some(ref mut image) = image {
+                    image.pixels[(x + width * y) as usize] = TgaPixel { a: 255, ..delta };
+                }
+            }
+        }
+
+        result.image = image;
+        result
+    }
+
+    pub fn flip_vertical(&mut self) {
+        let w = self.width as usize;
+        let h = self.height as usize;
+        for y in 0..h / 2 {
+            let (top, bottom) = self.pixels.split_at_mut((h - 1 - y) * w);
+            top[y * w..(y + 1) * w].swap_with_slice(&mut bottom[..w]);
+        }
+    }
+
+    pub fn flip_horizontal(&mut self) {
+        for row in self.pixels.chunks_mut(self.width as usize) {
+            row.reverse();
+        }
+    }
+
+    // Copy of the w x h region whose bottom left corner is at (x, y)
+    pub fn crop(&self, x: i32, y: i32, w: i32, h: i32) -> Result<TgaImage, TgaError> {
+        if x < 0 || y < 0 || w <= 0 || h <= 0 || x + w > self.width || y + h > self.height {
+            return Err(TgaError::OutOfBounds { x: x, y: y, width: w, height: h });
+        }
+
+        let mut pixels = Vec::with_capacity((w * h) as usize);
+        for row in self.pixels.chunks(self.width as usize).skip(y as usize).take(h as usize) {
+            pixels.extend_from_slice(&row[x as usize..(x + w) as usize]);
+        }
+
+        return Ok(TgaImage { width: w, height: h, pixels: pixels });
+    }
+
+    // Copies src with its bottom left corner at (dst_x, dst_y), parts falling
+    // outside this image are clipped
+    pub fn blit(&mut self, src: &TgaImage, dst_x: i32, dst_y: i32) {
+        let x0 = dst_x.max(0);
+        let x1 = (dst_x + src.width).min(self.width);
+        if x0 >= x1 { return; }
+
+        for y in dst_y.max(0)..(dst_y + src.height).min(self.height) {
+            let from = ((y - dst_y) * src.width + x0 - dst_x) as usize;
+            let to = (y * self.width + x0) as usize;
+            let len = (x1 - x0) as usize;
+            self.pixels[to..to + len].copy_from_slice(&src.pixels[from..from + len]);
+        }
+    }
+
+    pub fn write_to_file(&self, filename: &Path) -> Result<(), TgaError> {
+        self.write_to_file_with_options(filename, TgaWriteOptions::default())
+    }
+
+    pub fn write_to_file_with_options(&self, filename: &Path, options: TgaWriteOptions) -> Result<(), TgaError> {
+        let file = File::create(filename)?;
+        self.encode_with_options(BufWriter::new(file), options)
+    }
+
+    // Writes an uncompressed 8 bit grayscale image (data type 3) using the red channel as luminance
+    pub fn write_to_file_grayscale(&self, filename: &Path) -> Result<(), TgaError> {
+        let file = File::create(filename)?;
+        self.encode_grayscale(BufWriter::new(file))
+    }
+
+    pub fn encode<W: Write>(&self, writer: W) -> Result<(), TgaError> {
+        self.encode_with_options(writer, TgaWriteOptions::default())
+    }
+
+    pub fn encode_with_options<W: Write>(&self, mut writer: W, options: TgaWriteOptions) -> Result<(), TgaError> {
+        let bytes_per_pixel = if options.alpha { 4 } else { 3 };
+
+        // TGA Header, data type 2 or 10 (RLE), 24 or 32 bits per pixel
+        // with the alpha channel bits in the image descriptor
+        writer.write_all(&self.header(if options.rle { 10 } else { 2 }, 8 * bytes_per_pixel, if options.alpha { 8 } else { 0 })?)?;
+
+        // pixel data, one write per scanline through a reused buffer
+        let mut data = Vec::<u8>::with_capacity((bytes_per_pixel as i32 * self.width * 2) as usize);
+        for row in self.pixels.chunks(self.width as usize) {
+            data.clear();
+            if options.rle {
+                encode_rle_row(row, &mut data, options.alpha);
             } else {
-                for _ in 0..count {
-                    let b = data.next().unwrap();
-                    let g = data.next().unwrap();
-                    let r = data.next().unwrap();
-                
-                    image.set_pixel(pixel % width, pixel / width, &RgbaColor::new_from_u8(r, g, b, 255));
-                    pixel += 1;
+                for p in row.iter() {
+                    push_pixel(&mut data, p, options.alpha);
                 }
             }
+            writer.write_all(&data[..])?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn encode_grayscale<W: Write>(&self, mut writer: W) -> Result<(), TgaError> {
+        writer.write_all(&self.header(3, 8, 0)?)?;
+
+        let mut data = Vec::<u8>::with_capacity(self.width as usize);
+        for row in self.pixels.chunks(self.width as usize) {
+            data.clear();
+            data.extend(row.iter().map(|p| p.r));
+            writer.write_all(&data[..])?;
         }
-        
-        return image;
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    // Writes a PNG instead of a TGA, RGBA when alpha is set and RGB otherwise
+    pub fn write_png(&self, filename: &Path, alpha: bool) -> Result<(), TgaError> {
+        let file = File::create(filename)?;
+        self.encode_png(BufWriter::new(file), alpha)
+    }
+
+    pub fn encode_png<W: Write>(&self, writer: W, alpha: bool) -> Result<(), TgaError> {
+        let channels = if alpha { 4 } else { 3 };
+        let mut data = Vec::<u8>::with_capacity((channels * self.width * self.height) as usize);
+        self.push_rows_top_down(&mut data, alpha);
+
+        png::encode(writer, self.width as u32, self.height as u32, channels as u8, &data)?;
+        Ok(())
+    }
+
+    // Picks PNG or TGA by the file extension, anything but .png is written as TGA
+    pub fn save(&self, filename: &Path) -> Result<(), TgaError> {
+        match filename.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("png") => self.write_png(filename, false),
+            _ => self.write_to_file(filename)
+        }
+    }
+
+    // Binary PPM (P6), alpha is dropped
+    pub fn write_ppm<W: Write>(&self, writer: W) -> Result<(), TgaError> {
+        let header = format!("P6\n{} {}\n255\n", self.width, self.height);
+        self.write_netpbm(writer, header, false)
+    }
+
+    // PAM (P7) with an RGB_ALPHA tuple type so alpha survives
+    pub fn write_pam<W: Write>(&self, writer: W) -> Result<(), TgaError> {
+        let header = format!("P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n", self.width, self.height);
+        self.write_netpbm(writer, header, true)
+    }
+
+    fn write_netpbm<W: Write>(&self, mut writer: W, header: String, alpha: bool) -> Result<(), TgaError> {
+        let channels = if alpha { 4 } else { 3 };
+        let mut data = Vec::<u8>::with_capacity(header.len() + (channels * self.width * self.height) as usize);
+        data.extend(header.as_bytes());
+        self.push_rows_top_down(&mut data, alpha);
+
+        writer.write_all(&data[..])?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    // Reads binary PPM (P6) or PAM (P7) with RGB or RGB_ALPHA tuples and a maxval of 255
+    pub fn read_ppm<R: Read>(mut reader: R) -> Result<TgaImage, TgaError> {
+        let (mut width, mut height, mut depth, mut maxval) = (0, 0, 3, 0);
+
+        match read_token(&mut reader)?.as_str() {
+            "P6" => {
+                width = parse_header_value(&read_token(&mut reader)?)?;
+                height = parse_header_value(&read_token(&mut reader)?)?;
+                maxval = parse_header_value(&read_token(&mut reader)?)?;
+            },
+            "P7" => loop {
+                let key = read_token(&mut reader)?;
+                if key == "ENDHDR" { break; }
+                let value = read_token(&mut reader)?;
+                match key.as_str() {
+                    "WIDTH" => width = parse_header_value(&value)?,
+                    "HEIGHT" => height = parse_header_value(&value)?,
+                    "DEPTH" => depth = parse_header_value(&value)?,
+                    "MAXVAL" => maxval = parse_header_value(&value)?,
+                    _ => {}
+                }
+            },
+            magic => return Err(TgaError::Invalid(format!("unknown magic {:?}", magic)))
+        }
+
+        if maxval != 255 || (depth != 3 && depth != 4) {
+            return Err(TgaError::Invalid(format!("can't read depth {} with maxval {}", depth, maxval)));
+        }
+        let bytes = match width.checked_mul(height).and_then(|n| n.checked_mul(depth)) {
+            Some(bytes) if width > 0 && height > 0 => bytes as usize,
+            _ => return Err(TgaError::InvalidDimensions { width: width, height: height })
+        };
+
+        // Read up front so a header promising more than there is fails
+        // before the image is allocated
+        let mut data = Vec::new();
+        (&mut reader).take(bytes as u64).read_to_end(&mut data)?;
+        if data.len() < bytes {
+            return Err(TgaError::UnexpectedEof);
+        }
+
+        let mut image = TgaImage::new(width, height);
+        for (row, y) in data.chunks((width * depth) as usize).zip((0..height).rev()) {
+            for (x, p) in row.chunks(depth as usize).enumerate() {
+                let idx = (x as i32 + width * y) as usize;
+                image.pixels[idx] = TgaPixel { r: p[0], g: p[1], b: p[2], a: if depth == 4 { p[3] } else { 255 } };
+            }
+        }
+
+        Ok(image)
+    }
+
+    // RGB(A) bytes with the top row first, as most formats other than TGA expect
+    fn push_rows_top_down(&self, data: &mut Vec<u8>, alpha: bool) {
+        for row in self.pixels.chunks(self.width as usize).rev() {
+            for p in row.iter() {
+                data.extend(&[p.r, p.g, p.b]);
+                if alpha { data.push(p.a); }
+            }
+        }
+    }
+
+    // TGA stores both sizes in 16 bits, larger images can't be written
+    fn header(&self, data_type: u8, bpp: u8, img_desc: u8) -> Result<[u8; 18], TgaError> {
+        if self.width > 0xFFFF || self.height > 0xFFFF {
+            return Err(TgaError::InvalidDimensions { width: self.width, height: self.height });
+        }
+
+        Ok([
+            0, 0, data_type, 0,0,0,0,0,0,0,0,0,
+            (self.width & 0xFF) as u8,
+            (self.width >> 8 & 0xFF) as u8,
+            (self.height & 0xFF) as u8,
+            (self.height >> 8 & 0xFF) as u8,
+            bpp,
+            img_desc
+        ])
+    }
+
+    pub fn new_from_file(filename: &Path) -> Result<TgaImage, TgaError> {
+        let file = File::open(filename)?;
+        TgaImage::decode(BufReader::new(file))
+    }
+
+    pub fn decode<R: Read>(mut reader: R) -> Result<TgaImage, TgaError> {
+        let mut header = [0u8; 18];
+        reader.read_exact(&mut header)?;
+        let le16 = |i: usize| header[i] as usize | (header[i + 1] as usize) << 8;
+
+        let id_len = header[0] as usize;
+        let color_map_type = header[1];
+        let data_type = header[2];
+        let color_map_first = le16(3);
+        let color_map_len = le16(5);
+        let color_map_bits = header[7];
+        // x and y origin at 8 and 10 are not used
+        let width = le16(12) as i32;
+        let height = le16(14) as i32;
+        let bpp = header[16];
+        let img_desc = header[17];
+
+        let mut id = vec![0u8; id_len];
+        reader.read_exact(&mut id)?;
+
+        // Low bits give the kind of image data, bit 3 marks run-length encoding
+        let kind = data_type & 7;
+        let rle = data_type & 8 != 0;
+        let color_mapped = kind == 1;
+        let mut palette: Vec<RgbaColor> = Vec::with_capacity(color_map_len);
+
+        if color_map_type != 0 {
+            match color_map_bits {
+                15 | 16 | 24 | 32 => {},
+                _ => return Err(TgaError::UnsupportedFormat { data_type: data_type, bpp: color_map_bits })
+            }
+            // Read even when unused by true color images, to skip over it
+            for _ in 0..color_map_len {
+                palette.push(read_color(&mut reader, color_map_bits)?);
+            }
+        } else if color_mapped {
+            return Err(TgaError::UnsupportedFormat { data_type: data_type, bpp: bpp });
+        }
+
+        match (data_type, bpp) {
+            (1, 8) | (1, 16) | (9, 8) | (9, 16) => {},
+            (2, 15) | (2, 16) | (2, 24) | (2, 32) => {},
+            (10, 15) | (10, 16) | (10, 24) | (10, 32) => {},
+            (3, 8) | (3, 16) | (11, 8) | (11, 16) => {},
+            _ => return Err(TgaError::UnsupportedFormat { data_type: data_type, bpp: bpp })
+        }
+
+        // Up to 65535 on each side, which can overflow the pixel count
+        if width == 0 || height == 0 || width.checked_mul(height).is_none() {
+            return Err(TgaError::InvalidDimensions { width: width, height: height });
+        }
+
+        // The attribute bit of 16 bit colors is only alpha when the descriptor says so
+        let color_bits = if bpp == 16 && img_desc & 0x0F == 0 { 15 } else { bpp };
+
+        // Pixels are color map indices, colors or luminance with optional alpha
+        let read = |reader: &mut R| -> Result<RgbaColor, TgaError> {
+            match kind {
+                1 => {
+                    let mut buf = [0u8; 2];
+                    reader.read_exact(&mut buf[..bpp as usize / 8])?;
+                    let index = buf[0] as usize | (buf[1] as usize) << 8;
+                    match palette.get(index.wrapping_sub(color_map_first)) {
+                        Some(c) => Ok(*c),
+                        None => Err(TgaError::InvalidColorIndex(index))
+                    }
+                },
+                3 => {
+                    let mut buf = [0u8, 255];
+                    reader.read_exact(&mut buf[..bpp as usize / 8])?;
+                    Ok(RgbaColor::new_from_u8(buf[0], buf[0], buf[0], buf[1]))
+                },
+                _ => Ok(read_color(reader, color_bits)?)
+            }
+        };
+
+        // Pixels are collected in file order as they are read, so a header
+        // promising more than the data holds can't allocate the whole image
+        // up front. Runs past the last pixel are dropped
+        let total = (width * height) as usize;
+        let mut pixels: Vec<TgaPixel> = Vec::with_capacity(total.min(1 << 20));
+        let push = |pixels: &mut Vec<TgaPixel>, color: &RgbaColor| {
+            let mut p = TgaPixel::default();
+            p.set_color(color);
+            pixels.push(p);
+        };
+
+        if !rle {
+            while pixels.len() < total {
+                let color = read(&mut reader)?;
+                push(&mut pixels, &color);
+            }
+        } else {
+            while pixels.len() < total {
+                let mut packet = [0u8];
+                reader.read_exact(&mut packet)?;
+                let count = (packet[0] & 127) + 1;
+
+                if packet[0] & 128 > 0 {
+                    let color = read(&mut reader)?;
+                    for _ in 0..count {
+                        push(&mut pixels, &color);
+                    }
+                } else {
+                    for _ in 0..count {
+                        let color = read(&mut reader)?;
+                        push(&mut pixels, &color);
+                    }
+                }
+            }
+            pixels.truncate(total);
+        }
+
+        // File order is bottom to top and left to right unless image
+        // descriptor bits 4 and 5 say otherwise. Pixel (0, 0) is always the
+        // bottom left corner
+        let mut image = TgaImage { width: width, height: height, pixels: pixels };
+        if img_desc & 0x10 != 0 { image.flip_horizontal(); }
+        if img_desc & 0x20 != 0 { image.flip_vertical(); }
+
+        return Ok(image);
     }
 }
@@ -0,0 +1,106 @@
+// Exercises the library the way a dependent crate would, public items only
+
+extern crate renderer;
+
+use std::path::Path;
+use renderer::{Renderer,Shading,Vertex,Model,Texture,TgaImage,RgbaColor,Vec2f,Vec3f,Mat4};
+
+fn vertex(x: f32, y: f32) -> Vertex {
+    Vertex {
+        p: Vec3f::new(x, y, 0.0),
+        t: Vec2f::new(0.0, 0.0),
+        i: 1.0,
+        n: Vec3f::new(0.0, 0.0, 1.0),
+        world: Vec3f::new(0.0, 0.0, 0.0),
+        c: RgbaColor::new(1.0, 0.0, 0.0, 1.0)
+    }
+}
+
+#[test]
+fn screen_space_triangle() {
+    let mut renderer = Renderer::new(32, 32);
+    renderer.triangle(vertex(2.0, 2.0), vertex(30.0, 2.0), vertex(2.0, 30.0));
+
+    let image = renderer.to_image();
+    let (inside, outside) = (image.get_pixel(5, 5), image.get_pixel(28, 28));
+    assert!(inside.r > 0.9 && inside.g == 0.0 && inside.b == 0.0);
+    assert_eq!(outside.r, 0.0);
+    assert_eq!(renderer.stats().drawn, 1);
+}
+
+#[test]
+fn model_built_in_memory() {
+    let vertices = vec![Vec3f::new(-1.0, -1.0, 0.0), Vec3f::new(1.0, -1.0, 0.0), Vec3f::new(0.0, 1.0, 0.0)];
+    let model = Model::new(vertices, Vec::new(), Vec::new(), vec![[0, -1, -1, 1, -1, -1, 2, -1, -1]]).unwrap();
+
+    let mut renderer = Renderer::new(64, 64);
+    renderer.set_shading(Shading::Flat);
+    renderer.draw_model(&model, &Mat4::identity());
+
+    let stats = renderer.stats();
+    assert_eq!((stats.submitted, stats.drawn), (1, 1));
+    assert!(stats.fragments.passed > 0);
+    assert!(renderer.to_image().get_pixel(32, 32).r > 0.0);
+}
+
+// Needs the assets in data/, skipped when they're missing
+#[test]
+fn head_render() {
+    let (obj, tga) = (Path::new("data/model.obj"), Path::new("data/diffuse.tga"));
+    if !obj.exists() || !tga.exists() {
+        return;
+    }
+
+    let mut model = Model::new_from_file(obj).unwrap();
+    model.normalize_to_unit_cube();
+    let mut renderer = Renderer::new(200, 200);
+    renderer.set_diffuse(Texture::new(TgaImage::new_from_file(tga).unwrap()));
+    renderer.set_shading(Shading::Gouraud);
+    renderer.draw_model(&model, &Mat4::identity());
+
+    let stats = renderer.stats();
+    assert_eq!(stats.submitted, stats.drawn + stats.culled + stats.clipped + stats.degenerate);
+    assert!(stats.drawn > 0);
+}
+
+#[test]
+fn gamma_correction_is_opt_in() {
+    let half_lit = |gamma: bool| {
+        let mut renderer = Renderer::new(8, 8);
+        if gamma {
+            renderer.set_gamma_correction(true);
+        }
+        let mut v = [vertex(0.0, 0.0), vertex(8.0, 0.0), vertex(0.0, 8.0)];
+        for v in v.iter_mut() {
+            v.i = 0.5;
+            v.c = RgbaColor::new(1.0, 1.0, 1.0, 1.0);
+        }
+        renderer.triangle(v[0], v[1], v[2]);
+        renderer.to_image().get_pixel(1, 1).g
+    };
+
+    // Stored as shaded by default, encoded to sRGB with correction
+    assert!((half_lit(false) - 0.5).abs() < 1.0 / 255.0);
+    assert!(half_lit(true) > 0.7);
+}
+
+#[test]
+fn stats_count_one_drawn_and_one_off_screen() {
+    let vertices = vec![Vec3f::new(-0.5, -0.5, 0.0), Vec3f::new(0.5, -0.5, 0.0), Vec3f::new(-0.5, 0.5, 0.0),
+                        Vec3f::new(5.0, 5.0, 0.0), Vec3f::new(6.0, 5.0, 0.0), Vec3f::new(5.0, 6.0, 0.0)];
+    let faces = vec![[0, -1, -1, 1, -1, -1, 2, -1, -1], [3, -1, -1, 4, -1, -1, 5, -1, -1]];
+    let model = Model::new(vertices, Vec::new(), Vec::new(), faces).unwrap();
+
+    let mut renderer = Renderer::new(64, 64);
+    renderer.draw_model(&model, &Mat4::identity());
+    let stats = renderer.stats();
+    assert_eq!((stats.submitted, stats.drawn, stats.clipped, stats.culled, stats.degenerate), (2, 1, 1, 0, 0));
+
+    // Half of a 32x32 square, give or take the pixels along the diagonal
+    let area = 32 * 32 / 2;
+    assert!(stats.fragments.passed + 32 >= area && stats.fragments.passed <= area + 32, "{} fragments", stats.fragments.passed);
+
+    renderer.clear(RgbaColor::new(0.0, 0.0, 0.0, 1.0));
+    let stats = renderer.stats();
+    assert_eq!((stats.submitted, stats.drawn, stats.clipped, stats.fragments.passed), (0, 0, 0, 0));
+}
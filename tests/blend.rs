@@ -0,0 +1,76 @@
+extern crate renderer;
+
+use renderer::{Renderer,Vertex,TgaImage,RgbaColor,Vec2f,Vec3f};
+use renderer::framebuffer::{Framebuffer,BlendMode};
+
+const RED_HALF: RgbaColor = RgbaColor { r: 1.0, g: 0.0, b: 0.0, a: 0.5 };
+const BLUE: RgbaColor = RgbaColor { r: 0.0, g: 0.0, b: 1.0, a: 1.0 };
+
+fn vertex(x: f32, y: f32, z: f32, c: RgbaColor) -> Vertex {
+    Vertex {
+        p: Vec3f::new(x, y, z),
+        t: Vec2f::new(0.0, 0.0),
+        i: 1.0,
+        n: Vec3f::new(0.0, 0.0, 1.0),
+        world: Vec3f::new(0.0, 0.0, 0.0),
+        c: c
+    }
+}
+
+// Screen-space triangle covering the whole 8x8 image
+fn cover(renderer: &mut Renderer, z: f32, c: RgbaColor) {
+    renderer.triangle(vertex(-1.0, -1.0, z, c), vertex(20.0, -1.0, z, c), vertex(-1.0, 20.0, z, c));
+}
+
+#[test]
+fn half_red_over_blue_is_purple() {
+    let mut framebuffer = Framebuffer::new(1, 1);
+    framebuffer.set_pixel(0, 0, &BLUE);
+    framebuffer.set_pixel_blend(0, 0, &RED_HALF);
+    assert_eq!(framebuffer.get_pixel(0, 0), Some(RgbaColor::new(0.5, 0.0, 0.5, 1.0)));
+}
+
+#[test]
+fn translucent_triangles_blend_over_what_is_drawn() {
+    let mut renderer = Renderer::new(8, 8);
+    cover(&mut renderer, 0.0, BLUE);
+    cover(&mut renderer, 0.5, RED_HALF);
+    let c = renderer.to_image().get_pixel(4, 4);
+    assert!((c.r - 0.5).abs() < 0.01 && c.g == 0.0 && (c.b - 0.5).abs() < 0.01 && c.a == 1.0, "{:?}", c);
+
+    // Behind the opaque surface it is hidden
+    let mut renderer = Renderer::new(8, 8);
+    cover(&mut renderer, 0.5, BLUE);
+    cover(&mut renderer, 0.0, RED_HALF);
+    assert_eq!(renderer.to_image().get_pixel(4, 4), BLUE);
+}
+
+#[test]
+fn half_white_gives_exact_bytes() {
+    let white_half = RgbaColor::new(1.0, 1.0, 1.0, 0.5);
+    let black = RgbaColor::new_from_u8(0, 0, 0, 255);
+    let gray = RgbaColor::new_from_u8(128, 128, 128, 255);
+
+    // Quantizing truncates, 127.5 over black and 191.5 over gray
+    for &(below, alpha_blend, additive) in [(black, 127, 127), (gray, 191, 255)].iter() {
+        let mut image = TgaImage::new(1, 1);
+        image.set_pixel(0, 0, &below);
+        image.set_pixel_blend(0, 0, &white_half);
+        assert_eq!(image.get_pixel(0, 0), RgbaColor::new_from_u8(alpha_blend, alpha_blend, alpha_blend, 255));
+
+        // Replace keeps the incoming alpha as well
+        for &(mode, expected, alpha) in [(BlendMode::AlphaBlend, alpha_blend, 255), (BlendMode::Additive, additive, 255), (BlendMode::Replace, 255, 127)].iter() {
+            let mut framebuffer = Framebuffer::new(1, 1);
+            framebuffer.set_pixel(0, 0, &below);
+            framebuffer.set_pixel_blended(0, 0, &white_half, mode);
+            assert_eq!(framebuffer.to_image(false, false).get_pixel(0, 0), RgbaColor::new_from_u8(expected, expected, expected, alpha), "{:?}", mode);
+        }
+    }
+
+    // The same through the renderer's triangle fill
+    let mut renderer = Renderer::new(8, 8);
+    cover(&mut renderer, 0.0, gray);
+    renderer.set_blend_mode(BlendMode::AlphaBlend);
+    cover(&mut renderer, 0.5, white_half);
+    assert_eq!(renderer.to_image().get_pixel(4, 4), RgbaColor::new_from_u8(191, 191, 191, 255));
+}
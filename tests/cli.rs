@@ -0,0 +1,112 @@
+extern crate renderer;
+
+use std::path::PathBuf;
+use renderer::{Shading,RgbaColor};
+use renderer::cli::{parse_args,parse_color,Command,Config,Mode,CliError};
+
+fn parse(args: &[&str]) -> Result<Command, CliError> {
+    parse_args(args.iter().map(|a| a.to_string()))
+}
+
+fn config(args: &[&str]) -> Config {
+    match parse(args) {
+        Ok(Command::Render(config)) => config,
+        other => panic!("{:?} parsed as {:?}", args, other)
+    }
+}
+
+#[test]
+fn defaults_render_the_bundled_head() {
+    let config = config(&[]);
+    assert_eq!(config, Config::default());
+    assert_eq!(config.model, PathBuf::from("data/model.obj"));
+    assert_eq!(config.diffuse, Some(PathBuf::from("data/diffuse.tga")));
+    assert_eq!(config.mode, Mode::Shaded(Shading::Gouraud));
+}
+
+#[test]
+fn all_options() {
+    let config = config(&["--model", "cube.ply", "--diffuse", "bricks.tga", "--glow", "eyes.tga", "--bloom", "--output", "out.png", "--width", "320",
+                          "--height", "240", "--shading", "flat", "--color", "#ff8000", "--perspective", "--no-cull",
+                          "--threads", "4", "--ssaa", "2", "--stats"]);
+    assert_eq!(config, Config {
+        model: PathBuf::from("cube.ply"),
+        diffuse: Some(PathBuf::from("bricks.tga")),
+        glow: Some(PathBuf::from("eyes.tga")),
+        bloom: true,
+        output: PathBuf::from("out.png"),
+        width: 320,
+        height: 240,
+        mode: Mode::Shaded(Shading::Flat),
+        color: RgbaColor::new_from_u8(255, 128, 0, 255),
+        perspective: true,
+        cull: false,
+        threads: 4,
+        ssaa: 2,
+        stats: true,
+        preview: false,
+        frames: None,
+        out_dir: PathBuf::from("."),
+        frame_numbers: false
+    });
+}
+
+#[test]
+fn own_model_is_untextured_by_default() {
+    let config = config(&["--model", "teapot.obj", "--shading", "wireframe"]);
+    assert_eq!(config.diffuse, None);
+    assert_eq!((config.glow, config.bloom), (None, false));
+    assert_eq!(config.mode, Mode::Wireframe);
+    assert_eq!(config.output, PathBuf::from("output.tga"));
+    assert_eq!(self::config(&["--png"]).output, PathBuf::from("output.png"));
+}
+
+#[test]
+fn preview_unless_writing_a_file() {
+    assert!(config(&["--preview"]).preview);
+    assert!(config(&["--output", "out.tga", "--preview"]).preview);
+    assert!(!config(&["--png"]).preview);
+    assert_eq!(config(&[]).preview, cfg!(feature = "preview"));
+}
+
+#[test]
+fn turntable() {
+    let config = config(&["--frames", "36", "--out-dir", "frames", "--frame-numbers"]);
+    assert_eq!((config.frames, config.out_dir, config.preview, config.frame_numbers), (Some(36), PathBuf::from("frames"), false, true));
+    assert_eq!(self::config(&[]).frames, None);
+    assert!(!self::config(&[]).frame_numbers);
+}
+
+#[test]
+fn help_and_benchmark() {
+    assert_eq!(parse(&["--width", "10", "--help"]), Ok(Command::Help));
+    assert_eq!(parse(&["--bench"]), Ok(Command::Benchmark));
+}
+
+#[test]
+fn malformed_arguments() {
+    assert_eq!(parse(&["--fast"]), Err(CliError::UnknownOption("--fast".to_string())));
+    assert_eq!(parse(&["model.obj"]), Err(CliError::UnknownOption("model.obj".to_string())));
+    assert_eq!(parse(&["--output"]), Err(CliError::MissingValue("--output".to_string())));
+    assert_eq!(parse(&["--width", "60000", "--height", "60000"]), Err(CliError::TooLarge { width: 60000, height: 60000, ssaa: 1 }));
+    assert_eq!(parse(&["--width", "20000", "--height", "20000", "--ssaa", "4"]), Err(CliError::TooLarge { width: 20000, height: 20000, ssaa: 4 }));
+    for args in [["--width", "0"], ["--height", "tall"], ["--threads", "-1"], ["--shading", "cel"], ["--color", "fff"]].iter() {
+        match parse(args) {
+            Err(CliError::InvalidValue { ref option, ref value, .. }) => assert_eq!((option.as_str(), value.as_str()), (args[0], args[1])),
+            other => panic!("{:?} parsed as {:?}", args, other)
+        }
+    }
+}
+
+#[test]
+fn toon_shading() {
+    assert_eq!(config(&["--shading", "toon"]).mode, Mode::Shaded(Shading::Toon { bands: 3 }));
+}
+
+#[test]
+fn colors() {
+    assert_eq!(parse_color("000000"), Some(RgbaColor::new(0.0, 0.0, 0.0, 1.0)));
+    assert_eq!(parse_color("#FFffFF"), Some(RgbaColor::new(1.0, 1.0, 1.0, 1.0)));
+    assert_eq!(parse_color("12345g"), None);
+    assert_eq!(parse_color("#1234567"), None);
+}
@@ -0,0 +1,49 @@
+// Setup shared by the integration tests, pulled in with `mod common`. Not
+// every test uses all of it
+#![allow(dead_code)]
+
+use renderer::{Renderer,Model,TgaImage,RgbaColor,Vec2f,Vec3f};
+
+// Unlit and without culling, so whatever is drawn comes out in its own colors
+pub fn unlit(width: i32, height: i32) -> Renderer {
+    let mut renderer = Renderer::new(width, height);
+    renderer.clear_lights();
+    renderer.set_ambient(1.0);
+    renderer.set_backface_culling(false);
+    renderer
+}
+
+// Square of half size s at depth z facing the camera, mapping the whole texture
+pub fn square(s: f32, z: f32) -> Model {
+    let vertices = vec![Vec3f::new(-s, -s, z), Vec3f::new(s, -s, z), Vec3f::new(s, s, z), Vec3f::new(-s, s, z)];
+    let uvs = vec![Vec2f::new(0.0, 0.0), Vec2f::new(1.0, 0.0), Vec2f::new(1.0, 1.0), Vec2f::new(0.0, 1.0)];
+    Model::new(vertices, uvs, Vec::new(), vec![[0, 0, -1, 1, 1, -1, 2, 2, -1], [0, 0, -1, 2, 2, -1, 3, 3, -1]]).unwrap()
+}
+
+// Cube from -s to s, sides facing out and each mapping the whole texture
+pub fn cube(s: f32) -> Model {
+    let mut vertices = Vec::new();
+    for i in 0..8 {
+        let corner = |bit: i32| if i & bit != 0 { s } else { -s };
+        vertices.push(Vec3f::new(corner(1), corner(2), corner(4)));
+    }
+    let uvs = vec![Vec2f::new(0.0, 0.0), Vec2f::new(1.0, 0.0), Vec2f::new(1.0, 1.0), Vec2f::new(0.0, 1.0)];
+    let sides = [[0, 2, 3, 1], [4, 5, 7, 6], [0, 1, 5, 4], [2, 6, 7, 3], [0, 4, 6, 2], [1, 3, 7, 5]];
+    let mut faces = Vec::new();
+    for s in sides.iter() {
+        faces.push([s[0], 0, -1, s[1], 1, -1, s[2], 2, -1]);
+        faces.push([s[0], 0, -1, s[2], 2, -1, s[3], 3, -1]);
+    }
+    Model::new(vertices, uvs, Vec::new(), faces).unwrap()
+}
+
+// size x size image of a single color
+pub fn solid(size: i32, color: RgbaColor) -> TgaImage {
+    let mut image = TgaImage::new(size, size);
+    for y in 0..size {
+        for x in 0..size {
+            image.set_pixel(x, y, &color);
+        }
+    }
+    image
+}
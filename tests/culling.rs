@@ -0,0 +1,61 @@
+extern crate renderer;
+
+mod common;
+
+use std::path::Path;
+use std::time::{Duration,Instant};
+use renderer::{Renderer,Model,Shading,Winding,Mat4};
+use common::{unlit,cube};
+
+// Turned so three sides face the camera and three face away
+fn draw_cube(renderer: &mut Renderer) {
+    renderer.draw_model(&cube(0.5), &(Mat4::rotation_x(0.5) * Mat4::rotation_y(0.7)));
+}
+
+#[test]
+fn half_of_a_cube_is_culled() {
+    let mut renderer = unlit(64, 64);
+    renderer.set_backface_culling(true);
+    draw_cube(&mut renderer);
+    let stats = renderer.stats();
+    assert_eq!((stats.submitted, stats.drawn, stats.culled), (12, 6, 6));
+    let front = renderer.to_image();
+
+    // Clockwise fronts keep the inside instead, which covers the same pixels
+    let mut renderer = unlit(64, 64);
+    renderer.set_backface_culling(true);
+    renderer.set_front_face(Winding::Clockwise);
+    draw_cube(&mut renderer);
+    let stats = renderer.stats();
+    assert_eq!((stats.drawn, stats.culled), (6, 6));
+    assert!(front.diff_with(&renderer.to_image(), 0, false).is_match());
+
+    let mut renderer = unlit(64, 64);
+    draw_cube(&mut renderer);
+    assert_eq!((renderer.stats().drawn, renderer.stats().culled), (12, 0));
+}
+
+// Wall clock of the head drawn with culling on and off, best of a few runs.
+// Timing depends on the machine, run with cargo test --release -- --ignored
+#[test]
+#[ignore]
+fn culling_speeds_up_the_head() {
+    let mut model = Model::new_from_file(Path::new("data/model.obj")).unwrap();
+    model.normalize_to_unit_cube();
+    let time = |cull: bool| -> Duration {
+        (0..5).map(|_| {
+            let mut renderer = Renderer::new(800, 800);
+            renderer.set_shading(Shading::Gouraud);
+            renderer.set_backface_culling(cull);
+            let start = Instant::now();
+            renderer.draw_model(&model, &Mat4::identity());
+            let elapsed = start.elapsed();
+            assert_eq!(renderer.stats().culled > 0, cull);
+            elapsed
+        }).min().unwrap()
+    };
+
+    let (on, off) = (time(true), time(false));
+    println!("culling on {:?}, off {:?}", on, off);
+    assert!(on < off, "culling on took {:?}, off {:?}", on, off);
+}
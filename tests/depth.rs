@@ -0,0 +1,234 @@
+extern crate renderer;
+
+use renderer::{Renderer,DepthBuffer,Vertex,Model,RgbaColor,Vec2f,Vec3f,Mat4};
+use renderer::framebuffer::DepthFunc;
+
+fn vertex(x: f32, y: f32, z: f32, c: RgbaColor) -> Vertex {
+    Vertex {
+        p: Vec3f::new(x, y, z),
+        t: Vec2f::new(0.0, 0.0),
+        i: 1.0,
+        n: Vec3f::new(0.0, 0.0, 1.0),
+        world: Vec3f::new(0.0, 0.0, 0.0),
+        c: c
+    }
+}
+
+// Red at depth 0.75 then blue at 0.25 over the same square, returns the color
+// the middle ends up with
+fn overlap(func: DepthFunc, depth_write: bool) -> RgbaColor {
+    let mut renderer = Renderer::new(16, 16);
+    renderer.set_depth_test(func);
+    renderer.set_depth_write(depth_write);
+    for &(z, c) in [(0.75, RgbaColor::new(1.0, 0.0, 0.0, 1.0)), (0.25, RgbaColor::new(0.0, 0.0, 1.0, 1.0))].iter() {
+        renderer.triangle(vertex(0.0, 0.0, z, c), vertex(16.0, 0.0, z, c), vertex(0.0, 16.0, z, c));
+    }
+    renderer.to_image().get_pixel(4, 4)
+}
+
+fn is_red(c: RgbaColor) -> bool {
+    c.r > 0.9 && c.b == 0.0
+}
+
+fn is_blue(c: RgbaColor) -> bool {
+    c.b > 0.9 && c.r == 0.0
+}
+
+#[test]
+fn nearer_triangle_wins_in_either_order() {
+    let (red, blue) = (RgbaColor::new(1.0, 0.0, 0.0, 1.0), RgbaColor::new(0.0, 0.0, 1.0, 1.0));
+    let (near, far) = ([-0.8, -0.8, 0.8, -0.8, 0.0, 0.8], [-0.9, -0.6, 0.9, -0.6, 0.0, 0.9]);
+    for &reversed in [false, true].iter() {
+        let mut vertices = Vec::new();
+        let mut order = vec![(near, 0.5), (far, -0.5)];
+        if reversed {
+            order.reverse();
+        }
+        for &(ref xy, z) in order.iter() {
+            for c in xy.chunks(2) {
+                vertices.push(Vec3f::new(c[0], c[1], z));
+            }
+        }
+        let faces = vec![[0, -1, -1, 1, -1, -1, 2, -1, -1], [3, -1, -1, 4, -1, -1, 5, -1, -1]];
+        let mut model = Model::new(vertices, Vec::new(), Vec::new(), faces).unwrap();
+        model.vertex_colors = if reversed { vec![blue, blue, blue, red, red, red] } else { vec![red, red, red, blue, blue, blue] };
+
+        let mut renderer = Renderer::new(16, 16);
+        renderer.clear_lights();
+        renderer.set_ambient(1.0);
+        renderer.draw_model(&model, &Mat4::identity());
+        assert!(is_red(renderer.to_image().get_pixel(8, 6)), "reversed: {}", reversed);
+        assert!(renderer.depth_at(8, 6).unwrap() > 0.5);
+    }
+}
+
+#[test]
+fn greater_keeps_the_larger_depth() {
+    assert!(is_red(overlap(DepthFunc::Greater, true)));
+    assert!(is_red(overlap(DepthFunc::GreaterEqual, true)));
+}
+
+#[test]
+fn less_keeps_the_smaller_depth() {
+    assert!(is_blue(overlap(DepthFunc::Less, true)));
+    assert!(is_blue(overlap(DepthFunc::LessEqual, true)));
+}
+
+#[test]
+fn always_and_never() {
+    assert!(is_blue(overlap(DepthFunc::Always, true)));
+    assert_eq!(overlap(DepthFunc::Never, true), RgbaColor::new(0.0, 0.0, 0.0, 0.0));
+}
+
+#[test]
+fn without_depth_writes_the_last_triangle_wins() {
+    // Both only test against the empty z-buffer
+    assert!(is_blue(overlap(DepthFunc::Greater, false)));
+}
+
+#[test]
+fn both_conventions_draw_the_same_model() {
+    // Two squares, the front one smaller so both show
+    let vertices = vec![
+        Vec3f::new(-1.0, -1.0, -0.5), Vec3f::new(1.0, -1.0, -0.5), Vec3f::new(1.0, 1.0, -0.5), Vec3f::new(-1.0, 1.0, -0.5),
+        Vec3f::new(-0.5, -0.5, 0.5), Vec3f::new(0.5, -0.5, 0.5), Vec3f::new(0.5, 0.5, 0.5), Vec3f::new(-0.5, 0.5, 0.5)
+    ];
+    let faces = vec![
+        [0, -1, -1, 1, -1, -1, 2, -1, -1], [0, -1, -1, 2, -1, -1, 3, -1, -1],
+        [4, -1, -1, 5, -1, -1, 6, -1, -1], [4, -1, -1, 6, -1, -1, 7, -1, -1]
+    ];
+    let model = Model::new(vertices, Vec::new(), Vec::new(), faces).unwrap();
+
+    let mut greater = Renderer::new(32, 32);
+    greater.draw_model(&model, &Mat4::rotation_y(0.3));
+
+    let mut less = Renderer::new(32, 32);
+    less.set_depth_test(DepthFunc::Less);
+    less.set_depth_range(0.0, 1.0);
+    less.draw_model(&model, &Mat4::rotation_y(0.3));
+
+    let (a, b) = (greater.to_image(), less.to_image());
+    for y in 0..32 {
+        for x in 0..32 {
+            assert_eq!(a.get_pixel(x, y), b.get_pixel(x, y), "at {}, {}", x, y);
+            let (za, zb) = (greater.depth_at(x, y), less.depth_at(x, y));
+            assert_eq!(za.is_some(), zb.is_some());
+            if let (Some(za), Some(zb)) = (za, zb) {
+                assert!((0.0..=1.0).contains(&za));
+                assert!((za + zb - 1.0).abs() < 1e-6);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_and_set_follows_the_function() {
+    let mut depth = DepthBuffer::new(2, 2);
+    assert_eq!(depth.get(0, 0), Some(f32::NEG_INFINITY));
+
+    // Empty pixels take anything but Never
+    assert!(!depth.test_and_set(0, 0, 0.5, DepthFunc::Never));
+    assert!(depth.test_and_set(0, 0, 0.5, DepthFunc::Less));
+
+    assert!(!depth.test_and_set(0, 0, 0.5, DepthFunc::Less));
+    assert!(depth.test_and_set(0, 0, 0.5, DepthFunc::LessEqual));
+    assert!(!depth.test_and_set(0, 0, 0.5, DepthFunc::Greater));
+    assert!(depth.test_and_set(0, 0, 0.5, DepthFunc::GreaterEqual));
+    assert!(depth.test_and_set(0, 0, 0.25, DepthFunc::Less));
+    assert_eq!(depth.get(0, 0), Some(0.25));
+    assert!(!depth.test_and_set(0, 0, 0.1, DepthFunc::Greater));
+    assert!(depth.test_and_set(0, 0, 0.1, DepthFunc::Always));
+    assert_eq!(depth.get(0, 0), Some(0.1));
+
+    depth.clear();
+    assert_eq!(depth.get(0, 0), Some(f32::NEG_INFINITY));
+}
+
+#[test]
+fn non_square_buffers_index_by_width() {
+    let mut depth = DepthBuffer::new(5, 3);
+    for y in 0..3 {
+        for x in 0..5 {
+            assert!(depth.test_and_set(x, y, (x + 10 * y) as f32, DepthFunc::Always));
+        }
+    }
+    for y in 0..3 {
+        for x in 0..5 {
+            assert_eq!(depth.get(x, y), Some((x + 10 * y) as f32));
+        }
+    }
+
+    for &(x, y) in [(-1, 0), (0, -1), (5, 0), (0, 3), (4, 4)].iter() {
+        assert_eq!(depth.get(x, y), None);
+        assert!(!depth.test_and_set(x, y, 1.0, DepthFunc::Always));
+    }
+
+    let image = depth.to_grayscale_image();
+    assert_eq!((image.width, image.height), (5, 3));
+    assert_eq!(image.get_pixel(0, 0).r, 0.0);
+    assert_eq!(image.get_pixel(4, 2).r, 1.0);
+}
+
+#[test]
+fn occlusion_queries() {
+    // Closer than 0.5 on the left half
+    let mut depth = DepthBuffer::new(8, 4);
+    for y in 0..4 {
+        for x in 0..4 {
+            depth.test_and_set(x, y, 0.75, DepthFunc::Always);
+        }
+    }
+    assert_eq!(depth.occlusion_query(0, 0, 4, 4, 0.5, DepthFunc::Greater), 0);
+    assert_eq!(depth.occlusion_query(2, 1, 6, 3, 0.5, DepthFunc::Greater), 4);
+    assert_eq!(depth.occlusion_query(0, 0, 4, 4, 0.9, DepthFunc::Greater), 16);
+    // Clipped to the buffer
+    assert_eq!(depth.occlusion_query(-10, -10, 100, 100, 0.5, DepthFunc::Greater), 16);
+    assert_eq!(depth.occlusion_query(10, 0, 20, 4, 0.5, DepthFunc::Greater), 0);
+
+    // The renderer's own z-buffer after drawing
+    let mut renderer = Renderer::new(16, 16);
+    let c = RgbaColor::new(1.0, 1.0, 1.0, 1.0);
+    renderer.triangle(vertex(0.0, 0.0, 0.5, c), vertex(16.0, 0.0, 0.5, c), vertex(0.0, 16.0, 0.5, c));
+    let zbuffer = renderer.depth_buffer();
+    assert_eq!(zbuffer.occlusion_query(0, 0, 4, 4, 0.25, DepthFunc::GreaterEqual), 0);
+    assert_eq!(zbuffer.occlusion_query(12, 12, 16, 16, 0.25, DepthFunc::GreaterEqual), 16);
+}
+
+#[test]
+fn depth_image_of_overlapping_triangles() {
+    let (red, blue) = (RgbaColor::new(1.0, 0.0, 0.0, 1.0), RgbaColor::new(0.0, 0.0, 1.0, 1.0));
+    let mut renderer = Renderer::new(16, 16);
+    renderer.triangle(vertex(0.0, 0.0, 200.0, red), vertex(12.0, 0.0, 200.0, red), vertex(0.0, 12.0, 200.0, red));
+    renderer.triangle(vertex(4.0, 4.0, 100.0, blue), vertex(16.0, 4.0, 100.0, blue), vertex(4.0, 16.0, 100.0, blue));
+
+    // (5, 5) is in both, (12, 6) only in the far one and (15, 15) in neither
+    let image = renderer.to_image();
+    assert!(is_red(image.get_pixel(5, 5)));
+    assert!(is_blue(image.get_pixel(12, 6)));
+    assert_eq!((renderer.depth_at(5, 5), renderer.depth_at(12, 6), renderer.depth_at(15, 15)), (Some(200.0), Some(100.0), None));
+    assert_eq!(renderer.depth_at(16, 0), None);
+
+    // Stretched from the farthest drawn depth up to the nearest
+    let depth = renderer.depth_image();
+    assert_eq!(depth.get_pixel(5, 5), RgbaColor::new(1.0, 1.0, 1.0, 1.0));
+    assert_eq!(depth.get_pixel(12, 6), RgbaColor::new(0.0, 0.0, 0.0, 1.0));
+    assert_eq!(depth.get_pixel(15, 15), RgbaColor::new(0.0, 0.0, 0.0, 1.0));
+}
+
+#[test]
+fn depth_image_without_a_range() {
+    let white = RgbaColor::new(1.0, 1.0, 1.0, 1.0);
+    let black = RgbaColor::new(0.0, 0.0, 0.0, 1.0);
+
+    // Nothing drawn
+    let renderer = Renderer::new(4, 4);
+    let depth = renderer.depth_image();
+    assert!((0..4).all(|y| (0..4).all(|x| depth.get_pixel(x, y) == black)));
+
+    // A single depth everywhere something was drawn
+    let mut renderer = Renderer::new(4, 4);
+    renderer.triangle(vertex(0.0, 0.0, 0.5, white), vertex(4.0, 0.0, 0.5, white), vertex(0.0, 4.0, 0.5, white));
+    let depth = renderer.depth_image();
+    assert_eq!(depth.get_pixel(0, 0), white);
+    assert_eq!(depth.get_pixel(3, 3), black);
+}
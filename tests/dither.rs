@@ -0,0 +1,54 @@
+extern crate renderer;
+
+use renderer::{Renderer,Vertex,TgaImage,RgbaColor,Vec2f,Vec3f};
+
+fn vertex(x: f32, y: f32, v: f32) -> Vertex {
+    Vertex {
+        p: Vec3f::new(x, y, 0.0),
+        t: Vec2f::new(0.0, 0.0),
+        i: 1.0,
+        n: Vec3f::new(0.0, 0.0, 1.0),
+        world: Vec3f::new(0.0, 0.0, 0.0),
+        c: RgbaColor::new(v, v, v, 1.0)
+    }
+}
+
+// Dark horizontal gradient where each 8 bit step spans about 8 pixels
+fn gradient(dither: bool) -> TgaImage {
+    let mut renderer = Renderer::new(256, 8);
+    renderer.set_dithering(dither);
+    let (left, right) = (0.0, 32.0 / 255.0);
+    renderer.triangle(vertex(0.0, 0.0, left), vertex(256.0, 0.0, right), vertex(256.0, 8.0, right));
+    renderer.triangle(vertex(0.0, 0.0, left), vertex(256.0, 8.0, right), vertex(0.0, 8.0, left));
+    renderer.to_image()
+}
+
+// Runs of at least 8 equal pixels along row y
+fn bands(image: &TgaImage, y: i32) -> usize {
+    let mut count = 0;
+    let mut run = 1;
+    for x in 1..image.width {
+        if image.get_pixel(x, y) == image.get_pixel(x - 1, y) {
+            run += 1;
+        } else {
+            if run >= 8 { count += 1; }
+            run = 1;
+        }
+    }
+    if run >= 8 { count += 1; }
+    count
+}
+
+#[test]
+fn dithering_breaks_up_bands() {
+    let plain = gradient(false);
+    let dithered = gradient(true);
+    for y in 0..8 {
+        let (before, after) = (bands(&plain, y), bands(&dithered, y));
+        assert!(before >= 16, "only {} bands without dithering in row {}", before, y);
+        assert!(after < before / 4, "{} bands with dithering against {} in row {}", after, before, y);
+    }
+
+    // The same pattern every time
+    assert!(gradient(true).diff_with(&dithered, 0, false).is_match());
+}
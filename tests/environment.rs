@@ -0,0 +1,82 @@
+extern crate renderer;
+
+use renderer::{Renderer,TgaImage,RgbaColor,Vec3f};
+use renderer::environment::Environment;
+
+// Latitude bands 22.5 degrees high, black from the horizon up to 22.5
+fn stripes() -> TgaImage {
+    let mut image = TgaImage::new(64, 32);
+    for y in 0..32 {
+        for x in 0..64 {
+            let v = if (y / 4) % 2 == 0 { 0.0 } else { 1.0 };
+            image.set_pixel(x, y, &RgbaColor::new(v, v, v, 1.0));
+        }
+    }
+    image
+}
+
+fn background(pitch: f32) -> TgaImage {
+    let mut renderer = Renderer::new(64, 64);
+    renderer.set_environment(stripes());
+    let eye = Vec3f::new(0.0, -pitch.sin(), pitch.cos()) * 2.0;
+    renderer.set_camera(eye, Vec3f::new(0.0, 0.0, 0.0), Vec3f::new(0.0, 1.0, 0.0));
+    renderer.set_camera_distance(2.0);
+    renderer.clear(RgbaColor::new(0.0, 0.0, 0.0, 1.0));
+    renderer.to_image()
+}
+
+fn white(image: &TgaImage, x: i32, y: i32) -> bool {
+    image.get_pixel(x, y).r > 0.5
+}
+
+// Rows up the column where the band changes, from black to white when rising
+fn transitions(image: &TgaImage, x: i32, rising: bool) -> Vec<i32> {
+    (1..image.height).filter(|&y| white(image, x, y) == rising && white(image, x, y - 1) != rising).collect()
+}
+
+#[test]
+fn stripes_span_the_background() {
+    let image = background(0.0);
+    // Level camera, the horizon runs through the middle
+    assert!((0..64).all(|x| !white(&image, x, 34)));
+    assert!((0..64).all(|x| white(&image, x, 29)));
+    assert_eq!(transitions(&image, 32, false), vec![32]);
+    assert_eq!(transitions(&image, 32, true).len(), 2);
+}
+
+#[test]
+fn stripes_move_down_and_bend_when_looking_up() {
+    let (level, up) = (background(0.0), background(0.3));
+    let horizon = |image: &TgaImage, x: i32| transitions(image, x, false)[0];
+    assert!(horizon(&up, 32) < horizon(&level, 32) - 10);
+
+    // The horizon stays straight while circles of latitude above the view
+    // direction bend up around the zenith
+    assert_eq!(horizon(&up, 0), horizon(&up, 32));
+    let (center, edge) = (transitions(&up, 32, true)[0], transitions(&up, 0, true)[0]);
+    assert!(edge > center + 1, "{} {}", center, edge);
+}
+
+#[test]
+fn no_seams() {
+    let mut image = TgaImage::new(8, 4);
+    for y in 0..4 {
+        image.set_pixel(0, y, &RgbaColor::new(1.0, 0.0, 0.0, 1.0));
+        image.set_pixel(7, y, &RgbaColor::new(0.0, 0.0, 1.0, 1.0));
+        for x in 0..8 {
+            let mut c = image.get_pixel(x, y);
+            c.g = if y == 3 { 1.0 } else { 0.0 };
+            image.set_pixel(x, y, &c);
+        }
+    }
+    let environment = Environment::new(image);
+
+    // Straight behind the default camera is where the image wraps around,
+    // halfway between its first and last columns
+    let behind = environment.sample(Vec3f::new(0.0, 0.0, 1.0));
+    assert!((behind.r - 0.5).abs() < 0.01 && (behind.b - 0.5).abs() < 0.01);
+
+    // The poles only see their own row
+    assert_eq!(environment.sample(Vec3f::new(0.0, 1.0, 0.0)).g, 1.0);
+    assert_eq!(environment.sample(Vec3f::new(0.0, -1.0, 0.0)).g, 0.0);
+}
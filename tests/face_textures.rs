@@ -0,0 +1,111 @@
+extern crate renderer;
+
+mod common;
+
+use std::rc::Rc;
+use renderer::{Scene,NodeMaterial,Model,Texture,TgaImage,RgbaColor,Vec2f,Vec3f,Mat4};
+use renderer::model::Group;
+use renderer::shader::MISSING_TEXTURE_COLOR;
+use common::{unlit,solid};
+
+const RED: RgbaColor = RgbaColor { r: 1.0, g: 0.0, b: 0.0, a: 1.0 };
+const BLUE: RgbaColor = RgbaColor { r: 0.0, g: 0.0, b: 1.0, a: 1.0 };
+const GREEN: RgbaColor = RgbaColor { r: 0.0, g: 1.0, b: 0.0, a: 1.0 };
+
+// Quads left and right of x = 0 as groups "left" and "right" of two faces each
+fn model() -> Model {
+    let vertices = vec![
+        Vec3f::new(-1.0, -1.0, 0.0), Vec3f::new(0.0, -1.0, 0.0), Vec3f::new(0.0, 1.0, 0.0), Vec3f::new(-1.0, 1.0, 0.0),
+        Vec3f::new(1.0, -1.0, 0.0), Vec3f::new(1.0, 1.0, 0.0)
+    ];
+    let uvs = vec![Vec2f::new(0.0, 0.0), Vec2f::new(1.0, 0.0), Vec2f::new(1.0, 1.0), Vec2f::new(0.0, 1.0)];
+    let faces = vec![
+        [0, 0, -1, 1, 1, -1, 2, 2, -1], [0, 0, -1, 2, 2, -1, 3, 3, -1],
+        [1, 0, -1, 4, 1, -1, 5, 2, -1], [1, 0, -1, 5, 2, -1, 2, 3, -1]
+    ];
+    let mut model = Model::new(vertices, uvs, Vec::new(), faces).unwrap();
+    model.groups = vec![
+        Group { name: "left".to_string(), faces: vec![0, 1] },
+        Group { name: "right".to_string(), faces: vec![2, 3] }
+    ];
+    model
+}
+
+fn colors(image: &TgaImage) -> Vec<RgbaColor> {
+    let mut colors = Vec::new();
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let c = image.get_pixel(x, y);
+            if !colors.contains(&c) {
+                colors.push(c);
+            }
+        }
+    }
+    colors
+}
+
+#[test]
+fn groups_pick_their_textures() {
+    let mut renderer = unlit(32, 32);
+    let (red, blue) = (renderer.add_texture(solid(2, RED)), renderer.add_texture(solid(2, BLUE)));
+    let mut model = model();
+    assert!(model.set_group_texture("left", Some(red)));
+    assert!(model.set_group_texture("right", Some(blue)));
+    assert!(!model.set_group_texture("weapon", Some(red)));
+    renderer.draw_model(&model, &Mat4::identity());
+
+    let image = renderer.to_image();
+    assert_eq!(image.get_pixel(8, 16), RED);
+    assert_eq!(image.get_pixel(24, 16), BLUE);
+    let colors = colors(&image);
+    assert_eq!(colors.len(), 2, "{:?}", colors);
+    assert!(!colors.contains(&MISSING_TEXTURE_COLOR));
+}
+
+#[test]
+fn faces_without_a_texture_use_the_color() {
+    let mut renderer = unlit(32, 32);
+    renderer.set_color(GREEN);
+    let red = renderer.add_texture(solid(2, RED));
+    let mut model = model();
+    model.set_group_texture("left", Some(red));
+    renderer.draw_model(&model, &Mat4::identity());
+    let image = renderer.to_image();
+    assert_eq!(image.get_pixel(8, 16), RED);
+    assert_eq!(image.get_pixel(24, 16), GREEN);
+
+    // Cleared again
+    model.set_texture(&[0, 1], None);
+    renderer.draw_model(&model, &Mat4::identity());
+    assert_eq!(renderer.to_image().get_pixel(8, 16), GREEN);
+}
+
+#[test]
+fn missing_textures_are_magenta() {
+    let mut renderer = unlit(32, 32);
+    let red = renderer.add_texture(solid(2, RED));
+    let mut model = model();
+    model.set_group_texture("left", Some(red));
+    model.face_textures[2] = 7;
+    model.face_textures[3] = 7;
+    renderer.draw_model(&model, &Mat4::identity());
+
+    let image = renderer.to_image();
+    assert_eq!(image.get_pixel(8, 16), RED);
+    assert_eq!(image.get_pixel(24, 16), MISSING_TEXTURE_COLOR);
+}
+
+#[test]
+fn scenes_hold_the_textures_they_draw_with() {
+    let mut scene = Scene::new();
+    let blue = scene.add_texture(Texture::new(solid(2, BLUE)));
+    let mut model = model();
+    model.set_group_texture("right", Some(blue));
+    scene.add(Rc::new(model), Mat4::identity(), NodeMaterial::default());
+
+    let mut renderer = unlit(32, 32);
+    renderer.draw_scene(&scene);
+    let image = renderer.to_image();
+    assert_eq!(image.get_pixel(8, 16), RgbaColor::new(1.0, 1.0, 1.0, 1.0));
+    assert_eq!(image.get_pixel(24, 16), BLUE);
+}
@@ -0,0 +1,72 @@
+extern crate renderer;
+
+mod common;
+
+use renderer::{Renderer,Model,FogMode,TgaImage,RgbaColor,Vec3f,Mat4};
+use common::unlit;
+
+// Identical triangles side by side at z = 0.5, -0.5 and -0.9, 0.5, 1.5 and
+// 1.9 in front of the orthographic camera
+fn model() -> Model {
+    let mut vertices = Vec::new();
+    let mut faces = Vec::new();
+    for (i, &(x, z)) in [(-0.9, 0.5), (-0.3, -0.5), (0.3, -0.9)].iter().enumerate() {
+        vertices.extend_from_slice(&[Vec3f::new(x, -0.5, z), Vec3f::new(x + 0.5, -0.5, z), Vec3f::new(x, 0.5, z)]);
+        let k = 3 * i as i32;
+        faces.push([k, -1, -1, k + 1, -1, -1, k + 2, -1, -1]);
+    }
+    Model::new(vertices, Vec::new(), Vec::new(), faces).unwrap()
+}
+
+const FOG: RgbaColor = RgbaColor { r: 0.0, g: 0.0, b: 1.0, a: 1.0 };
+
+fn render(renderer: &mut Renderer) -> TgaImage {
+    renderer.draw_model(&model(), &Mat4::identity());
+    renderer.to_image()
+}
+
+// Pixels inside the near, far and farthest triangles
+const NEAR: (i32, i32) = (6, 28);
+const FAR: (i32, i32) = (25, 28);
+const FARTHEST: (i32, i32) = (44, 28);
+
+#[test]
+fn linear_fog_grows_with_depth() {
+    let mut renderer = unlit(64, 64);
+    renderer.set_fog(FOG, FogMode::Linear { start: 0.0, end: 1.8 });
+    let image = render(&mut renderer);
+
+    let (near, far) = (image.get_pixel(NEAR.0, NEAR.1), image.get_pixel(FAR.0, FAR.1));
+    assert!(near.r < 1.0 && near.b == 1.0, "{:?}", near);
+    assert!(far.r < near.r, "{:?} {:?}", near, far);
+    assert!((near.r - (1.0 - 0.5 / 1.8)).abs() < 0.01 && (far.r - (1.0 - 1.5 / 1.8)).abs() < 0.01, "{:?} {:?}", near, far);
+    assert_eq!(image.get_pixel(FARTHEST.0, FARTHEST.1), FOG);
+}
+
+#[test]
+fn exponential_fog() {
+    let mut renderer = unlit(64, 64);
+    renderer.set_fog(FOG, FogMode::Exp { density: 1.0 });
+    let image = render(&mut renderer);
+    let (near, far, farthest) = (image.get_pixel(NEAR.0, NEAR.1), image.get_pixel(FAR.0, FAR.1), image.get_pixel(FARTHEST.0, FARTHEST.1));
+    assert!(near.r > far.r && far.r > farthest.r && farthest.r > 0.0, "{:?} {:?} {:?}", near, far, farthest);
+    assert!((near.r - (-0.5f32).exp()).abs() < 0.01, "{:?}", near);
+
+    assert_eq!(FogMode::Exp { density: 1.0 }.factor(-1.0), 0.0);
+    assert_eq!(FogMode::Linear { start: 1.0, end: 2.0 }.factor(1.5), 0.5);
+}
+
+#[test]
+fn fog_leaves_depth_alone_and_turns_off_cleanly() {
+    let mut plain = unlit(64, 64);
+    let expected = render(&mut plain);
+
+    let mut fogged = unlit(64, 64);
+    fogged.set_fog(FOG, FogMode::Linear { start: 0.0, end: 1.0 });
+    assert!(!render(&mut fogged).diff_with(&expected, 0, false).is_match());
+    assert_eq!(fogged.depth_buffer().data(), plain.depth_buffer().data());
+
+    fogged.remove_fog();
+    fogged.clear(RgbaColor::new(0.0, 0.0, 0.0, 0.0));
+    assert!(render(&mut fogged).diff_with(&expected, 0, false).is_match());
+}
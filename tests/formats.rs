@@ -0,0 +1,126 @@
+// glTF and PLY loading, OBJ has tests/obj.rs
+
+extern crate renderer;
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use renderer::{Model,Vec3f};
+use renderer::model::ModelError;
+
+fn write(name: &str, data: &[u8]) -> PathBuf {
+    let path = env::temp_dir().join(format!("renderer_formats_{}", name));
+    File::create(&path).unwrap().write_all(data).unwrap();
+    path
+}
+
+// Triangle (0, 0, 0), (1, 0, 0), (0, 1, 0) with u16 indices and texture
+// coords, 36 bytes of positions, 8 of indices and 24 of texture coords in
+// one embedded buffer
+const GLTF_TRIANGLE: &str = r#"{
+  "asset": { "version": "2.0" },
+  "buffers": [{ "byteLength": 68, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAAAAAAAAAAAAAAAgD8AAAAAAAAAAAAAgD8=" }],
+  "bufferViews": [
+    { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+    { "buffer": 0, "byteOffset": 36, "byteLength": 6 },
+    { "buffer": 0, "byteOffset": 44, "byteLength": 24 }
+  ],
+  "accessors": [
+    { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] },
+    { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" },
+    { "bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC2", "normalized": false }
+  ],
+  "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0, "TEXCOORD_0": 2 }, "indices": 1 }] }]
+}"#;
+
+#[test]
+fn embedded_gltf_triangle() {
+    let model = Model::new_from_gltf(&write("triangle.gltf", GLTF_TRIANGLE.as_bytes())).unwrap();
+    assert_eq!(model.vertices, vec![Vec3f::new(0.0, 0.0, 0.0), Vec3f::new(1.0, 0.0, 0.0), Vec3f::new(0.0, 1.0, 0.0)]);
+    assert_eq!(model.faces, vec![[0, 0, 0, 1, 1, 1, 2, 2, 2]]);
+    // Flipped from glTF's top left origin
+    let uv = model.texture_coord(0, 2).unwrap();
+    assert_eq!((uv.x, uv.y), (0.0, 0.0));
+    assert_eq!(model.normal(0, 0), Some(Vec3f::new(0.0, 0.0, 1.0)));
+}
+
+#[test]
+fn gltf_errors() {
+    match Model::new_from_gltf(&write("binary.glb", b"glTF\x02\x00\x00\x00")) {
+        Err(ModelError::Unsupported(_)) => {},
+        other => panic!("loaded as {:?}", other.map(|m| m.faces))
+    }
+    let short = GLTF_TRIANGLE.replace("\"count\": 3, \"type\": \"VEC2\"", "\"count\": 4, \"type\": \"VEC2\"");
+    match Model::new_from_gltf(&write("short.gltf", short.as_bytes())) {
+        Err(ModelError::Invalid(_)) => {},
+        other => panic!("loaded as {:?}", other.map(|m| m.faces))
+    }
+    match Model::new_from_gltf(&write("broken.gltf", b"{ \"meshes\": [")) {
+        Err(ModelError::Invalid(_)) => {},
+        other => panic!("loaded as {:?}", other.map(|m| m.faces))
+    }
+}
+
+#[test]
+fn gltf_accessors_outside_the_buffer_are_rejected() {
+    let huge = GLTF_TRIANGLE.replace("\"count\": 3, \"type\": \"VEC3\"", "\"count\": 1e18, \"type\": \"VEC3\"");
+    let offset = GLTF_TRIANGLE.replace("\"bufferView\": 0, \"componentType\"", "\"bufferView\": 0, \"byteOffset\": 1e19, \"componentType\"");
+    let stride = GLTF_TRIANGLE.replace("\"byteOffset\": 0, \"byteLength\": 36", "\"byteOffset\": 0, \"byteLength\": 36, \"byteStride\": 256");
+    for &(name, text) in [("huge.gltf", &huge), ("offset.gltf", &offset), ("stride.gltf", &stride)].iter() {
+        assert!(text.as_str() != GLTF_TRIANGLE);
+        match Model::new_from_gltf(&write(name, text.as_bytes())) {
+            Err(ModelError::Invalid(_)) => {},
+            other => panic!("{} loaded as {:?}", name, other.map(|m| m.faces))
+        }
+    }
+}
+
+const PLY_HEADER: &str = "element vertex 8\nproperty float x\nproperty float y\nproperty float z\nelement face 6\nproperty list uchar int vertex_indices\nend_header\n";
+const CUBE_SIDES: [[i32; 4]; 6] = [[0, 2, 3, 1], [4, 5, 7, 6], [0, 1, 5, 4], [2, 6, 7, 3], [0, 4, 6, 2], [1, 3, 7, 5]];
+
+fn corner(i: i32) -> [f32; 3] {
+    let c = |bit: i32| if i & bit != 0 { 1.0 } else { -1.0 };
+    [c(1), c(2), c(4)]
+}
+
+#[test]
+fn ply_cube_in_both_formats() {
+    let mut ascii = format!("ply\nformat ascii 1.0\ncomment hand written\n{}", PLY_HEADER);
+    let mut binary = format!("ply\nformat binary_little_endian 1.0\n{}", PLY_HEADER).into_bytes();
+    for i in 0..8 {
+        let c = corner(i);
+        ascii.push_str(&format!("{} {} {}\n", c[0], c[1], c[2]));
+        for v in c.iter() {
+            binary.extend_from_slice(&v.to_bits().to_le_bytes());
+        }
+    }
+    for side in CUBE_SIDES.iter() {
+        ascii.push_str(&format!("4 {} {} {} {}\n", side[0], side[1], side[2], side[3]));
+        binary.push(4);
+        for i in side.iter() {
+            binary.extend_from_slice(&i.to_le_bytes());
+        }
+    }
+
+    let ascii = Model::new_from_ply(&write("cube_ascii.ply", ascii.as_bytes())).unwrap();
+    let binary = Model::new_from_ply(&write("cube_binary.ply", &binary)).unwrap();
+    for model in [&ascii, &binary].iter() {
+        assert_eq!(model.vertices.len(), 8);
+        assert_eq!(model.faces.len(), 12);
+        for (i, v) in model.vertices.iter().enumerate() {
+            let c = corner(i as i32);
+            assert_eq!(*v, Vec3f::new(c[0], c[1], c[2]));
+        }
+        for (s, side) in CUBE_SIDES.iter().enumerate() {
+            let (a, b) = (model.faces[2 * s], model.faces[2 * s + 1]);
+            assert_eq!([a[0], a[3], a[6], b[0], b[3], b[6]], [side[0], side[1], side[2], side[0], side[2], side[3]]);
+        }
+    }
+    assert_eq!(ascii.normals, binary.normals);
+
+    match Model::new_from_ply(&write("cube_big_endian.ply", format!("ply\nformat binary_big_endian 1.0\n{}", PLY_HEADER).as_bytes())) {
+        Err(ModelError::Unsupported(_)) => {},
+        other => panic!("loaded as {:?}", other.map(|m| m.faces))
+    }
+}
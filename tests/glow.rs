@@ -0,0 +1,99 @@
+extern crate renderer;
+
+mod common;
+
+use renderer::{Renderer,Kernel,TgaImage,RgbaColor,Vec3f,Mat4};
+use common::square;
+
+// Black but for the middle 2x2 of 8x8 texels, 4x4 pixels each
+fn glow_map(color: RgbaColor) -> TgaImage {
+    let mut image = TgaImage::new(8, 8);
+    for y in 0..8 {
+        for x in 0..8 {
+            let lit = (3..5).contains(&x) && (3..5).contains(&y);
+            image.set_pixel(x, y, &if lit { color } else { RgbaColor::new(0.0, 0.0, 0.0, 1.0) });
+        }
+    }
+    image
+}
+
+// The quad spans pixels 16 to 47 with the glowing texels at 28 to 35.
+// Lit from behind, so it's black without glow
+fn renderer() -> Renderer {
+    let mut renderer = Renderer::new(64, 64);
+    renderer.set_two_sided_lighting(false);
+    renderer.set_light_dir(Vec3f::new(0.0, 0.0, -1.0));
+    renderer
+}
+
+const CYAN: RgbaColor = RgbaColor { r: 0.0, g: 0.8, b: 1.0, a: 1.0 };
+
+#[test]
+fn glow_shows_without_light() {
+    let mut renderer = renderer();
+    renderer.draw_model(&square(0.5, 0.0), &Mat4::identity());
+    let unlit = renderer.to_image();
+    assert_eq!(unlit.get_pixel(32, 32), RgbaColor::new(0.0, 0.0, 0.0, 1.0));
+
+    renderer.set_glow_map(glow_map(CYAN));
+    renderer.clear(RgbaColor::new(0.0, 0.0, 0.0, 0.0));
+    renderer.draw_model(&square(0.5, 0.0), &Mat4::identity());
+    let image = renderer.to_image();
+    let c = image.get_pixel(32, 32);
+    assert!(c.r == 0.0 && (c.g - 0.8).abs() < 0.01 && c.b == 1.0, "{:?}", c);
+    assert_eq!(image.get_pixel(20, 20), unlit.get_pixel(20, 20));
+
+    renderer.remove_glow_map();
+    renderer.clear(RgbaColor::new(0.0, 0.0, 0.0, 0.0));
+    renderer.draw_model(&square(0.5, 0.0), &Mat4::identity());
+    assert!(renderer.to_image().diff_with(&unlit, 0, false).is_match());
+}
+
+#[test]
+fn glow_saturates_over_light() {
+    let mut renderer = renderer();
+    renderer.set_light_dir(Vec3f::new(0.0, 0.0, 1.0));
+    renderer.set_ambient(0.5);
+    renderer.set_glow_map(glow_map(RgbaColor::new(1.0, 1.0, 1.0, 1.0)));
+    renderer.draw_model(&square(0.5, 0.0), &Mat4::identity());
+    assert_eq!(renderer.to_image().get_pixel(32, 32), RgbaColor::new(1.0, 1.0, 1.0, 1.0));
+}
+
+#[test]
+fn bloom_spreads_visible_glow() {
+    let blur = vec![Kernel::box_blur(5); 2];
+    let mut plain = renderer();
+    plain.set_glow_map(glow_map(CYAN));
+    plain.draw_model(&square(0.5, 0.0), &Mat4::identity());
+    let plain = plain.to_image();
+
+    let mut renderer = renderer();
+    renderer.set_glow_map(glow_map(CYAN));
+    let usage = renderer.memory_usage();
+    renderer.set_bloom(&blur).unwrap();
+    // The glow buffer is allocated up front, one color per sample
+    assert_eq!(renderer.memory_usage(), usage + 64 * 64 * 16);
+    renderer.draw_model(&square(0.5, 0.0), &Mat4::identity());
+    let bloomed = renderer.to_image();
+    assert_eq!(plain.get_pixel(26, 32).b, 0.0);
+    assert!(bloomed.get_pixel(26, 32).b > 0.0);
+    assert!(bloomed.get_pixel(32, 32).b >= plain.get_pixel(32, 32).b);
+    assert_eq!(bloomed.get_pixel(18, 18), plain.get_pixel(18, 18));
+    assert_eq!(renderer.stats().drawn, 2);
+
+    // Covered by a quad without glow drawn after it, nothing shows
+    renderer.remove_glow_map();
+    renderer.draw_model(&square(0.5, 0.5), &Mat4::identity());
+    let covered = renderer.to_image();
+    for &(x, y) in [(26, 32), (32, 32)].iter() {
+        assert_eq!(covered.get_pixel(x, y).b, 0.0, "at {}, {}", x, y);
+    }
+
+    // Without glow maps bloom changes nothing
+    let mut expected = self::renderer();
+    expected.draw_model(&square(0.5, 0.0), &Mat4::identity());
+    let mut renderer = self::renderer();
+    renderer.set_bloom(&blur).unwrap();
+    renderer.draw_model(&square(0.5, 0.0), &Mat4::identity());
+    assert!(renderer.to_image().diff_with(&expected.to_image(), 0, false).is_match());
+}
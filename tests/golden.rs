@@ -0,0 +1,136 @@
+// Renders of the built-in test scene compared against the references in
+// tests/golden. UPDATE_GOLDEN=1 rewrites the references from the current
+// output, GOLDEN_TOLERANCE overrides how far channels may drift. Changes to
+// coverage or interpolation rules move whole edge pixels, rewrite the
+// references in a commit of their own instead of raising the tolerance
+
+extern crate renderer;
+
+use std::env;
+use std::path::{Path,PathBuf};
+use renderer::{Renderer,Model,Shading,Texture,TgaImage,Kernel,Mat4};
+use renderer::tga::TgaWriteOptions;
+use renderer::test_scene;
+
+// Per channel, in 8 bit steps
+const TOLERANCE: u8 = 1;
+
+// Alpha included, the background is transparent
+fn save(image: &TgaImage, path: &Path) {
+    image.write_to_file_with_options(path, TgaWriteOptions { rle: true, alpha: true }).unwrap();
+}
+
+fn reference_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(format!("{}.tga", name))
+}
+
+// On a mismatch the output and the difference image are left in the temp
+// directory next to the failure report
+fn check(name: &str, image: &TgaImage) {
+    let path = reference_path(name);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        save(image, &path);
+        return;
+    }
+
+    let tolerance = env::var("GOLDEN_TOLERANCE").ok().and_then(|t| t.parse().ok()).unwrap_or(TOLERANCE);
+    let reference = TgaImage::new_from_file(&path).unwrap_or_else(|e| panic!("cannot read {}: {}", path.display(), e));
+    let diff = image.diff_with(&reference, tolerance, true);
+    if !diff.is_match() {
+        let actual = env::temp_dir().join(format!("golden_{}_actual.tga", name));
+        let difference = env::temp_dir().join(format!("golden_{}_diff.tga", name));
+        save(image, &actual);
+        save(diff.image.as_ref().unwrap(), &difference);
+        panic!("{} doesn't match {}\n{}\noutput written to {}, differences to {}",
+               name, path.display(), diff, actual.display(), difference.display());
+    }
+}
+
+#[test]
+fn scene() {
+    check("scene", &test_scene::render(64, 64, 1));
+}
+
+#[test]
+fn non_square_scene() {
+    check("scene_wide", &test_scene::render(80, 48, 1));
+}
+
+#[test]
+fn supersampled_and_blurred_scene() {
+    let mut renderer = Renderer::new(64, 64);
+    renderer.set_supersampling(2).unwrap();
+    renderer.post_process(&[Kernel::gaussian3()]);
+    test_scene::draw(&mut renderer);
+    check("scene_ssaa", &renderer.to_image());
+}
+
+// The textured head from data/, drawn the way the binary draws it
+#[test]
+fn head() {
+    let mut model = Model::new_from_file(Path::new("data/model.obj")).unwrap();
+    model.normalize_to_unit_cube();
+    let mut renderer = Renderer::new(96, 96);
+    renderer.set_diffuse(Texture::new(TgaImage::new_from_file(Path::new("data/diffuse.tga")).unwrap()));
+    renderer.set_shading(Shading::Gouraud);
+    renderer.draw_model(&model, &Mat4::identity());
+    check("head", &renderer.to_image());
+}
+
+#[test]
+fn output_is_deterministic() {
+    let first = test_scene::render(80, 48, 1);
+    assert!(first.diff(&test_scene::render(80, 48, 1)).is_match());
+    for &threads in [2, 3, 8].iter() {
+        let diff = first.diff(&test_scene::render(80, 48, threads));
+        assert!(diff.is_match(), "{} threads: {}", threads, diff);
+    }
+}
+
+// Many tiles, some split by the silhouette, compared as encoded bytes
+#[test]
+fn threaded_head_is_byte_identical() {
+    let mut model = Model::new_from_file(Path::new("data/model.obj")).unwrap();
+    model.normalize_to_unit_cube();
+    let render = |threads: usize| {
+        let mut renderer = Renderer::new(300, 260);
+        renderer.set_threads(threads);
+        renderer.set_diffuse(Texture::new(TgaImage::new_from_file(Path::new("data/diffuse.tga")).unwrap()));
+        renderer.set_shading(Shading::Gouraud);
+        renderer.draw_model(&model, &Mat4::identity());
+        let mut data = Vec::new();
+        renderer.to_image().encode_with_options(&mut data, TgaWriteOptions { rle: false, alpha: true }).unwrap();
+        renderer.depth_image().encode(&mut data).unwrap();
+        data
+    };
+
+    let single = render(1);
+    for &threads in [2, 4, 7].iter() {
+        assert!(render(threads) == single, "{} threads differ", threads);
+    }
+}
+
+#[test]
+fn diff_reports_differences() {
+    let a = test_scene::render(32, 32, 1);
+    let mut b = test_scene::render(32, 32, 1);
+    let mut c = b.get_pixel(3, 4);
+    c.g = (c.g + 10.0 / 255.0).min(1.0);
+    c.r = if c.r > 0.5 { c.r - 3.0 / 255.0 } else { c.r + 3.0 / 255.0 };
+    b.set_pixel(3, 4, &c);
+
+    let exact = a.diff_with(&b, 0, true);
+    assert_eq!(exact.differing, 1);
+    assert_eq!(exact.pixels, vec![(3, 4, exact.max_delta)]);
+    assert!(exact.max_delta >= 9 && exact.max_delta <= 10, "{}", exact);
+    let image = exact.image.unwrap();
+    assert!(image.get_pixel(3, 4).g > 0.0);
+    assert_eq!(image.get_pixel(4, 4).g, 0.0);
+    assert_eq!(image.get_pixel(4, 4).a, 1.0);
+
+    assert!(a.diff_with(&b, 10, false).is_match());
+
+    // The extra column differs entirely
+    let wider = a.diff(&TgaImage::new(33, 32));
+    assert!(wider.differing >= 32 && wider.max_delta == 255);
+}
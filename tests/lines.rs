@@ -0,0 +1,114 @@
+extern crate renderer;
+
+use renderer::{Renderer,TgaImage};
+
+// Red of every pixel in column x, bottom up
+fn column(image: &TgaImage, x: i32) -> Vec<f32> {
+    (0..image.height).map(|y| image.get_pixel(x, y).r).collect()
+}
+
+fn row(image: &TgaImage, y: i32) -> Vec<f32> {
+    (0..image.width).map(|x| image.get_pixel(x, y).r).collect()
+}
+
+#[test]
+fn shallow_aa_line_spreads_coverage_over_two_rows() {
+    let mut renderer = Renderer::new(32, 32);
+    renderer.line_aa(2.0, 4.0, 29.0, 13.0);
+    let image = renderer.to_image();
+
+    let mut partial = 0;
+    for x in 3..29 {
+        let coverage = column(&image, x);
+        let lit: Vec<f32> = coverage.iter().cloned().filter(|&c| c > 0.0).collect();
+        assert!(lit.len() <= 2, "column {}: {:?}", x, lit);
+        assert!((lit.iter().sum::<f32>() - 1.0).abs() < 0.02, "column {}: {:?}", x, lit);
+        if lit.iter().any(|&c| c < 0.95) {
+            partial += 1;
+        }
+    }
+    // A third of a pixel up per column, so most columns fall between rows
+    assert!(partial > 13, "{} partially covered columns", partial);
+}
+
+#[test]
+fn steep_aa_line_spreads_coverage_over_two_columns() {
+    let mut renderer = Renderer::new(32, 32);
+    renderer.line_aa(4.0, 2.0, 13.0, 29.0);
+    let image = renderer.to_image();
+    for y in 3..29 {
+        let sum: f32 = row(&image, y).iter().sum();
+        assert!((sum - 1.0).abs() < 0.02, "row {}: {}", y, sum);
+    }
+}
+
+#[test]
+fn aa_line_with_identical_endpoints_is_a_pixel() {
+    let mut renderer = Renderer::new(8, 8);
+    renderer.line_aa(3.0, 5.0, 3.0, 5.0);
+    let image = renderer.to_image();
+    assert_eq!(image.get_pixel(3, 5).r, 1.0);
+    let lit = (0..8).flat_map(|y| (0..8).map(move |x| (x, y))).filter(|&(x, y)| image.get_pixel(x, y).r > 0.0).count();
+    assert_eq!(lit, 1);
+}
+
+#[test]
+fn diagonal_aa_line_is_symmetric() {
+    // Halfway between two pixel centers the whole way along
+    let mut renderer = Renderer::new(32, 32);
+    renderer.line_aa(2.0, 2.5, 28.0, 28.5);
+    let image = renderer.to_image();
+    for x in 3..28 {
+        let coverage = column(&image, x);
+        let (below, above) = (coverage[x as usize], coverage[x as usize + 1]);
+        assert!((below - 0.5).abs() < 0.01 && below == above, "column {}: {} {}", x, below, above);
+        assert_eq!(coverage.iter().filter(|&&c| c > 0.0).count(), 2, "column {}", x);
+    }
+
+    // Mirroring the line across the diagonal mirrors the pixels, away from
+    // the endpoints which are weighted by where they fall along x
+    let mut renderer = Renderer::new(32, 32);
+    renderer.line_aa(2.0, 2.25, 28.0, 28.25);
+    let mut mirrored = Renderer::new(32, 32);
+    mirrored.line_aa(2.25, 2.0, 28.25, 28.0);
+    let (image, mirrored) = (renderer.to_image(), mirrored.to_image());
+    for y in 4..27 {
+        for x in 4..27 {
+            assert_eq!(image.get_pixel(x, y), mirrored.get_pixel(y, x), "at {}, {}", x, y);
+        }
+    }
+}
+
+#[test]
+fn thick_horizontal_lines_cover_width_rows() {
+    for &(y, width, first, last) in [(10.5, 3.0, 9, 11), (10.0, 4.0, 8, 11), (10.5, 1.0, 10, 10)].iter() {
+        let mut renderer = Renderer::new(32, 32);
+        renderer.line_thick(4.0, y, 20.0, y, width);
+        let image = renderer.to_image();
+        for row in 0..32 {
+            let lit: Vec<i32> = (0..32).filter(|&x| image.get_pixel(x, row).r > 0.0).collect();
+            if row >= first && row <= last {
+                assert_eq!(lit, (4..20).collect::<Vec<i32>>(), "row {} of width {}", row, width);
+            } else {
+                assert!(lit.is_empty(), "row {} of width {}: {:?}", row, width, lit);
+            }
+        }
+    }
+}
+
+#[test]
+fn degenerate_and_off_screen_lines() {
+    let mut renderer = Renderer::new(8, 8);
+    renderer.line(3, 4, 3, 4);
+    renderer.line_thick(2.0, 2.0, 2.0, 2.0, 3.0);
+    renderer.line(-50, -50, -10, 100);
+    renderer.line_aa(-50.0, 20.0, 100.0, 20.0);
+    renderer.line_thick(-1e6, 6.5, 1e6, 6.5, 1.0);
+    let image = renderer.to_image();
+
+    // The single pixel and the clipped row of the long thick line
+    let lit: Vec<(i32, i32)> = (0..8).flat_map(|y| (0..8).map(move |x| (x, y))).filter(|&(x, y)| image.get_pixel(x, y).r > 0.0).collect();
+    let mut expected = vec![(3, 4)];
+    expected.extend((0..8).map(|x| (x, 6)));
+    assert_eq!(lit, expected);
+}
@@ -0,0 +1,387 @@
+extern crate renderer;
+
+use std::f32::consts::PI;
+use renderer::{Renderer,Shading,Model,RgbaColor};
+use renderer::math::{Mat3,Mat4,Mat4d,Quat,Vec2f,Vec3f,Vec3d,Vec3i,Vec4f,lerp,barycentric_mix,clamp01,smoothstep};
+use renderer::math::geometry::{barycentric,point_in_triangle,ray_triangle_intersect,triangle_area_2d};
+
+fn assert_close(a: Vec3f, b: Vec3f) {
+    assert!(a.approx_eq(&b, 1e-5), "{:?} != {:?}", a, b);
+}
+
+fn assert_mat_close(a: &Mat4, b: &Mat4) {
+    for i in 0..4 {
+        for j in 0..4 {
+            assert!((a.m[i][j] - b.m[i][j]).abs() < 1e-5, "[{}][{}] {:?} != {:?}", i, j, a, b);
+        }
+    }
+}
+
+// Column vectors, so the rightmost matrix applies first
+#[test]
+fn matrices_apply_right_to_left() {
+    let p = Vec3f::new(1.0, 0.0, 0.0);
+    let translate = Mat4::translation(Vec3f::new(1.0, 0.0, 0.0));
+    let rotate = Mat4::rotation_z(PI / 2.0);
+
+    assert_close(translate * rotate * p, Vec3f::new(1.0, 1.0, 0.0));
+    assert_close(rotate * translate * p, Vec3f::new(0.0, 2.0, 0.0));
+    assert_close(Mat4::rotation_x(PI / 2.0) * Vec3f::new(0.0, 1.0, 0.0), Vec3f::new(0.0, 0.0, 1.0));
+    assert_close(Mat4::rotation_y(PI / 2.0) * Vec3f::new(0.0, 0.0, 1.0), Vec3f::new(1.0, 0.0, 0.0));
+    assert_close(Mat4::scale(Vec3f::new(2.0, 3.0, 4.0)) * Vec3f::new(1.0, 1.0, 1.0), Vec3f::new(2.0, 3.0, 4.0));
+
+    // Translation sits in the last column
+    assert_eq!((translate.m[0][3], translate.m[3][0]), (1.0, 0.0));
+    assert_eq!(translate.transpose().m[3][0], 1.0);
+    assert_eq!(translate.transpose().transpose(), translate);
+}
+
+#[test]
+fn inverse_undoes_the_transform() {
+    let m = Mat4::translation(Vec3f::new(3.0, -2.0, 5.0)) * Mat4::rotation_y(0.7) * Mat4::scale(Vec3f::new(2.0, 0.5, 4.0));
+    let inv = m.inverse().unwrap();
+    assert_mat_close(&(m * inv), &Mat4::identity());
+    assert_mat_close(&(inv * m), &Mat4::identity());
+    assert_close(inv * (m * Vec3f::new(1.0, 2.0, 3.0)), Vec3f::new(1.0, 2.0, 3.0));
+
+    // Needs a row swap to find a pivot
+    let swap = Mat4::new([[0.0, 1.0, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]]);
+    assert_mat_close(&swap.inverse().unwrap(), &swap);
+
+    assert!(Mat4::scale(Vec3f::new(1.0, 0.0, 1.0)).inverse().is_none());
+    assert!(Mat4::new([[0.0; 4]; 4]).inverse().is_none());
+}
+
+#[test]
+fn camera_center_lands_mid_viewport() {
+    let viewport = Mat4::viewport(0.0, 0.0, 800.0, 600.0, 255.0);
+    let projection = Mat4::perspective(PI / 3.0, 800.0 / 600.0, 0.1, 100.0);
+    let view = Mat4::lookat(Vec3f::new(0.0, 0.0, 5.0), Vec3f::new(0.0, 0.0, 0.0), Vec3f::new(0.0, 1.0, 0.0));
+    let screen = viewport * projection * view * Vec3f::new(0.0, 0.0, 0.0);
+    assert!((screen.x - 400.0).abs() < 1e-3 && (screen.y - 300.0).abs() < 1e-3, "{:?}", screen);
+
+    // NDC corners reach the viewport corners and the depth range
+    assert_close(viewport * Vec3f::new(-1.0, -1.0, -1.0), Vec3f::new(0.0, 0.0, 0.0));
+    assert_close(viewport * Vec3f::new(1.0, 1.0, 1.0), Vec3f::new(800.0, 600.0, 255.0));
+
+    // The same angle off axis covers fewer pixels across the wider side
+    let up = projection * Vec3f::new(0.0, 1.0, -5.0);
+    let right = projection * Vec3f::new(1.0, 0.0, -5.0);
+    assert!((right.x * 800.0 / 600.0 - up.y).abs() < 1e-5, "{:?} {:?}", right, up);
+
+    // Near maps to 1 and far to -1
+    assert!(((projection * Vec3f::new(0.0, 0.0, -0.1)).z - 1.0).abs() < 1e-4);
+    assert!(((projection * Vec3f::new(0.0, 0.0, -100.0)).z + 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn lookat_survives_up_along_the_view() {
+    for &up in [Vec3f::new(0.0, 1.0, 0.0), Vec3f::new(0.0, -3.0, 0.0)].iter() {
+        let view = Mat4::lookat(Vec3f::new(0.0, 4.0, 0.0), Vec3f::new(0.0, 0.0, 0.0), up);
+        for row in view.m.iter() {
+            assert!(row.iter().all(|v| v.is_finite()), "{:?}", view);
+        }
+        assert_close(view * Vec3f::new(0.0, 0.0, 0.0), Vec3f::new(0.0, 0.0, -4.0));
+    }
+}
+
+#[test]
+fn simple_projection_divides_by_distance() {
+    let p = Mat4::simple_projection(5.0) * Vec3f::new(1.0, 1.0, 2.5);
+    assert_close(p, Vec3f::new(2.0, 2.0, 5.0));
+}
+
+#[test]
+fn directions_ignore_translation() {
+    let m = Mat4::translation(Vec3f::new(10.0, 20.0, 30.0)) * Mat4::rotation_z(PI / 2.0);
+    let d = m * Vec3f::new(1.0, 0.0, 0.0).to_direction();
+    assert_eq!(d.w, 0.0);
+    assert_close(d.to_vec3(), Vec3f::new(0.0, 1.0, 0.0));
+    assert_close(m.transform_direction(Vec3f::new(1.0, 0.0, 0.0)), Vec3f::new(0.0, 1.0, 0.0));
+
+    let p = m * Vec3f::new(1.0, 0.0, 0.0).to_point();
+    assert_eq!(p.w, 1.0);
+    assert_close(p.to_vec3(), Vec3f::new(10.0, 21.0, 30.0));
+    assert_close(Vec4f::new(2.0, 4.0, 6.0, 2.0).to_vec3(), Vec3f::new(1.0, 2.0, 3.0));
+}
+
+#[test]
+fn zero_w_is_not_divided() {
+    // A point on the camera plane projects to w = 0
+    let camera = Mat4::perspective(PI / 2.0, 1.0, 1.0, 10.0);
+    let clip = camera * Vec3f::new(1.0, 2.0, 0.0).to_point();
+    assert_eq!(clip.w, 0.0);
+    let v = clip.to_vec3();
+    assert!(v.x.is_finite() && v.y.is_finite() && v.z.is_finite(), "{:?}", v);
+    assert_close(v, Vec3f::new(clip.x, clip.y, clip.z));
+    assert_close(camera * Vec3f::new(1.0, 2.0, 0.0), v);
+}
+
+#[test]
+fn integer_and_float_vectors_agree() {
+    let (a, b) = (Vec3i::new(1, -2, 3), Vec3i::new(4, 5, -6));
+    let (fa, fb) = (Vec3f::from(a), Vec3f::from(b));
+
+    assert_eq!(Vec3f::from(a + b), fa + fb);
+    assert_eq!(Vec3f::from(a - b), fa - fb);
+    assert_eq!(Vec3f::from(a * 3), fa * 3.0);
+    assert_eq!(Vec3f::from(a ^ b), fa ^ fb);
+    assert_eq!(Vec3f::from(a.cross(b)), fa.cross(fb));
+    assert_eq!(Vec3f::from(-a), -fa);
+    assert_eq!((a * b) as f32, fa * fb);
+    assert_eq!(a.dot(b) as f32, fa.dot(fb));
+    assert_eq!((a[0], a[1], a[2]), (1, -2, 3));
+
+    let mut c = a;
+    c += b;
+    c -= Vec3i::new(1, 1, 1);
+    c *= 2;
+    assert_eq!(c, Vec3i::new(8, 4, -8));
+}
+
+#[test]
+fn float_to_integer_truncates_towards_zero() {
+    assert_eq!(Vec3i::from(Vec3f::new(1.9, -1.9, 0.5)), Vec3i::new(1, -1, 0));
+    assert_eq!(Vec3i::from(Vec3f::new(-0.5, 2.0, -2.0)), Vec3i::new(0, 2, -2));
+    assert_eq!(Vec3f::from(Vec3i::new(-7, 0, 16777216)), Vec3f::new(-7.0, 0.0, 16777216.0));
+}
+
+#[test]
+fn quaternions_rotate_like_matrices() {
+    let q = Quat::from_axis_angle(Vec3f::new(0.0, 0.0, 2.0), PI / 2.0);
+    let x = Vec3f::new(1.0, 0.0, 0.0);
+    assert_close(q.rotate_vec3(x), Vec3f::new(0.0, 1.0, 0.0));
+    assert_mat_close(&q.to_mat4(), &Mat4::rotation_z(PI / 2.0));
+    assert_mat_close(&Mat4::from(q), &q.to_mat4());
+    assert_close(q.conjugate().rotate_vec3(q.rotate_vec3(x)), x);
+
+    // b first, then a
+    let a = Quat::from_axis_angle(Vec3f::new(1.0, 0.0, 0.0), PI / 2.0);
+    let v = Vec3f::new(0.3, -0.2, 0.9);
+    assert_close((a * q).rotate_vec3(v), a.rotate_vec3(q.rotate_vec3(v)));
+    assert_close((a * q).to_mat4() * v, Mat4::rotation_x(PI / 2.0) * (Mat4::rotation_z(PI / 2.0) * v));
+
+    let n = Quat::new(0.0, 0.0, 3.0, 4.0).normalize();
+    assert!((n.dot(&n) - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn slerp_takes_the_short_way() {
+    let a = Quat::from_axis_angle(Vec3f::new(0.0, 1.0, 0.0), 0.3);
+    let b = Quat::from_axis_angle(Vec3f::new(0.0, 1.0, 0.0), 1.5);
+    assert_eq!(Quat::slerp(a, b, 0.0), a);
+    assert_eq!(Quat::slerp(a, b, 1.0), b);
+
+    let half = Quat::slerp(a, b, 0.5);
+    let expected = Quat::from_axis_angle(Vec3f::new(0.0, 1.0, 0.0), 0.9);
+    assert!((half.dot(&expected) - 1.0).abs() < 1e-6, "{:?}", half);
+
+    // -b is the same rotation, the result shouldn't go the long way round
+    let flipped = Quat::slerp(a, Quat::new(-b.x, -b.y, -b.z, -b.w), 0.5);
+    assert!((flipped.dot(&expected).abs() - 1.0).abs() < 1e-6, "{:?}", flipped);
+
+    // Nearly identical ends don't divide by sin(0)
+    let c = Quat::from_axis_angle(Vec3f::new(0.0, 1.0, 0.0), 0.3 + 1e-6);
+    for &t in [0.0, 0.25, 0.5, 1.0].iter() {
+        let q = Quat::slerp(a, c, t);
+        assert!(q.x.is_finite() && q.y.is_finite() && q.z.is_finite() && q.w.is_finite(), "{:?}", q);
+        assert!((q.dot(&a) - 1.0).abs() < 1e-5);
+    }
+    let same = Quat::slerp(a, a, 0.5);
+    assert!((same.dot(&a) - 1.0).abs() < 1e-6);
+}
+
+fn vec2(x: f32, y: f32) -> Vec2f {
+    Vec2f::new(x, y)
+}
+
+#[test]
+fn barycentric_weights_on_edges_and_vertices() {
+    let (a, b, c) = (vec2(0.0, 0.0), vec2(4.0, 0.0), vec2(0.0, 4.0));
+    assert_eq!(triangle_area_2d(a, b, c), 8.0);
+    assert_eq!(triangle_area_2d(a, c, b), -8.0);
+
+    assert_close(barycentric(a, a, b, c), Vec3f::new(1.0, 0.0, 0.0));
+    assert_close(barycentric(b, a, b, c), Vec3f::new(0.0, 1.0, 0.0));
+    assert_close(barycentric(c, a, b, c), Vec3f::new(0.0, 0.0, 1.0));
+    assert_close(barycentric(vec2(2.0, 0.0), a, b, c), Vec3f::new(0.5, 0.5, 0.0));
+    assert_close(barycentric(vec2(1.0, 1.0), a, b, c), Vec3f::new(0.5, 0.25, 0.25));
+
+    for &p in [a, b, c, vec2(2.0, 0.0), vec2(0.0, 2.0), vec2(2.0, 2.0), vec2(1.0, 1.0)].iter() {
+        assert!(point_in_triangle(p, a, b, c), "{:?}", p);
+        assert!(point_in_triangle(p, a, c, b), "clockwise {:?}", p);
+    }
+    for &p in [vec2(-0.1, 0.0), vec2(2.1, 2.0), vec2(0.0, 4.1), vec2(5.0, -1.0)].iter() {
+        assert!(!point_in_triangle(p, a, b, c), "{:?}", p);
+    }
+
+    // Degenerate triangles contain nothing, not even their own points
+    let line = (vec2(0.0, 0.0), vec2(1.0, 1.0), vec2(2.0, 2.0));
+    assert_eq!(barycentric(vec2(1.0, 1.0), line.0, line.1, line.2), Vec3f::new(-1.0, 1.0, 1.0));
+    assert!(!point_in_triangle(vec2(1.0, 1.0), line.0, line.1, line.2));
+    assert!(!point_in_triangle(a, a, a, a));
+}
+
+#[test]
+fn rays_hit_misses_and_backfaces() {
+    let (v0, v1, v2) = (Vec3f::new(0.0, 0.0, 0.0), Vec3f::new(1.0, 0.0, 0.0), Vec3f::new(0.0, 1.0, 0.0));
+    let down = Vec3f::new(0.0, 0.0, -1.0);
+
+    // Counter-clockwise seen from +z
+    let (t, u, v) = ray_triangle_intersect(Vec3f::new(0.25, 0.5, 2.0), down, v0, v1, v2, true).unwrap();
+    assert!((t - 2.0).abs() < 1e-6 && (u - 0.25).abs() < 1e-6 && (v - 0.5).abs() < 1e-6, "{} {} {}", t, u, v);
+
+    // Edges and vertices hit
+    for &(x, y) in [(0.0, 0.0), (1.0, 0.0), (0.5, 0.5), (0.0, 0.5)].iter() {
+        assert!(ray_triangle_intersect(Vec3f::new(x, y, 1.0), down, v0, v1, v2, true).is_some(), "{} {}", x, y);
+    }
+    assert!(ray_triangle_intersect(Vec3f::new(0.6, 0.6, 1.0), down, v0, v1, v2, true).is_none());
+
+    // From below the back faces the ray
+    let up = Vec3f::new(0.0, 0.0, 1.0);
+    assert!(ray_triangle_intersect(Vec3f::new(0.25, 0.25, -1.0), up, v0, v1, v2, true).is_none());
+    assert!(ray_triangle_intersect(Vec3f::new(0.25, 0.25, -1.0), up, v0, v1, v2, false).is_some());
+
+    // Behind the origin and parallel to the plane
+    assert!(ray_triangle_intersect(Vec3f::new(0.25, 0.25, 1.0), up, v0, v1, v2, false).is_none());
+    assert!(ray_triangle_intersect(Vec3f::new(-1.0, 0.25, 0.0), Vec3f::new(1.0, 0.0, 0.0), v0, v1, v2, false).is_none());
+    assert!(ray_triangle_intersect(Vec3f::new(-1.0, 0.25, 1.0), Vec3f::new(1.0, 0.0, 0.0), v0, v1, v2, false).is_none());
+}
+
+#[test]
+fn zero_vectors_normalize_without_nan() {
+    let zero = Vec3f::new(0.0, 0.0, 0.0);
+    assert_eq!(zero.normalized(), zero);
+    assert_eq!(zero.try_normalized(), None);
+    assert_eq!(Vec3f::new(0.0, 3.0, -4.0).try_normalized(), Some(Vec3f::new(0.0, 0.6, -0.8)));
+    assert!((Vec3f::new(1.0, 2.0, 2.0).normalized().length() - 1.0).abs() < 1e-6);
+
+    // A face collapsed to a point next to a real one, flat shaded
+    let vertices = vec![Vec3f::new(-1.0, -1.0, 0.0), Vec3f::new(1.0, -1.0, 0.0), Vec3f::new(0.0, 1.0, 0.0), Vec3f::new(0.5, 0.5, 0.0)];
+    let single = Model::new(vertices.clone(), Vec::new(), Vec::new(), vec![[0, -1, -1, 1, -1, -1, 2, -1, -1]]).unwrap();
+    let with_degenerate = Model::new(vertices, Vec::new(), Vec::new(),
+                                     vec![[3, -1, -1, 3, -1, -1, 3, -1, -1], [0, -1, -1, 1, -1, -1, 2, -1, -1]]).unwrap();
+    let render = |model: &Model| {
+        let mut renderer = Renderer::new(32, 32);
+        renderer.set_shading(Shading::Flat);
+        renderer.draw_model(model, &Mat4::identity());
+        renderer.to_image()
+    };
+    let image = render(&with_degenerate);
+    assert!(image.get_pixel(16, 16).r > 0.0);
+    assert!(image.diff_with(&render(&single), 0, false).is_match());
+}
+
+#[test]
+fn non_uniform_scale_keeps_normals_radial() {
+    let scale = Mat4::scale(Vec3f::new(2.0, 1.0, 1.0));
+    let normal_matrix = scale.normal_matrix();
+    let mut naive_worst: f32 = 0.0;
+
+    // On a unit sphere the normal is the position itself, on the scaled
+    // ellipsoid x^2 / 4 + y^2 + z^2 = 1 it's the gradient (x / 4, y, z)
+    for i in 0..16 {
+        for j in 1..8 {
+            let (theta, phi) = (i as f32 * PI / 8.0, j as f32 * PI / 8.0);
+            let n = Vec3f::new(phi.sin() * theta.cos(), phi.sin() * theta.sin(), phi.cos());
+            let p = scale * n;
+            let expected = Vec3f::new(p.x / 4.0, p.y, p.z).normalized();
+
+            assert_close((normal_matrix * n).normalized(), expected);
+            let naive = scale.transform_direction(n).normalized();
+            naive_worst = naive_worst.max((naive - expected).length());
+        }
+    }
+    assert!(naive_worst > 0.1, "{}", naive_worst);
+
+    let m = Mat3::new([[2.0, 0.0, 1.0], [1.0, 3.0, 0.0], [0.0, 1.0, 4.0]]);
+    assert_eq!(m.determinant(), 25.0);
+    let inv = m.inverse().unwrap();
+    let identity = m * inv;
+    for i in 0..3 {
+        for j in 0..3 {
+            assert!((identity.m[i][j] - if i == j { 1.0 } else { 0.0 }).abs() < 1e-6, "{:?}", identity);
+        }
+    }
+    assert_eq!(m.transpose().m[0][1], 1.0);
+    assert!(Mat3::new([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 0.0, 1.0]]).inverse().is_none());
+
+    // Translation doesn't reach normals
+    let moved = (Mat4::translation(Vec3f::new(5.0, 5.0, 5.0)) * scale).normal_matrix();
+    assert_eq!(moved, normal_matrix);
+}
+
+#[test]
+fn lerp_hits_the_ends_and_extrapolates() {
+    assert_eq!(lerp(2.0, 6.0, 0.0), 2.0);
+    assert_eq!(lerp(2.0, 6.0, 1.0), 6.0);
+    assert_eq!(lerp(2.0, 6.0, 0.25), 3.0);
+    assert_eq!(lerp(2.0, 6.0, -0.5), 0.0);
+    assert_eq!(lerp(2.0, 6.0, 1.5), 8.0);
+
+    let (a, b) = (Vec3f::new(0.0, 1.0, -2.0), Vec3f::new(4.0, 1.0, 2.0));
+    assert_eq!(lerp(a, b, 0.0), a);
+    assert_eq!(lerp(a, b, 1.0), b);
+    assert_eq!(lerp(a, b, 2.0), Vec3f::new(8.0, 1.0, 6.0));
+    let uv = lerp(Vec2f::new(0.0, 1.0), Vec2f::new(1.0, 0.0), 0.75);
+    assert_eq!((uv.x, uv.y), (0.75, 0.25));
+
+    // Colors aren't clamped either
+    let (black, white) = (RgbaColor::new(0.0, 0.0, 0.0, 1.0), RgbaColor::new(1.0, 1.0, 1.0, 1.0));
+    assert_eq!(lerp(black, white, 0.0), black);
+    assert_eq!(lerp(black, white, 1.0), white);
+    assert_eq!(lerp(black, white, 1.5), RgbaColor::new(1.5, 1.5, 1.5, 1.0));
+
+    assert_eq!((clamp01(-0.5), clamp01(0.3), clamp01(7.0)), (0.0, 0.3, 1.0));
+    assert_eq!((smoothstep(1.0, 3.0, 0.0), smoothstep(1.0, 3.0, 2.0), smoothstep(1.0, 3.0, 9.0)), (0.0, 0.5, 1.0));
+    assert!(smoothstep(1.0, 3.0, 1.5) < 0.25);
+}
+
+#[test]
+fn barycentric_mix_tolerates_rounded_weights() {
+    let (a, b, c) = (Vec3f::new(1.0, 0.0, 0.0), Vec3f::new(0.0, 1.0, 0.0), Vec3f::new(0.0, 0.0, 1.0));
+    assert_eq!(barycentric_mix(a, b, c, Vec3f::new(1.0, 0.0, 0.0)), a);
+    assert_eq!(barycentric_mix(a, b, c, Vec3f::new(0.0, 1.0, 0.0)), b);
+    assert_eq!(barycentric_mix(a, b, c, Vec3f::new(0.0, 0.0, 1.0)), c);
+    assert_close(barycentric_mix(a, b, c, Vec3f::new(0.2, 0.3, 0.5)), Vec3f::new(0.2, 0.3, 0.5));
+
+    // Weights a rounding error away from summing to 1
+    let w = Vec3f::new(0.1, 0.2, 0.7 + 1e-7);
+    assert_close(barycentric_mix(a, b, c, w), Vec3f::new(0.1, 0.2, 0.7));
+    let w = Vec3f::new(1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0);
+    assert_close(barycentric_mix(a, b, c, w), Vec3f::new(1.0, 1.0, 1.0) / 3.0);
+    assert_eq!(barycentric_mix(2.0, 4.0, 8.0, Vec3f::new(0.5, 0.5, 0.0)), 3.0);
+}
+
+#[test]
+fn ten_million_increments_drift_in_f32_only() {
+    let step = Vec3f::new(1e-4, 2e-4, 0.0);
+    let mut single = Vec3f::new(0.0, 0.0, 0.0);
+    let mut double = Vec3d::new(0.0, 0.0, 0.0);
+    for _ in 0..10_000_000 {
+        single += step;
+        double += Vec3d::from(step);
+    }
+
+    // Exactly 1000 and 2000 give or take the rounding of the step itself
+    let expected = Vec3d::from(step) * 1e7;
+    assert!((double - expected).length() < 1e-4, "{:?}", double);
+    assert!((Vec3d::from(single) - expected).length() > 1.0, "{:?}", single);
+}
+
+#[test]
+fn single_and_double_precision_agree() {
+    let (a, b) = (Vec3f::new(1.5, -2.0, 0.25), Vec3f::new(-3.0, 0.5, 4.0));
+    let (da, db) = (Vec3d::from(a), Vec3d::from(b));
+    assert_eq!(Vec3f::from(da + db), a + b);
+    assert_eq!(Vec3f::from(da - db), a - b);
+    assert_eq!(Vec3f::from(da ^ db), a ^ b);
+    assert_eq!(Vec3f::from(da * 2.0), a * 2.0);
+    assert_eq!((da * db) as f32, a * b);
+
+    let m = Mat4::translation(Vec3f::new(1.0, 2.0, 3.0)) * Mat4::rotation_y(0.5) * Mat4::scale(Vec3f::new(2.0, 2.0, 2.0));
+    let dm = Mat4d::translation(Vec3d::new(1.0, 2.0, 3.0)) * Mat4d::rotation_y(0.5) * Mat4d::scale(Vec3d::new(2.0, 2.0, 2.0));
+    assert_mat_close(&Mat4::from(dm), &m);
+    assert_mat_close(&Mat4::from(Mat4d::from(m).inverse().unwrap()), &m.inverse().unwrap());
+    assert_close(Vec3f::from(dm * db), m * b);
+}
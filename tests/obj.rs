@@ -0,0 +1,409 @@
+extern crate renderer;
+
+use std::env;
+use std::fs::File;
+use std::io::{BufRead,BufReader,Write};
+use std::path::Path;
+use renderer::{Renderer,RgbaColor,Vec3f,Mat4};
+use renderer::model::{Model,ModelError};
+
+// Loads text as an .obj file, name keeps parallel tests apart
+fn obj(name: &str, text: &str) -> Result<Model, ModelError> {
+    let path = env::temp_dir().join(format!("renderer_obj_{}.obj", name));
+    File::create(&path).unwrap().write_all(text.as_bytes()).unwrap();
+    Model::new_from_file(&path)
+}
+
+#[test]
+fn negative_indices_before_the_first_element_are_out_of_range() {
+    match obj("negative_range", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf -1 -2 -4\n") {
+        Err(ModelError::IndexOutOfRange { face: 0, kind: "vertex", index: -4, count: 3 }) => {},
+        other => panic!("loaded as {:?}", other.map(|m| m.faces))
+    }
+    match obj("negative_range_vt", "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nf 1/-1 2/-2 3/-1\n") {
+        Err(ModelError::IndexOutOfRange { face: 0, kind: "texture coord", index: -2, count: 1 }) => {},
+        other => panic!("loaded as {:?}", other.map(|m| m.faces))
+    }
+}
+
+#[test]
+fn indexing_shares_corners_between_faces() {
+    let model = obj("indexed_quad", "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\nf 1//1 3//1 4//1\n").unwrap();
+    let mesh = model.build_indexed();
+    assert_eq!(mesh.original_vertex_count(), 6);
+    assert_eq!(mesh.unique_vertex_count(), 4);
+    assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+
+    // A different normal on a shared position is another vertex
+    let model = obj("indexed_crease", "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nvn 1 0 0\nf 1//1 2//1 3//1\nf 1//2 3//2 4//2\n").unwrap();
+    assert_eq!(model.build_indexed().unique_vertex_count(), 6);
+}
+
+#[test]
+fn indexing_the_head_removes_most_duplicates() {
+    let mesh = Model::new_from_file(Path::new("data/model.obj")).unwrap().build_indexed();
+    assert!(mesh.unique_vertex_count() * 2 < mesh.original_vertex_count(),
+            "{} unique of {}", mesh.unique_vertex_count(), mesh.original_vertex_count());
+}
+
+// Positions, texture coords, normals and faces as bits, read the way the
+// loader did before it reused its buffers: a String per line, split and
+// parsed word by word
+type Parsed = (Vec<[u32; 3]>, Vec<[u32; 2]>, Vec<[u32; 3]>, Vec<[i32; 9]>);
+
+fn line_by_line(path: &Path) -> Parsed {
+    let (mut vertices, mut uvs, mut normals, mut faces) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for line in BufReader::new(File::open(path).unwrap()).lines() {
+        let line = line.unwrap();
+        let mut words = line.split_whitespace();
+        let kind = words.next().unwrap_or("");
+        let words: Vec<String> = words.map(|w| w.to_string()).collect();
+        let values = || words.iter().map(|w| w.parse::<f32>().unwrap().to_bits()).collect::<Vec<u32>>();
+        match kind {
+            "v" => { let v = values(); vertices.push([v[0], v[1], v[2]]); },
+            "vt" => { let v = values(); uvs.push([v[0], v[1]]); },
+            "vn" => { let v = values(); normals.push([v[0], v[1], v[2]]); },
+            "f" => {
+                let counts = [vertices.len() as i32, uvs.len() as i32, normals.len() as i32];
+                let corners: Vec<[i32; 3]> = words.iter().map(|w| {
+                    let mut corner = [-1; 3];
+                    for (i, index) in w.split('/').enumerate().filter(|&(_, index)| !index.is_empty()) {
+                        let index: i32 = index.parse().unwrap();
+                        corner[i] = if index < 0 { counts[i] + index } else { index - 1 };
+                    }
+                    corner
+                }).collect();
+                for i in 1..corners.len() - 1 {
+                    let (a, b, c) = (corners[0], corners[i], corners[i + 1]);
+                    faces.push([a[0], a[1], a[2], b[0], b[1], b[2], c[0], c[1], c[2]]);
+                }
+            },
+            _ => {}
+        }
+    }
+    (vertices, uvs, normals, faces)
+}
+
+fn bits(model: &Model) -> Parsed {
+    (model.vertices.iter().map(|v| [v.x.to_bits(), v.y.to_bits(), v.z.to_bits()]).collect(),
+     model.texture_coords.iter().map(|t| [t.x.to_bits(), t.y.to_bits()]).collect(),
+     model.normals.iter().map(|n| [n.x.to_bits(), n.y.to_bits(), n.z.to_bits()]).collect(),
+     model.faces.clone())
+}
+
+#[test]
+fn head_loads_bit_for_bit_like_a_line_by_line_parser() {
+    let path = Path::new("data/model.obj");
+    assert!(bits(&Model::new_from_file(path).unwrap()) == line_by_line(path));
+}
+
+// Loads a generated grid of 2M triangles with both parsers and prints how
+// long each took. cargo test --release --features timing -- --nocapture
+#[cfg(feature = "timing")]
+#[test]
+fn large_obj_timing() {
+    use std::time::Instant;
+
+    const SIDE: usize = 1000;
+    let path = env::temp_dir().join("renderer_obj_timing.obj");
+    {
+        let mut out = std::io::BufWriter::new(File::create(&path).unwrap());
+        for y in 0..SIDE {
+            for x in 0..SIDE {
+                let (u, v) = (x as f32 / (SIDE - 1) as f32, y as f32 / (SIDE - 1) as f32);
+                writeln!(out, "v {} {} {}", u * 2.0 - 1.0, v * 2.0 - 1.0, (u * 17.0).sin() * (v * 13.0).cos() * 0.1).unwrap();
+                writeln!(out, "vt {} {}", u, v).unwrap();
+            }
+        }
+        writeln!(out, "vn 0 0 1").unwrap();
+        for y in 0..SIDE - 1 {
+            for x in 0..SIDE - 1 {
+                let i = y * SIDE + x + 1;
+                writeln!(out, "f {0}/{0}/1 {1}/{1}/1 {2}/{2}/1 {3}/{3}/1", i, i + 1, i + SIDE + 1, i + SIDE).unwrap();
+            }
+        }
+    }
+
+    let start = Instant::now();
+    let model = Model::new_from_file(&path).unwrap();
+    let loader = start.elapsed();
+    let start = Instant::now();
+    let reference = line_by_line(&path);
+    let line_by_line = start.elapsed();
+    println!("{} faces: loader {:?}, line by line {:?}", model.faces.len(), loader, line_by_line);
+
+    assert_eq!(model.faces.len(), 2 * (SIDE - 1) * (SIDE - 1));
+    assert!(bits(&model) == reference);
+}
+
+const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\n";
+
+#[test]
+fn corners_keep_their_normal_indices() {
+    let model = obj("normals", &format!("{}vn 0 0 1\nvn 0 1 0\nvn 1 0 0\nf 1/1/3 2/2/1 3/3/2\n", TRIANGLE)).unwrap();
+    assert_eq!(model.faces, vec![[0, 0, 2, 1, 1, 0, 2, 2, 1]]);
+    assert_eq!(model.normal(0, 0), Some(Vec3f::new(1.0, 0.0, 0.0)));
+    assert_eq!(model.normal(0, 1), Some(Vec3f::new(0.0, 0.0, 1.0)));
+    assert_eq!(model.normal(0, 2), Some(Vec3f::new(0.0, 1.0, 0.0)));
+}
+
+#[test]
+fn normals_may_follow_the_faces() {
+    let model = obj("normals_after", &format!("{}f 1//1 2//1 3//2\nvn 0 0 1\nvn 0 1 0\n", TRIANGLE)).unwrap();
+    assert_eq!(model.normals.len(), 2);
+    assert_eq!(model.normal(0, 2), Some(Vec3f::new(0.0, 1.0, 0.0)));
+}
+
+// Unit cube written with one quad per side
+const QUAD_CUBE: &str = "\
+v -1 -1 -1\nv 1 -1 -1\nv 1 1 -1\nv -1 1 -1\nv -1 -1 1\nv 1 -1 1\nv 1 1 1\nv -1 1 1
+f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 4 8 7 3\nf 1 5 8 4\nf 2 3 7 6
+";
+
+#[test]
+fn quads_are_split_into_triangle_fans() {
+    let model = obj("quad_cube", QUAD_CUBE).unwrap();
+    assert_eq!(model.faces.len(), 12);
+    assert_eq!(model.triangulated_faces, 6);
+    assert_eq!((model.faces[0][0], model.faces[0][3], model.faces[0][6]), (0, 3, 2));
+    assert_eq!((model.faces[1][0], model.faces[1][3], model.faces[1][6]), (0, 2, 1));
+
+    let mut renderer = Renderer::new(32, 32);
+    renderer.set_backface_culling(false);
+    renderer.draw_model(&model, &Mat4::rotation_y(0.5));
+    assert_eq!(renderer.stats().submitted, 12);
+
+    // A pentagon fans out from its first corner
+    let model = obj("pentagon", &format!("{}v 1 1 0\nv 0.5 2 0\nf 1/1 2/2 4 5 3/3\n", TRIANGLE)).unwrap();
+    assert_eq!(model.triangulated_faces, 1);
+    let corners: Vec<(i32, i32)> = model.faces.iter().flat_map(|f| vec![(f[0], f[1]), (f[3], f[4]), (f[6], f[7])]).collect();
+    assert_eq!(corners, vec![(0, 0), (1, 1), (3, -1), (0, 0), (3, -1), (4, -1), (0, 0), (4, -1), (2, 2)]);
+}
+
+#[test]
+fn all_four_face_formats() {
+    let text = format!("{}vn 0 0 1\n", TRIANGLE);
+    let formats = [
+        ("f 1 2 3", [0, -1, -1, 1, -1, -1, 2, -1, -1]),
+        ("f 1/1 2/2 3/3", [0, 0, -1, 1, 1, -1, 2, 2, -1]),
+        ("f 1//1 2//1 3//1", [0, -1, 0, 1, -1, 0, 2, -1, 0]),
+        ("f 1/1/1 2/2/1 3/3/1", [0, 0, 0, 1, 1, 0, 2, 2, 0])
+    ];
+    for (i, &(face, expected)) in formats.iter().enumerate() {
+        let model = obj(&format!("format_{}", i), &format!("{}{}\n", text, face)).unwrap();
+        assert_eq!(model.faces, vec![expected], "{}", face);
+    }
+
+    let mixed: Vec<&str> = formats.iter().map(|f| f.0).collect();
+    let model = obj("format_mixed", &format!("{}{}\nf 3/3 1 2//1\n", text, mixed.join("\n"))).unwrap();
+    let mut expected: Vec<[i32; 9]> = formats.iter().map(|f| f.1).collect();
+    expected.push([2, 2, -1, 0, -1, -1, 1, -1, 0]);
+    assert_eq!(model.faces, expected);
+}
+
+#[test]
+fn faces_without_normals_get_generated_ones() {
+    let model = obj("no_normals", &format!("{}f 1/1 2/2 3/3\n", TRIANGLE)).unwrap();
+    assert_eq!(model.normal(0, 0), Some(Vec3f::new(0.0, 0.0, 1.0)));
+    let uv = model.texture_coord(0, 1).unwrap();
+    assert_eq!((uv.x, uv.y), (1.0, 0.0));
+}
+
+#[test]
+fn negative_indices_count_back_from_the_last_element() {
+    // The second triangle's -1 is the fourth vertex, defined after the first face
+    let model = obj("negative", &format!("{}vn 0 0 1\nf -3/-3/-1 -2/-2/-1 -1/-1/-1\nv 1 1 0\nf -3 -2 -1\n", TRIANGLE)).unwrap();
+    assert_eq!(model.faces, vec![[0, 0, 0, 1, 1, 0, 2, 2, 0], [1, -1, -1, 2, -1, -1, 3, -1, -1]]);
+}
+
+#[test]
+fn broken_files_report_what_went_wrong() {
+    match Model::new_from_file(&env::temp_dir().join("renderer_obj_missing.obj")) {
+        Err(ModelError::Io(_)) => {},
+        other => panic!("loaded as {:?}", other.map(|m| m.faces))
+    }
+
+    // Cut off in the middle of a number, and garbage in a face
+    for &(name, text, line, bad) in [
+        ("truncated", "v 0 0 0\nv 1 0 0\nv 0 1e", 3, "v 0 1e"),
+        ("corrupted", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 x\n", 4, "f 1 2 x"),
+        ("zero_index", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2\n", 4, "f 0 1 2")
+    ].iter() {
+        match obj(name, text) {
+            Err(ModelError::Parse { line: l, ref text }) if l == line && text == bad => {},
+            other => panic!("{} loaded as {:?}", name, other.map(|m| m.faces))
+        }
+    }
+
+    // Forward references are only caught once the whole file is read
+    match obj("forward", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 1 2 9999\n") {
+        Err(e @ ModelError::IndexOutOfRange { face: 1, kind: "vertex", index: 9999, count: 3 }) =>
+            assert_eq!(e.to_string(), "face 1 references vertex 9999 but the file only has 3"),
+        other => panic!("loaded as {:?}", other.map(|m| m.faces))
+    }
+}
+
+#[test]
+fn generated_cube_normals_average_the_sides() {
+    // Corners 1 to 8 from bits of i, then a center vertex per side with four
+    // triangles around it, so every corner touches equal areas of its sides
+    let mut text = String::new();
+    for i in 0..8 {
+        let c = |bit: i32| if i & bit != 0 { 1 } else { -1 };
+        text.push_str(&format!("v {} {} {}\n", c(1), c(2), c(4)));
+    }
+    let sides = [[0, 2, 3, 1], [4, 5, 7, 6], [0, 1, 5, 4], [2, 6, 7, 3], [0, 4, 6, 2], [1, 3, 7, 5]];
+    for (s, side) in sides.iter().enumerate() {
+        let mut center = [0; 3];
+        for &i in side.iter() {
+            for (axis, &bit) in [1, 2, 4].iter().enumerate() {
+                center[axis] += if i & bit != 0 { 1 } else { -1 };
+            }
+        }
+        text.push_str(&format!("v {} {} {}\n", center[0] / 4, center[1] / 4, center[2] / 4));
+        for k in 0..4 {
+            text.push_str(&format!("f {} {} {}\n", side[k] + 1, side[(k + 1) % 4] + 1, 9 + s));
+        }
+    }
+
+    let model = obj("cube_normals", &text).unwrap();
+    assert_eq!(model.normals.len(), 14);
+    let d = 1.0 / 3f32.sqrt();
+    for (i, n) in model.normals.iter().enumerate() {
+        let expected = if i < 8 {
+            let c = |bit: usize| if i & bit != 0 { d } else { -d };
+            Vec3f::new(c(1), c(2), c(4))
+        } else {
+            model.vertices[i]
+        };
+        assert!((*n - expected).length() < 1e-6, "vertex {}: {:?}", i, n);
+    }
+    // Every corner points at its own normal
+    for face in model.faces.iter() {
+        assert_eq!((face[2], face[5], face[8]), (face[0], face[3], face[6]));
+    }
+}
+
+#[test]
+fn degenerate_faces_add_nothing_to_generated_normals() {
+    let model = obj("degenerate", "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 2 0 0\nf 1 2 3\nf 1 2 4\n").unwrap();
+    assert_eq!(model.normals[1], Vec3f::new(0.0, 0.0, 1.0));
+    // Only on the degenerate face, left zero rather than NaN
+    assert_eq!(model.normals[3], Vec3f::new(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn groups_and_objects_collect_their_faces() {
+    let text = format!("{}f 1 2 3\no left\nf 1 2 3\nf 1 3 2\ng right\nf 2 3 1\ng left\nf 3 1 2\n", TRIANGLE);
+    let model = obj("groups", &text).unwrap();
+    assert_eq!(model.group_names(), vec!["default", "left", "right"]);
+    assert_eq!(model.faces_for_group("default"), Some(&[0][..]));
+    assert_eq!(model.faces_for_group("left"), Some(&[1, 2, 4][..]));
+    assert_eq!(model.faces_for_group("right"), Some(&[3][..]));
+    assert_eq!(model.faces_for_group("missing"), None);
+
+    // Drawing one group draws only its faces
+    let mut renderer = Renderer::new(16, 16);
+    renderer.set_backface_culling(false);
+    renderer.draw_model_groups(&model, &Mat4::identity(), &["right"]);
+    assert_eq!(renderer.stats().submitted, 1);
+}
+
+#[test]
+fn materials_come_from_the_library() {
+    let mtl = "newmtl red\nKd 1 0 0\nKs 0.5 0.5 0.5\nNs 32\nmap_Kd missing.tga\nnewmtl blue\nKd 0 0 1\nd 0.5\n";
+    File::create(env::temp_dir().join("renderer_obj_materials.mtl")).unwrap().write_all(mtl.as_bytes()).unwrap();
+    let text = format!("mtllib renderer_obj_materials.mtl missing.mtl\n{}f 1 2 3\nusemtl red\nf 1 2 3\nusemtl nothing\nf 1 2 3\nusemtl blue\nf 1 2 3\n", TRIANGLE);
+    let model = obj("materials", &text).unwrap();
+
+    assert_eq!(model.face_materials, vec![-1, 0, -1, 1]);
+    let red = model.material(1).unwrap();
+    assert_eq!((red.diffuse_color, red.specular_color, red.shininess), (RgbaColor::new(1.0, 0.0, 0.0, 1.0), RgbaColor::new(0.5, 0.5, 0.5, 1.0), 32.0));
+    assert!(red.diffuse_map.is_none());
+    assert_eq!(model.material(3).unwrap().diffuse_color, RgbaColor::new(0.0, 0.0, 1.0, 0.5));
+    assert!(model.material(0).is_none() && model.material(2).is_none());
+
+    // Faces with a material draw in its color
+    let model = obj("material_red", &format!("mtllib renderer_obj_materials.mtl\n{}usemtl red\nf 1 2 3\n", TRIANGLE)).unwrap();
+    let mut renderer = Renderer::new(16, 16);
+    renderer.clear_lights();
+    renderer.set_ambient(1.0);
+    renderer.draw_model(&model, &Mat4::identity());
+    let c = renderer.to_image().get_pixel(9, 9);
+    assert!(c.r > 0.99 && c.g == 0.0 && c.b == 0.0, "{:?}", c);
+}
+
+#[test]
+fn tangents_point_along_u() {
+    // The same quad twice, U along +x and then along +y
+    for &(uvs, tangent) in [("vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n", Vec3f::new(1.0, 0.0, 0.0)),
+                            ("vt 0 0\nvt 0 -1\nvt 1 -1\nvt 1 0\n", Vec3f::new(0.0, 1.0, 0.0))].iter() {
+        let text = format!("v 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\n{}vn 0 0 1\nf 1/1/1 2/2/1 3/3/1 4/4/1\n", uvs);
+        let mut model = obj("tangents", &text).unwrap();
+        model.compute_tangents().unwrap();
+        assert_eq!(model.tangents.len(), model.normals.len());
+        assert!((model.tangents[0] - tangent).length() < 1e-6, "{:?}", model.tangents[0]);
+        assert_eq!(model.bitangent_signs[0], 1.0);
+    }
+
+    // Mirrored V flips the bitangent
+    let text = "v 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\nvt 0 1\nvt 1 1\nvt 1 0\nvt 0 0\nvn 0 0 1\nf 1/1/1 2/2/1 3/3/1 4/4/1\n";
+    let mut model = obj("tangents_mirrored", text).unwrap();
+    model.compute_tangents().unwrap();
+    assert_eq!(model.bitangent_signs[0], -1.0);
+
+    let mut model = obj("tangents_without_uvs", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+    match model.compute_tangents() {
+        Err(ModelError::MissingAttribute(_)) => {},
+        other => panic!("computed tangents: {:?}", other.is_ok())
+    }
+}
+
+#[test]
+fn written_models_load_back_the_same() {
+    let mut model = Model::new_from_file(Path::new("data/model.obj")).unwrap();
+    model.normalize_to_unit_cube();
+    let path = env::temp_dir().join("renderer_obj_written_head.obj");
+    model.write_to_file(&path).unwrap();
+    let loaded = Model::new_from_file(&path).unwrap();
+    assert_eq!((loaded.vertices.len(), loaded.texture_coords.len(), loaded.normals.len()),
+               (model.vertices.len(), model.texture_coords.len(), model.normals.len()));
+    assert!(bits(&loaded) == bits(&model));
+
+    // Without texture coords, in groups
+    let model = obj("written_groups", "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\ng top\nf 3//1 2//1 1//1\n").unwrap();
+    let path = env::temp_dir().join("renderer_obj_written_groups.obj");
+    model.write_to_file(&path).unwrap();
+    let loaded = Model::new_from_file(&path).unwrap();
+    assert_eq!(loaded.faces, model.faces);
+    assert_eq!(loaded.group_names(), vec!["default", "top"]);
+    assert!(loaded.texture_coords.is_empty());
+}
+
+#[test]
+fn vertex_colors_fill_in_white() {
+    let model = obj("colors", "v 0 0 0\nv 1 0 0 1 0 0\nv 0 1 0\nv 1 1 0 0 0.5 1\nf 1 2 3\n").unwrap();
+    let white = RgbaColor::new(1.0, 1.0, 1.0, 1.0);
+    assert_eq!(model.vertex_colors, vec![white, RgbaColor::new(1.0, 0.0, 0.0, 1.0), white, RgbaColor::new(0.0, 0.5, 1.0, 1.0)]);
+    assert_eq!(model.vertex_color(0, 1), Some(RgbaColor::new(1.0, 0.0, 0.0, 1.0)));
+
+    let model = obj("no_colors", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+    assert!(model.vertex_colors.is_empty());
+    assert_eq!(model.vertex_color(0, 0), None);
+}
+
+#[test]
+fn vertex_colors_are_interpolated_without_a_texture() {
+    let model = obj("colors_render", "v -1 -1 0 1 0 0\nv 1 -1 0 0 1 0\nv -1 1 0 0 0 1\nf 1 2 3\n").unwrap();
+    let mut renderer = Renderer::new(32, 32);
+    renderer.clear_lights();
+    renderer.set_ambient(1.0);
+    renderer.draw_model(&model, &Mat4::identity());
+    let image = renderer.to_image();
+    let (red, green, blue) = (image.get_pixel(1, 1), image.get_pixel(28, 1), image.get_pixel(1, 28));
+    assert!(red.r > 0.9 && red.g < 0.1 && red.b < 0.1, "{:?}", red);
+    assert!(green.g > 0.8 && green.r < 0.2, "{:?}", green);
+    assert!(blue.b > 0.8 && blue.r < 0.2, "{:?}", blue);
+    let middle = image.get_pixel(10, 10);
+    assert!(middle.r > 0.2 && middle.g > 0.2 && middle.b > 0.2, "{:?}", middle);
+}
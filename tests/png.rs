@@ -0,0 +1,100 @@
+extern crate renderer;
+
+use renderer::{TgaImage,RgbaColor};
+
+fn be32(data: &[u8]) -> u32 {
+    (data[0] as u32) << 24 | (data[1] as u32) << 16 | (data[2] as u32) << 8 | data[3] as u32
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB88320 & (!(crc & 1)).wrapping_add(1));
+        }
+    }
+    !crc
+}
+
+// Chunk types and payloads, checking each CRC on the way
+fn chunks(data: &[u8]) -> Vec<(String, Vec<u8>)> {
+    assert_eq!(&data[..8], &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n']);
+    let mut result = Vec::new();
+    let mut i = 8;
+    while i < data.len() {
+        let len = be32(&data[i..]) as usize;
+        let body = &data[i + 4..i + 8 + len];
+        assert_eq!(be32(&data[i + 8 + len..]), crc32(body), "CRC of chunk at {}", i);
+        result.push((String::from_utf8(body[..4].to_vec()).unwrap(), body[4..].to_vec()));
+        i += 12 + len;
+    }
+    result
+}
+
+// Contents of a zlib stream made of stored deflate blocks
+fn inflate_stored(data: &[u8]) -> Vec<u8> {
+    assert_eq!((data[0] as u32 * 256 + data[1] as u32) % 31, 0);
+    let mut raw = Vec::new();
+    let mut i = 2;
+    loop {
+        let last = data[i] & 1 != 0;
+        assert_eq!(data[i] & 6, 0, "stored block");
+        let len = data[i + 1] as usize | (data[i + 2] as usize) << 8;
+        assert_eq!(len ^ 0xFFFF, data[i + 3] as usize | (data[i + 4] as usize) << 8);
+        raw.extend(&data[i + 5..i + 5 + len]);
+        i += 5 + len;
+        if last { break; }
+    }
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for &v in raw.iter() {
+        a = (a + v as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    assert_eq!(be32(&data[i..]), b << 16 | a, "adler32");
+    assert_eq!(data.len(), i + 4);
+    raw
+}
+
+#[test]
+fn crc_matches_the_known_iend_value() {
+    assert_eq!(crc32(b"IEND"), 0xAE426082);
+}
+
+#[test]
+fn png_scanlines_read_back() {
+    // More than one stored block worth of scanlines
+    let (width, height) = (300, 120);
+    let mut image = TgaImage::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            image.set_pixel(x, y, &RgbaColor::new_from_u8(x as u8, y as u8, (x ^ y) as u8, 255));
+        }
+    }
+
+    for &alpha in [false, true].iter() {
+        let mut data = Vec::new();
+        image.encode_png(&mut data, alpha).unwrap();
+
+        let chunks = chunks(&data);
+        let kinds: Vec<&str> = chunks.iter().map(|c| c.0.as_str()).collect();
+        assert_eq!(kinds, vec!["IHDR", "IDAT", "IEND"]);
+
+        let ihdr = &chunks[0].1;
+        assert_eq!((be32(ihdr), be32(&ihdr[4..])), (width as u32, height as u32));
+        assert_eq!(&ihdr[8..], &[8, if alpha { 6 } else { 2 }, 0, 0, 0]);
+
+        // Filter type 0 scanlines from the top row down
+        let mut expected = Vec::new();
+        for y in (0..height).rev() {
+            expected.push(0);
+            for x in 0..width {
+                expected.extend(&[x as u8, y as u8, (x ^ y) as u8]);
+                if alpha { expected.push(255); }
+            }
+        }
+        let raw = inflate_stored(&chunks[1].1);
+        assert!(raw == expected, "scanlines differ");
+    }
+}
@@ -0,0 +1,111 @@
+extern crate renderer;
+
+use renderer::{Renderer,Model,PointColor,TgaImage,RgbaColor,Vec3f,Mat4};
+
+fn points(vertices: Vec<Vec3f>) -> Model {
+    Model::new(vertices, Vec::new(), Vec::new(), Vec::new()).unwrap()
+}
+
+fn lit(image: &TgaImage) -> Vec<(i32, i32)> {
+    let mut pixels = Vec::new();
+    for y in 0..image.height {
+        for x in 0..image.width {
+            if image.get_pixel(x, y).g > 0.5 {
+                pixels.push((x, y));
+            }
+        }
+    }
+    pixels
+}
+
+#[test]
+fn cube_corners_land_where_projected() {
+    let mut corners = Vec::new();
+    for &x in [-0.5, 0.5].iter() {
+        for &y in [-0.5, 0.5].iter() {
+            for &z in [-0.5, 0.5].iter() {
+                corners.push(Vec3f::new(x, y, z));
+            }
+        }
+    }
+    let transform = Mat4::rotation_x(0.4) * Mat4::rotation_y(0.5);
+
+    let mut renderer = Renderer::new(64, 64);
+    renderer.set_color(RgbaColor::new(0.0, 1.0, 0.0, 1.0));
+    renderer.set_model_transform(transform);
+    renderer.draw_points(&points(corners.clone()), 1);
+
+    // Orthographic, NDC [-1, 1] onto the 64 pixels
+    let mut expected: Vec<(i32, i32)> = corners.iter().map(|&c| {
+        let p = transform * c;
+        (((p.x + 1.0) * 32.0).floor() as i32, ((p.y + 1.0) * 32.0).floor() as i32)
+    }).collect();
+    expected.sort_by_key(|&(x, y)| (y, x));
+    assert_eq!(lit(&renderer.to_image()), expected);
+}
+
+#[test]
+fn splats_are_squares_then_discs() {
+    let model = points(vec![Vec3f::new(0.0, 0.0, 0.0)]);
+    let mut renderer = Renderer::new(32, 32);
+    renderer.set_color(RgbaColor::new(0.0, 1.0, 0.0, 1.0));
+    renderer.draw_points(&model, 3);
+    assert_eq!(lit(&renderer.to_image()).len(), 9);
+
+    renderer.clear(RgbaColor::new(0.0, 0.0, 0.0, 1.0));
+    renderer.draw_points(&model, 8);
+    let disc = lit(&renderer.to_image());
+    assert!(disc.len() > 40 && disc.len() < 64, "{}", disc.len());
+    assert!(disc.contains(&(16, 16)) && !disc.contains(&(12, 12)));
+}
+
+#[test]
+fn offscreen_vertices_are_skipped_and_splats_clipped() {
+    let model = points(vec![
+        Vec3f::new(1.0, 1.0, 0.0),
+        // Beyond the view volume in every direction
+        Vec3f::new(0.0, 0.0, 2.0), Vec3f::new(0.0, 0.0, -2.0), Vec3f::new(50.0, 0.0, 0.0), Vec3f::new(0.0, -1e30, 0.0)
+    ]);
+    let mut renderer = Renderer::new(32, 32);
+    renderer.set_color(RgbaColor::new(0.0, 1.0, 0.0, 1.0));
+    renderer.draw_points(&model, 5);
+    // The corner vertex keeps the quarter of its disc inside, rounded off at (30, 30)
+    assert_eq!(lit(&renderer.to_image()), vec![(31, 30), (30, 31), (31, 31)]);
+}
+
+#[test]
+fn points_behind_a_filled_render_are_hidden() {
+    // Covers the left half at z = 0
+    let wall = Model::new(
+        vec![Vec3f::new(-1.0, -1.0, 0.0), Vec3f::new(0.0, -1.0, 0.0), Vec3f::new(0.0, 1.0, 0.0), Vec3f::new(-1.0, 1.0, 0.0)],
+        Vec::new(), Vec::new(),
+        vec![[0, -1, -1, 1, -1, -1, 2, -1, -1], [0, -1, -1, 2, -1, -1, 3, -1, -1]]
+    ).unwrap();
+    let cloud = points(vec![Vec3f::new(-0.5, 0.0, -0.5), Vec3f::new(-0.5, 0.5, 0.5), Vec3f::new(0.5, 0.0, -0.5)]);
+
+    let mut renderer = Renderer::new(32, 32);
+    renderer.set_color(RgbaColor::new(1.0, 0.0, 0.0, 1.0));
+    renderer.draw_model(&wall, &Mat4::identity());
+    renderer.set_color(RgbaColor::new(0.0, 1.0, 0.0, 1.0));
+
+    renderer.draw_points(&cloud, 1);
+    assert_eq!(lit(&renderer.to_image()), vec![(8, 16), (24, 16), (8, 24)]);
+
+    renderer.clear(RgbaColor::new(0.0, 0.0, 0.0, 1.0));
+    renderer.set_color(RgbaColor::new(1.0, 0.0, 0.0, 1.0));
+    renderer.draw_model(&wall, &Mat4::identity());
+    renderer.set_color(RgbaColor::new(0.0, 1.0, 0.0, 1.0));
+    renderer.set_wireframe_depth_test(Some(0.01));
+    renderer.draw_points(&cloud, 1);
+    assert_eq!(lit(&renderer.to_image()), vec![(24, 16), (8, 24)]);
+}
+
+#[test]
+fn depth_coloring() {
+    let mut renderer = Renderer::new(16, 16);
+    renderer.set_point_color(PointColor::Depth);
+    renderer.draw_points(&points(vec![Vec3f::new(-0.5, 0.0, 0.5), Vec3f::new(0.5, 0.0, -0.5)]), 1);
+    let image = renderer.to_image();
+    assert!(image.get_pixel(4, 8).r > image.get_pixel(12, 8).r);
+    assert!(image.get_pixel(12, 8).r > 0.0);
+}
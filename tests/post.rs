@@ -0,0 +1,84 @@
+extern crate renderer;
+
+use renderer::{Renderer,Kernel,TgaImage,RgbaColor};
+
+fn white_dot(width: i32, height: i32, x: i32, y: i32) -> TgaImage {
+    let mut image = TgaImage::new(width, height);
+    image.set_pixel(x, y, &RgbaColor::new(1.0, 1.0, 1.0, 1.0));
+    image
+}
+
+fn red(image: &TgaImage, x: i32, y: i32) -> u8 {
+    (image.get_pixel(x, y).r * 255.0).round() as u8
+}
+
+#[test]
+fn identity_keeps_every_pixel() {
+    let mut image = TgaImage::new(16, 9);
+    for y in 0..9 {
+        for x in 0..16 {
+            image.set_pixel(x, y, &RgbaColor::new_from_u8((x * 16) as u8, (y * 28) as u8, (x * y) as u8, 200));
+        }
+    }
+
+    let identity = [0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+    for result in [image.convolve(&identity, 3), image.apply_kernel(&Kernel::identity())].iter() {
+        for y in 0..9 {
+            for x in 0..16 {
+                assert_eq!(result.get_pixel(x, y), image.get_pixel(x, y));
+            }
+        }
+    }
+}
+
+#[test]
+fn box_blur_spreads_a_dot_evenly() {
+    let blurred = white_dot(7, 7, 3, 3).apply_kernel(&Kernel::box_blur(3));
+    for y in 0..7 {
+        for x in 0..7 {
+            let inside = (x - 3i32).abs() <= 1 && (y - 3i32).abs() <= 1;
+            assert_eq!(red(&blurred, x, y), if inside { 28 } else { 0 }, "at {}, {}", x, y);
+        }
+    }
+}
+
+#[test]
+fn separable_and_full_kernels_agree() {
+    let image = white_dot(9, 9, 4, 2);
+    let separable = image.apply_kernel(&Kernel::gaussian5());
+    let full = image.convolve(Kernel::gaussian5().weights(), 5);
+    for y in 0..9 {
+        for x in 0..9 {
+            assert!((red(&separable, x, y) as i32 - red(&full, x, y) as i32).abs() <= 1);
+        }
+    }
+}
+
+#[test]
+fn edges_repeat_the_border() {
+    // Clamped reads at the corner land on the dot 4 times out of 9
+    let blurred = white_dot(4, 4, 0, 0).apply_kernel(&Kernel::box_blur(3));
+    assert_eq!(red(&blurred, 0, 0), 113);
+    assert_eq!(red(&blurred, 1, 1), 28);
+
+    // Kernels wider than the image only read its pixels
+    let single = white_dot(1, 1, 0, 0);
+    assert_eq!(red(&single.apply_kernel(&Kernel::gaussian5()), 0, 0), 255);
+    assert_eq!(red(&single.apply_kernel(&Kernel::edge_detect()), 0, 0), 0);
+}
+
+#[test]
+fn renderer_runs_passes_in_order() {
+    let mut renderer = Renderer::new(8, 8);
+    renderer.set_color(RgbaColor::new(1.0, 1.0, 1.0, 1.0));
+    renderer.line(4, 4, 4, 4);
+    let plain = renderer.to_image();
+
+    renderer.post_process(&[Kernel::box_blur(3), Kernel::identity()]);
+    let blurred = renderer.to_image();
+    assert_eq!(red(&blurred, 3, 5), 28);
+    assert_eq!(red(&blurred, 4, 4), 28);
+
+    renderer.post_process(&[]);
+    assert_eq!(red(&renderer.to_image(), 4, 4), red(&plain, 4, 4));
+}
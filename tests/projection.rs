@@ -0,0 +1,170 @@
+extern crate renderer;
+
+mod common;
+
+use renderer::{Projection,TgaImage,RgbaColor,Vec3f,Mat4};
+use common::{unlit,square,cube};
+
+// Smallest and largest x and y of pixels drawn
+fn extent(image: &TgaImage) -> Option<(i32, i32, i32, i32)> {
+    let mut extent: Option<(i32, i32, i32, i32)> = None;
+    for y in 0..image.height {
+        for x in 0..image.width {
+            if image.get_pixel(x, y).r > 0.5 {
+                extent = Some(match extent {
+                    Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                    None => (x, y, x, y)
+                });
+            }
+        }
+    }
+    extent
+}
+
+const PERSPECTIVE: Projection = Projection::Perspective { fov: 0.5, near: 0.1, far: 10.0 };
+
+#[test]
+fn orthographic_box_scales_the_view() {
+    let mut renderer = unlit(64, 64);
+    renderer.set_projection(Projection::Orthographic { left: -2.0, right: 2.0, bottom: -2.0, top: 2.0, near: -2.0, far: 2.0 });
+    renderer.draw_model(&cube(1.0), &Mat4::identity());
+    // Half of the 64 pixels, centered
+    assert_eq!(extent(&renderer.to_image()), Some((16, 16, 47, 47)));
+    assert_eq!(renderer.stats().fragments.passed, 32 * 32 * 2);
+
+    // Off center boxes move the view
+    let mut renderer = unlit(64, 64);
+    renderer.set_projection(Projection::Orthographic { left: 0.0, right: 4.0, bottom: -1.0, top: 3.0, near: -2.0, far: 2.0 });
+    renderer.draw_model(&cube(1.0), &Mat4::identity());
+    assert_eq!(extent(&renderer.to_image()), Some((0, 0, 15, 31)));
+}
+
+#[test]
+fn default_box_matches_the_default_projection() {
+    let transform = Mat4::rotation_x(0.4) * Mat4::rotation_y(0.7) * Mat4::scale(Vec3f::new(0.5, 0.5, 0.5));
+    let mut expected = unlit(64, 64);
+    expected.draw_model(&cube(1.0), &transform);
+
+    let mut renderer = unlit(64, 64);
+    renderer.set_projection(Projection::Orthographic { left: -1.0, right: 1.0, bottom: -1.0, top: 1.0, near: -1.0, far: 1.0 });
+    renderer.draw_model(&cube(1.0), &transform);
+    let diff = renderer.to_image().diff_with(&expected.to_image(), 0, false);
+    assert!(diff.is_match(), "{}", diff);
+    assert_eq!(renderer.depth_at(32, 32), expected.depth_at(32, 32));
+}
+
+#[test]
+fn perspective_shrinks_with_distance() {
+    // At z = 0 the field of view spans [-1, 1] as the default projection does
+    let mut renderer = unlit(64, 64);
+    renderer.set_projection(PERSPECTIVE);
+    renderer.draw_model(&square(0.5, 0.0), &Mat4::identity());
+    assert_eq!(extent(&renderer.to_image()), Some((16, 16, 47, 47)));
+
+    let width = |z: f32| {
+        let mut renderer = unlit(64, 64);
+        renderer.set_projection(PERSPECTIVE);
+        renderer.draw_model(&square(0.5, z), &Mat4::identity());
+        let (x0, _, x1, _) = extent(&renderer.to_image()).unwrap();
+        x1 - x0
+    };
+    assert!(width(0.5) > 33 && width(-0.5) < 30, "{} {}", width(0.5), width(-0.5));
+
+    // The cube's back edges converge, so its silhouette is the front face
+    let mut renderer = unlit(64, 64);
+    renderer.set_projection(PERSPECTIVE);
+    renderer.draw_model(&cube(1.0), &Mat4::scale(Vec3f::new(0.5, 0.5, 0.5)));
+    let front = extent(&renderer.to_image());
+    let mut renderer = unlit(64, 64);
+    renderer.set_projection(PERSPECTIVE);
+    renderer.draw_model(&square(0.5, 0.5), &Mat4::identity());
+    assert_eq!(front, extent(&renderer.to_image()));
+}
+
+#[test]
+fn closer_stays_in_front() {
+    for &projection in [PERSPECTIVE, Projection::Orthographic { left: -1.0, right: 1.0, bottom: -1.0, top: 1.0, near: -2.0, far: 2.0 }].iter() {
+        let mut renderer = unlit(64, 64);
+        renderer.set_projection(projection);
+        renderer.set_color(RgbaColor::new(1.0, 0.0, 0.0, 1.0));
+        renderer.draw_model(&square(0.3, 0.5), &Mat4::identity());
+        renderer.set_color(RgbaColor::new(0.0, 0.0, 1.0, 1.0));
+        renderer.draw_model(&square(0.8, -0.5), &Mat4::identity());
+
+        let image = renderer.to_image();
+        assert_eq!(image.get_pixel(32, 32), RgbaColor::new(1.0, 0.0, 0.0, 1.0), "{:?}", projection);
+        assert_eq!(image.get_pixel(32, 10), RgbaColor::new(0.0, 0.0, 1.0, 1.0), "{:?}", projection);
+        assert!(renderer.depth_at(32, 32) > renderer.depth_at(32, 10), "{:?}", projection);
+    }
+}
+
+#[test]
+#[should_panic(expected = "near must be positive")]
+fn perspective_needs_a_positive_near() {
+    Projection::Perspective { fov: 1.0, near: 0.0, far: 10.0 }.matrix();
+}
+
+#[test]
+fn four_views_in_quadrants() {
+    // 32x32 quadrants with a one pixel border between them
+    let mut renderer = unlit(65, 65);
+    let quadrants = [(0, 0), (33, 0), (0, 33), (33, 33)];
+    for (i, &(x, y)) in quadrants.iter().enumerate() {
+        renderer.set_viewport(x, y, 32, 32);
+        renderer.draw_model(&cube(0.6), &(Mat4::rotation_y(0.4 * i as f32) * Mat4::rotation_x(0.3)));
+        // Lines running off the viewport on every side
+        renderer.line(x - 40, y + 16, x + 70, y + 16);
+        renderer.line(x + 16, y - 40, x + 16, y + 70);
+    }
+
+    let image = renderer.to_image();
+    for &(x0, y0) in quadrants.iter() {
+        let drawn = (y0..y0 + 32).flat_map(|y| (x0..x0 + 32).map(move |x| (x, y)))
+            .filter(|&(x, y)| image.get_pixel(x, y).r > 0.0).count();
+        assert!(drawn > 200, "{} pixels in the quadrant at {}, {}", drawn, x0, y0);
+    }
+    for i in 0..65 {
+        assert_eq!(image.get_pixel(32, i).r, 0.0, "at 32, {}", i);
+        assert_eq!(image.get_pixel(i, 32).r, 0.0, "at {}, 32", i);
+    }
+}
+
+#[test]
+fn wide_viewports_keep_squares_square() {
+    for &(width, height) in [(48, 16), (16, 48), (32, 32)].iter() {
+        let mut renderer = unlit(64, 64);
+        renderer.set_viewport(8, 8, width, height);
+        renderer.draw_model(&square(0.5, 0.0), &Mat4::identity());
+        let (x0, y0, x1, y1) = extent(&renderer.to_image()).unwrap();
+        assert_eq!(x1 - x0, y1 - y0, "{}x{}", width, height);
+        assert_eq!(((x0 + x1 + 1) / 2, (y0 + y1 + 1) / 2), (8 + width / 2, 8 + height / 2), "{}x{}", width, height);
+    }
+}
+
+#[test]
+fn behind_the_camera_draws_nothing() {
+    // The camera sits at 1 / tan(0.25), about 3.9, on the z axis
+    let mut renderer = unlit(64, 64);
+    renderer.set_projection(PERSPECTIVE);
+    renderer.draw_model(&square(0.5, 6.0), &Mat4::identity());
+    assert_eq!(extent(&renderer.to_image()), None);
+    assert_eq!(renderer.stats().fragments.passed, 0);
+    assert_eq!(renderer.stats().clipped, 2);
+}
+
+#[test]
+fn ground_through_the_camera_stays_below_the_horizon() {
+    // Reaches from far behind the camera to past the far plane
+    let ground = Mat4::translation(Vec3f::new(0.0, -0.5, 0.0)) * Mat4::rotation_x(-std::f32::consts::PI / 2.0);
+    let mut renderer = unlit(64, 64);
+    renderer.set_projection(PERSPECTIVE);
+    renderer.draw_model(&square(50.0, 0.0), &ground);
+
+    let image = renderer.to_image();
+    let (x0, y0, x1, y1) = extent(&image).unwrap();
+    assert_eq!((x0, y0, x1), (0, 0, 63));
+    assert!(y1 < 32, "ground reaches row {}", y1);
+    for x in 0..64 {
+        assert!(image.get_pixel(x, 0).r > 0.5 && image.get_pixel(x, 20).r > 0.5, "hole at {}", x);
+    }
+}
@@ -0,0 +1,90 @@
+extern crate renderer;
+
+mod common;
+
+use std::rc::Rc;
+use renderer::{Scene,NodeMaterial,Texture,RgbaColor,Vec3f,Mat4};
+use common::{unlit,cube,solid};
+
+const GREEN: RgbaColor = RgbaColor { r: 0.0, g: 1.0, b: 0.0, a: 1.0 };
+
+fn is_green(c: RgbaColor) -> bool {
+    c.g > 0.9 && c.r == 0.0
+}
+
+fn is_red(c: RgbaColor) -> bool {
+    c.r > 0.9 && c.g == 0.0
+}
+
+// Pixels of the textured cube alone, where both overlap and of the colored
+// one alone, the textured one spanning x in [-0.4, 0.1] and the colored one
+// [-0.1, 0.4]
+const TEXTURED: (i32, i32) = (22, 32);
+const OVERLAP: (i32, i32) = (32, 32);
+const COLORED: (i32, i32) = (42, 32);
+
+#[test]
+fn nodes_draw_with_their_own_looks_and_share_depth() {
+    let model = Rc::new(cube(0.25));
+    let mut scene = Scene::new();
+    scene.clear_color = Some(RgbaColor::new(0.0, 0.0, 0.0, 1.0));
+    let green = scene.add_texture(Texture::new(solid(2, GREEN)));
+    scene.add(model.clone(), Mat4::translation(Vec3f::new(-0.15, 0.0, 0.4)), NodeMaterial { diffuse: Some(green), ..NodeMaterial::default() });
+    let colored = scene.add(model.clone(), Mat4::translation(Vec3f::new(0.15, 0.0, 0.0)),
+                            NodeMaterial { color: RgbaColor::new(1.0, 0.0, 0.0, 1.0), ..NodeMaterial::default() });
+
+    let mut renderer = unlit(64, 64);
+    renderer.draw_scene(&scene);
+    let before = renderer.to_image();
+    assert!(is_green(before.get_pixel(TEXTURED.0, TEXTURED.1)));
+    assert!(is_green(before.get_pixel(OVERLAP.0, OVERLAP.1)));
+    assert!(is_red(before.get_pixel(COLORED.0, COLORED.1)));
+
+    // Drawn first, the colored cube now comes out in front
+    scene.set_transform(colored, Mat4::translation(Vec3f::new(0.15, 0.0, 0.8)));
+    renderer.draw_scene(&scene);
+    let after = renderer.to_image();
+    assert!(is_green(after.get_pixel(TEXTURED.0, TEXTURED.1)));
+    assert!(is_red(after.get_pixel(OVERLAP.0, OVERLAP.1)));
+    assert!(is_red(after.get_pixel(COLORED.0, COLORED.1)));
+
+    // Moved up and away from the textured one, which stays where it was
+    scene.node_mut(colored).transform = Mat4::translation(Vec3f::new(0.6, 0.6, 0.0));
+    renderer.draw_scene(&scene);
+    let moved = renderer.to_image();
+    for y in 0..64 {
+        for x in 0..64 {
+            let (was, is) = (before.get_pixel(x, y), moved.get_pixel(x, y));
+            if is_green(was) {
+                assert_eq!(was, is, "at {}, {}", x, y);
+            }
+            if is_red(is) {
+                assert!(x > 40 && y > 40, "at {}, {}", x, y);
+            }
+        }
+    }
+    assert!(!is_red(moved.get_pixel(COLORED.0, COLORED.1)));
+    assert_eq!(Rc::strong_count(&model), 3);
+}
+
+#[test]
+fn shared_textures_and_untouched_settings() {
+    let model = Rc::new(cube(0.25));
+    let mut scene = Scene::new();
+    let green = scene.add_texture(Texture::new(solid(2, GREEN)));
+    let material = NodeMaterial { diffuse: Some(green), ..NodeMaterial::default() };
+    scene.add(model.clone(), Mat4::translation(Vec3f::new(-0.5, 0.0, 0.0)), material);
+    scene.add(model.clone(), Mat4::translation(Vec3f::new(0.5, 0.0, 0.0)), material);
+
+    let mut renderer = unlit(64, 64);
+    renderer.set_color(RgbaColor::new(0.0, 0.0, 1.0, 1.0));
+    renderer.draw_scene(&scene);
+    let image = renderer.to_image();
+    assert!(is_green(image.get_pixel(16, 32)) && is_green(image.get_pixel(48, 32)));
+    assert_eq!(image.get_pixel(32, 32).g, 0.0);
+
+    // Back to the renderer's own color and no texture
+    renderer.draw_model(&model, &Mat4::identity());
+    let c = renderer.to_image().get_pixel(32, 32);
+    assert!(c.b > 0.9 && c.g == 0.0, "{:?}", c);
+}
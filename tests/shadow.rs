@@ -0,0 +1,96 @@
+extern crate renderer;
+
+mod common;
+
+use renderer::{Renderer,Model,RgbaColor,Vec3f,Mat4};
+use renderer::model::Group;
+use common::square;
+
+// Lit head on, the light looking down -z like the camera so whatever is
+// behind the occluder is in its shadow
+fn renderer() -> Renderer {
+    let mut renderer = Renderer::new(32, 32);
+    renderer.enable_shadows(Mat4::identity(), 32, 0.01);
+    renderer.set_shadow_factor(0.0);
+    renderer
+}
+
+#[test]
+fn earlier_draws_cast_no_shadows() {
+    let floor = square(1.0, -0.5);
+    let mut unshadowed = renderer();
+    unshadowed.disable_shadows();
+    unshadowed.draw_model(&floor, &Mat4::identity());
+
+    // The occluder is drawn and then wiped, leaving the floor on its own
+    let mut renderer = renderer();
+    renderer.draw_model(&square(0.5, 0.5), &Mat4::identity());
+    renderer.clear_color(RgbaColor::new(0.0, 0.0, 0.0, 0.0));
+    renderer.clear_depth();
+    renderer.draw_model(&floor, &Mat4::identity());
+    assert_eq!(renderer.to_image().get_pixel(16, 16), unshadowed.to_image().get_pixel(16, 16));
+}
+
+// Floor plus a small square in front of it in one model, grouped as
+// "floor" and "occluder"
+fn floor_and_occluder() -> Model {
+    let mut vertices = square(1.0, -0.5).vertices;
+    vertices.extend(square(0.25, 0.5).vertices);
+    let faces = vec![
+        [0, -1, -1, 1, -1, -1, 2, -1, -1], [0, -1, -1, 2, -1, -1, 3, -1, -1],
+        [4, -1, -1, 5, -1, -1, 6, -1, -1], [4, -1, -1, 6, -1, -1, 7, -1, -1]
+    ];
+    let mut model = Model::new(vertices, Vec::new(), Vec::new(), faces).unwrap();
+    model.groups = vec![
+        Group { name: "floor".to_string(), faces: vec![0, 1] },
+        Group { name: "occluder".to_string(), faces: vec![2, 3] }
+    ];
+    model
+}
+
+// Sheared so the light comes in at 45 degrees, moving the occluder's
+// shadow one unit right of it on the floor
+fn sloped_renderer() -> Renderer {
+    let mut renderer = renderer();
+    renderer.enable_shadows(Mat4::new([[1.0, 0.0, 1.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]]), 64, 0.01);
+    renderer
+}
+
+#[test]
+fn occluders_shadow_what_is_behind_them() {
+    let mut renderer = sloped_renderer();
+    renderer.draw_model(&floor_and_occluder(), &Mat4::identity());
+    let image = renderer.to_image();
+    assert_eq!(image.get_pixel(30, 16).r, 0.0);
+    assert!(image.get_pixel(4, 16).r > 0.5);
+    assert!(image.get_pixel(16, 16).r > 0.5);
+}
+
+#[test]
+fn clear_forgets_the_shadow_map() {
+    let mut renderer = renderer();
+    renderer.draw_model(&square(0.5, 0.5), &Mat4::identity());
+    let stored = renderer.shadow_map_image().unwrap();
+    assert!(stored.get_pixel(16, 16).r > 0.0);
+
+    renderer.clear(RgbaColor::new(0.0, 0.0, 0.0, 0.0));
+    assert_eq!(renderer.shadow_map_image().unwrap().get_pixel(16, 16).r, 0.0);
+}
+
+#[test]
+fn groups_are_drawn_and_shadowed_like_whole_models() {
+    let model = floor_and_occluder();
+    let transform = Mat4::translation(Vec3f::new(0.25, 0.0, 0.0));
+    let mut whole = sloped_renderer();
+    whole.draw_model(&model, &transform);
+    let mut groups = sloped_renderer();
+    groups.draw_model_groups(&model, &transform, &["floor", "occluder"]);
+    assert!(groups.to_image().diff_with(&whole.to_image(), 0, false).is_match());
+
+    // Left out, the occluder neither shows nor casts its shadow
+    let mut floor = sloped_renderer();
+    floor.draw_model_groups(&model, &Mat4::identity(), &["floor"]);
+    let image = floor.to_image();
+    assert!(image.get_pixel(16, 16).r > 0.5);
+    assert!(image.get_pixel(30, 16).r > 0.5);
+}
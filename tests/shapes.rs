@@ -0,0 +1,106 @@
+extern crate renderer;
+
+use renderer::{Renderer,TgaImage,RgbaColor,Vec2f};
+
+const GREEN: RgbaColor = RgbaColor { r: 0.0, g: 1.0, b: 0.0, a: 1.0 };
+
+fn lit(image: &TgaImage) -> Vec<(i32, i32)> {
+    let mut pixels = Vec::new();
+    for y in 0..image.height {
+        for x in 0..image.width {
+            if image.get_pixel(x, y).g > 0.5 {
+                pixels.push((x, y));
+            }
+        }
+    }
+    pixels
+}
+
+#[test]
+fn rectangles_cover_exactly_their_pixels() {
+    let mut renderer = Renderer::new(16, 16);
+    renderer.fill_rect(4, 5, 6, 3, GREEN);
+    let pixels = lit(&renderer.to_image());
+    assert_eq!(pixels.len(), 18);
+    assert_eq!((pixels[0], pixels[17]), ((4, 5), (9, 7)));
+
+    renderer.clear(RgbaColor::new(0.0, 0.0, 0.0, 1.0));
+    renderer.draw_rect(4, 5, 6, 3, GREEN);
+    let pixels = lit(&renderer.to_image());
+    assert_eq!(pixels.len(), 6 + 6 + 2);
+    for &corner in [(4, 5), (9, 5), (4, 7), (9, 7)].iter() {
+        assert!(pixels.contains(&corner), "{:?}", corner);
+    }
+    assert!(!pixels.contains(&(5, 6)));
+
+    // Supersampled, still whole output pixels
+    let mut renderer = Renderer::new(16, 16);
+    renderer.set_supersampling(2).unwrap();
+    renderer.fill_rect(4, 5, 6, 3, GREEN);
+    let image = renderer.to_image();
+    assert_eq!(lit(&image).len(), 18);
+    assert_eq!(image.get_pixel(4, 5).g, 1.0);
+}
+
+#[test]
+fn circles_cover_about_pi_r_squared() {
+    for &r in [6, 12, 24].iter() {
+        let mut renderer = Renderer::new(64, 64);
+        renderer.fill_circle(32, 32, r, GREEN);
+        let pixels = lit(&renderer.to_image());
+        let area = std::f32::consts::PI * (r * r) as f32;
+        // Within a ring of pixels around the edge
+        assert!((pixels.len() as f32 - area).abs() < 2.0 * std::f32::consts::PI * r as f32, "r = {}: {} pixels", r, pixels.len());
+        assert!(pixels.contains(&(32 + r, 32)) && !pixels.contains(&(33 + r, 32)));
+        assert!(pixels.contains(&(32, 32 - r)) && !pixels.contains(&(32, 31 - r)));
+
+        // The outline is the fill's boundary
+        renderer.clear(RgbaColor::new(0.0, 0.0, 0.0, 1.0));
+        renderer.draw_circle(32, 32, r, GREEN);
+        let outline = lit(&renderer.to_image());
+        assert!(outline.iter().all(|p| pixels.contains(p)));
+        assert!(!outline.contains(&(32, 32)) && outline.contains(&(32 - r, 32)));
+        assert!(outline.len() as f32 > 5.0 * r as f32 && (outline.len() as f32) < 8.0 * r as f32);
+    }
+}
+
+#[test]
+fn concave_polygons_fill_their_inside() {
+    // An L, the notch at the top right stays empty
+    let l = [
+        Vec2f::new(2.0, 2.0), Vec2f::new(14.0, 2.0), Vec2f::new(14.0, 6.0),
+        Vec2f::new(6.0, 6.0), Vec2f::new(6.0, 14.0), Vec2f::new(2.0, 14.0)
+    ];
+    let mut renderer = Renderer::new(16, 16);
+    renderer.fill_polygon(&l, GREEN);
+    let pixels = lit(&renderer.to_image());
+    assert_eq!(pixels.len(), 12 * 4 + 4 * 8);
+    for y in 0..16 {
+        for x in 0..16 {
+            let inside = (x >= 2 && y >= 2) && ((x < 14 && y < 6) || (x < 6 && y < 14));
+            assert_eq!(pixels.contains(&(x, y)), inside, "at {}, {}", x, y);
+        }
+    }
+}
+
+#[test]
+fn shapes_outside_are_clipped_and_skip_the_depth_buffer() {
+    let mut renderer = Renderer::new(16, 16);
+    renderer.fill_rect(-10, -10, 12, 12, GREEN);
+    renderer.fill_rect(i32::MAX - 1, 0, i32::MAX, 4, GREEN);
+    renderer.draw_rect(i32::MIN, i32::MIN, i32::MAX, i32::MAX, GREEN);
+    renderer.fill_circle(16, 16, 3, GREEN);
+    renderer.draw_circle(-1000, 8, 4, GREEN);
+    renderer.fill_circle(i32::MAX, 8, 4, GREEN);
+    renderer.fill_polygon(&[Vec2f::new(-1e30, -1e30), Vec2f::new(-1e30, 1e30), Vec2f::new(-1e29, 8.0)], GREEN);
+    renderer.fill_polygon(&[Vec2f::new(0.0, 0.0), Vec2f::new(f32::NAN, 1.0), Vec2f::new(1.0, 1.0)], GREEN);
+
+    let pixels = lit(&renderer.to_image());
+    assert!(pixels.contains(&(0, 0)) && pixels.contains(&(15, 15)));
+    assert!(!pixels.contains(&(15, 0)) && !pixels.contains(&(0, 15)));
+    for y in 0..16 {
+        for x in 0..16 {
+            assert_eq!(renderer.depth_at(x, y), None);
+        }
+    }
+}
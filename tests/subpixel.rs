@@ -0,0 +1,154 @@
+extern crate renderer;
+
+use renderer::{Renderer,Vertex,RgbaColor,Vec2f,Vec3f};
+use renderer::framebuffer::{BlendMode,DepthFunc};
+
+fn vertex(x: f32, y: f32) -> Vertex {
+    Vertex {
+        p: Vec3f::new(x, y, 0.5),
+        t: Vec2f::new(0.0, 0.0),
+        i: 1.0,
+        n: Vec3f::new(0.0, 0.0, 1.0),
+        world: Vec3f::new(0.0, 0.0, 0.0),
+        c: RgbaColor::new(0.25, 0.25, 0.25, 1.0)
+    }
+}
+
+// Pixels covered by a right triangle with legs of width and 24
+fn coverage(width: f32) -> usize {
+    let mut renderer = Renderer::new(32, 32);
+    renderer.triangle(vertex(2.0, 2.0), vertex(2.0 + width, 2.0), vertex(2.0, 26.0));
+    renderer.stats().fragments.passed
+}
+
+#[test]
+fn coverage_follows_fractional_corners() {
+    // Widening by 1/32 of a pixel at a time, corners snapped to whole pixels
+    // would jump once at the end
+    let counts: Vec<usize> = (0..33).map(|i| coverage(12.0 + i as f32 / 32.0)).collect();
+    let mut distinct = counts.clone();
+    distinct.dedup();
+    assert!(distinct.len() >= 8, "{:?}", counts);
+    assert!(counts.windows(2).all(|w| w[0] <= w[1]), "{:?}", counts);
+}
+
+#[test]
+fn shared_edges_are_covered_once() {
+    // A quad split along a diagonal with fractional corners, added up so
+    // overlaps and gaps both show
+    let mut renderer = Renderer::new(32, 32);
+    renderer.set_blend_mode(BlendMode::Additive);
+    renderer.set_depth_test(DepthFunc::Always);
+    let corners = [vertex(3.3, 2.7), vertex(28.1, 5.45), vertex(25.6, 29.9), vertex(1.85, 24.2)];
+    renderer.triangle(corners[0], corners[1], corners[2]);
+    renderer.triangle(corners[0], corners[2], corners[3]);
+
+    let image = renderer.to_image();
+    let once = image.get_pixel(15, 15).r;
+    assert!(once > 0.0 && once < 0.5);
+    for y in 0..32 {
+        for x in 0..32 {
+            let r = image.get_pixel(x, y).r;
+            assert!(r == 0.0 || r == once, "{} at {}, {}", r, x, y);
+        }
+    }
+}
+
+#[test]
+fn far_corners_are_skipped() {
+    let mut renderer = Renderer::new(32, 32);
+    renderer.triangle(vertex(0.0, 0.0), vertex(1e9, 0.0), vertex(0.0, 16.0));
+    renderer.triangle(vertex(0.0, 0.0), vertex(16.0, 0.0), vertex(0.0, f32::NAN));
+    assert_eq!(renderer.stats().clipped, 2);
+    assert_eq!(renderer.stats().fragments.passed, 0);
+}
+
+#[test]
+fn fans_leave_no_gaps() {
+    // Shared edges at every angle around an off-center hub
+    let (cx, cy, r, n) = (32.3, 31.7, 28.0, 17);
+    let rim = |i: usize| {
+        let a = i as f32 * 2.0 * std::f32::consts::PI / n as f32;
+        vertex(cx + r * a.cos(), cy + r * a.sin())
+    };
+    let mut renderer = Renderer::new(64, 64);
+    for i in 0..n {
+        renderer.triangle(vertex(cx, cy), rim(i), rim(i + 1));
+    }
+
+    // Everything inside the inscribed circle, less a pixel
+    let inner = r * (std::f32::consts::PI / n as f32).cos() - 1.0;
+    let image = renderer.to_image();
+    for y in 0..64 {
+        for x in 0..64 {
+            let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+            if dx * dx + dy * dy < inner * inner {
+                assert!(image.get_pixel(x, y).r > 0.0, "gap at {}, {}", x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn off_screen_corners_match_a_clipped_oracle() {
+    let corners = [(-100.0, -100.0), (900.0, 400.0), (400.0, 900.0)];
+    let mut renderer = Renderer::new(800, 800);
+    renderer.triangle(vertex(corners[0].0, corners[0].1), vertex(corners[1].0, corners[1].1), vertex(corners[2].0, corners[2].1));
+    let image = renderer.to_image();
+
+    // Signed distances of a pixel center from the counter-clockwise edges
+    let distances = |x: i32, y: i32| {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        (0..3).map(|i| {
+            let (a, b) = (corners[i], corners[(i + 1) % 3]);
+            let (dx, dy): (f32, f32) = (b.0 - a.0, b.1 - a.1);
+            (dx * (py - a.1) - dy * (px - a.0)) / (dx * dx + dy * dy).sqrt()
+        }).collect::<Vec<f32>>()
+    };
+
+    let mut inside = 0;
+    for y in 0..800 {
+        for x in 0..800 {
+            let d = distances(x, y);
+            let lit = image.get_pixel(x, y).r > 0.0;
+            // Pixel centers right on an edge go either way
+            if d.iter().all(|&d| d.abs() > 1e-3) {
+                assert_eq!(lit, d.iter().all(|&d| d > 0.0), "at {}, {}", x, y);
+            }
+            if lit { inside += 1; }
+        }
+    }
+    assert_eq!(renderer.stats().fragments.passed, inside);
+    assert!(renderer.depth_at(799, 799).is_none() && renderer.depth_at(0, 0).is_some());
+}
+
+#[test]
+fn tiled_grid_adds_up_to_one_step() {
+    // 10x10 cells of two triangles over the whole 200x200 image, inner grid
+    // points nudged off the pixel grid so edges run at all sorts of angles
+    let point = |i: usize, j: usize| {
+        let inner = |k: usize| if k > 0 && k < 10 { ((i * 7 + j * 13 + k) % 11) as f32 * 0.7 - 3.5 } else { 0.0 };
+        let (x, y) = (i as f32 * 20.0 + inner(i), j as f32 * 20.0 + inner(j) * 0.9);
+        Vertex { c: RgbaColor::new(1.0 / 255.0, 1.0 / 255.0, 1.0 / 255.0, 1.0), ..vertex(x, y) }
+    };
+
+    let mut renderer = Renderer::new(200, 200);
+    renderer.set_blend_mode(BlendMode::Additive);
+    renderer.set_depth_test(DepthFunc::Always);
+    for j in 0..10 {
+        for i in 0..10 {
+            renderer.triangle(point(i, j), point(i + 1, j), point(i + 1, j + 1));
+            renderer.triangle(point(i, j), point(i + 1, j + 1), point(i, j + 1));
+        }
+    }
+
+    let image = renderer.to_image();
+    let step = RgbaColor::new_from_u8(1, 1, 1, 255);
+    for y in 0..200 {
+        for x in 0..200 {
+            let c = image.get_pixel(x, y);
+            assert!(c.r == step.r && c.g == step.g && c.b == step.b, "{:?} at {}, {}", c, x, y);
+        }
+    }
+    assert_eq!(renderer.stats().fragments.passed, 200 * 200);
+}
@@ -0,0 +1,126 @@
+extern crate renderer;
+
+mod common;
+
+use renderer::{Renderer,RenderTarget,Model,RgbaColor,Vec3f,Mat4};
+use renderer::test_scene;
+use common::{unlit,square};
+
+fn triangle() -> Model {
+    let vertices = vec![Vec3f::new(-0.8, -0.6, 0.0), Vec3f::new(0.8, -0.6, 0.0), Vec3f::new(0.0, 0.8, 0.0)];
+    Model::new(vertices, Vec::new(), Vec::new(), vec![[0, -1, -1, 1, -1, -1, 2, -1, -1]]).unwrap()
+}
+
+#[test]
+fn render_to_texture() {
+    let mut renderer = unlit(800, 800);
+
+    // Red triangle on blue, turned a little
+    let offscreen = renderer.with_target(RenderTarget::new(256, 256), |r| {
+        r.clear(RgbaColor::new(0.0, 0.0, 1.0, 1.0));
+        r.set_color(RgbaColor::new(1.0, 0.0, 0.0, 1.0));
+        r.draw_model(&triangle(), &Mat4::rotation_z(0.4));
+    });
+    assert_eq!(renderer.output_size(), (800, 800));
+    assert_eq!((offscreen.width(), offscreen.height()), (256, 256));
+
+    renderer.set_color(RgbaColor::new(1.0, 1.0, 1.0, 1.0));
+    renderer.set_diffuse(offscreen.to_texture(true));
+    renderer.draw_model(&square(0.5, 0.0), &Mat4::identity());
+    let image = renderer.to_image();
+
+    let (mut red, mut blue) = (0, 0);
+    for y in 200..600 {
+        for x in 200..600 {
+            let c = image.get_pixel(x, y);
+            if c.r > 0.9 && c.b < 0.1 { red += 1; }
+            if c.b > 0.9 && c.r < 0.1 { blue += 1; }
+        }
+    }
+    assert!(red > 40000 && blue > 40000, "{} red, {} blue", red, blue);
+    // Just below the triangle's top corner, turned to (-0.31, 0.74) in the
+    // texture's NDC, which the quad maps to x and y in [200, 600]
+    let top = image.get_pixel(340, 528);
+    assert!(top.r > 0.9, "{:?}", top);
+    assert_eq!(image.get_pixel(100, 100).b, 0.0);
+}
+
+#[test]
+fn swapped_targets_match_the_default_one() {
+    let expected = test_scene::render(64, 64, 1);
+
+    let mut renderer = Renderer::new(16, 16);
+    let own = renderer.set_target(RenderTarget::new(64, 64));
+    assert_eq!(renderer.output_size(), (64, 64));
+    test_scene::draw(&mut renderer);
+    assert!(renderer.to_image().diff_with(&expected, 0, false).is_match());
+
+    let drawn = renderer.set_target(own);
+    assert_eq!(renderer.output_size(), (16, 16));
+    assert!(drawn.to_image(true, false).diff_with(&expected, 0, false).is_match());
+}
+
+#[test]
+fn supersampled_targets() {
+    let mut reference = Renderer::new(32, 32);
+    reference.set_supersampling(2).unwrap();
+    test_scene::draw(&mut reference);
+
+    let mut renderer = Renderer::new(8, 8);
+    renderer.set_viewport(2, 2, 4, 4);
+    let target = renderer.with_target(RenderTarget::try_new(32, 32, 2).unwrap(), test_scene::draw);
+    assert_eq!((target.supersampling(), target.framebuffer.width), (2, 64));
+    assert!(target.to_image(true, false).diff_with(&reference.to_image(), 0, false).is_match());
+    assert!(RenderTarget::try_new(1 << 20, 1 << 20, 4).is_err());
+}
+
+// 100000 squared overflows an i32 pixel count
+#[test]
+#[should_panic(expected = "more than the")]
+fn oversized_renderers_panic_with_the_size() {
+    Renderer::new(100000, 100000);
+}
+
+// Distance from p to the segment a b
+fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let t = (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+    let (ex, ey) = (a.0 + t * dx - p.0, a.1 + t * dy - p.1);
+    (ex * ex + ey * ey).sqrt()
+}
+
+#[test]
+fn supersampled_edges_are_partly_covered() {
+    let partial = |factor: u32| {
+        let mut renderer = unlit(32, 32);
+        if factor > 1 {
+            renderer.set_supersampling(factor).unwrap();
+        }
+        renderer.clear(RgbaColor::new(0.0, 0.0, 0.0, 1.0));
+        renderer.draw_model(&triangle(), &Mat4::identity());
+        let image = renderer.to_image();
+        let mut pixels = Vec::new();
+        for y in 0..32 {
+            for x in 0..32 {
+                let r = image.get_pixel(x, y).r;
+                if r > 0.0 && r < 1.0 {
+                    pixels.push((x, y));
+                }
+            }
+        }
+        pixels
+    };
+    assert_eq!(partial(1), vec![]);
+
+    // triangle() in pixels of the 32x32 image
+    let corners = [(3.2, 6.4), (28.8, 6.4), (16.0, 28.8)];
+    for &factor in [2, 4].iter() {
+        let pixels = partial(factor);
+        assert!(pixels.len() > 20, "{}x: {} partly covered pixels", factor, pixels.len());
+        for &(x, y) in pixels.iter() {
+            let p = (x as f32 + 0.5, y as f32 + 0.5);
+            let d = (0..3).map(|i| segment_distance(p, corners[i], corners[(i + 1) % 3])).fold(f32::INFINITY, f32::min);
+            assert!(d < 1.0, "{}x: {}, {} is {} from the edges", factor, x, y, d);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+extern crate renderer;
+
+use renderer::{Renderer,TgaImage,RgbaColor};
+use renderer::text::{self,GLYPH_WIDTH,GLYPH_HEIGHT};
+
+const GREEN: RgbaColor = RgbaColor { r: 0.0, g: 1.0, b: 0.0, a: 1.0 };
+
+fn is_lit(image: &TgaImage, x: i32, y: i32) -> bool {
+    image.get_pixel(x, y).g > 0.5
+}
+
+// Whether the scale x scale blocks with their top left at x, y match the
+// glyph's bitmap, top row first
+fn shows(image: &TgaImage, x: i32, y: i32, glyph: &[u8; 8], scale: i32) -> bool {
+    for row in 0..GLYPH_HEIGHT * scale {
+        for column in 0..GLYPH_WIDTH * scale {
+            let set = glyph[(row / scale) as usize] & (1 << (column / scale)) != 0;
+            if is_lit(image, x + column, y - row) != set {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn lit_count(image: &TgaImage) -> usize {
+    let mut count = 0;
+    for y in 0..image.height {
+        for x in 0..image.width {
+            if is_lit(image, x, y) {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+#[test]
+fn glyphs_come_out_as_in_the_font() {
+    let a = text::glyph('A');
+    assert_eq!(a, &[0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00]);
+
+    let mut renderer = Renderer::new(32, 32);
+    renderer.draw_text(3, 20, "A", GREEN, 1);
+    let image = renderer.to_image();
+    assert!(shows(&image, 3, 20, a, 1));
+    let bits: u32 = a.iter().map(|row| row.count_ones()).sum();
+    assert_eq!(lit_count(&image), bits as usize);
+
+    let mut renderer = Renderer::new(32, 32);
+    renderer.draw_text(5, 25, "A", GREEN, 3);
+    let image = renderer.to_image();
+    assert!(shows(&image, 5, 25, a, 3));
+    assert_eq!(lit_count(&image), 9 * bits as usize);
+}
+
+#[test]
+fn lines_and_unknown_characters() {
+    let mut renderer = Renderer::new(32, 32);
+    renderer.draw_text(0, 31, "1A\n\u{e9}\t", GREEN, 1);
+    let image = renderer.to_image();
+    assert!(shows(&image, 0, 31, text::glyph('1'), 1));
+    assert!(shows(&image, 8, 31, text::glyph('A'), 1));
+    assert!(shows(&image, 0, 23, &text::REPLACEMENT, 1));
+    assert!(shows(&image, 8, 23, &text::REPLACEMENT, 1));
+    assert_eq!(text::glyph(' '), &[0; 8]);
+}
+
+#[test]
+fn text_is_clipped() {
+    let mut renderer = Renderer::new(16, 16);
+    renderer.draw_text(-4, 3, "AB", GREEN, 1);
+    renderer.draw_text(i32::MAX - 4, i32::MIN + 2, "far\naway", GREEN, 1000);
+    renderer.draw_text(12, 20, "AB\nAB\nAB", GREEN, 2);
+    let image = renderer.to_image();
+    assert!(is_lit(&image, 0, 0) || is_lit(&image, 1, 0));
+    assert!(lit_count(&image) > 0);
+}
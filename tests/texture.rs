@@ -0,0 +1,90 @@
+extern crate renderer;
+
+use renderer::tga::{Filter,Wrap};
+use renderer::{Texture,TgaImage,RgbaColor};
+
+fn gray(v: u8) -> RgbaColor {
+    RgbaColor::new_from_u8(v, v, v, 255)
+}
+
+// Black at the bottom left and top right, white elsewhere
+fn checkerboard() -> TgaImage {
+    let mut image = TgaImage::new(2, 2);
+    for y in 0..2 {
+        for x in 0..2 {
+            image.set_pixel(x, y, &gray(if (x + y) % 2 == 0 { 0 } else { 255 }));
+        }
+    }
+    image
+}
+
+#[test]
+fn checkerboard_samples_exactly() {
+    let image = checkerboard();
+    let (black, white) = (gray(0), gray(255));
+    let half = RgbaColor::new(0.5, 0.5, 0.5, 1.0);
+
+    // Clamped corners stay on the corner texel however far out the UVs are
+    for &(u, v, expected) in [(-0.5, -0.5, black), (1.5, -3.0, white), (-2.0, 1.0, white), (7.0, 7.0, black)].iter() {
+        assert_eq!(image.sample(u, v, Filter::Bilinear, Wrap::ClampToEdge), expected, "at {}, {}", u, v);
+        assert_eq!(image.sample(u, v, Filter::Nearest, Wrap::ClampToEdge), expected, "at {}, {}", u, v);
+    }
+
+    // Repeat wraps 1.25 back to 0.25, and exactly 1 to 0
+    assert_eq!(image.sample(1.25, 0.25, Filter::Nearest, Wrap::Repeat), black);
+    assert_eq!(image.sample(1.0, 0.25, Filter::Nearest, Wrap::Repeat), black);
+    assert_eq!(image.sample(-0.25, 0.25, Filter::Nearest, Wrap::Repeat), white);
+    assert_eq!(image.sample(1.25, 0.25, Filter::Bilinear, Wrap::Repeat), black);
+
+    // Halfway between texel centers is an even blend of the two
+    assert!(image.sample(0.5, 0.25, Filter::Bilinear, Wrap::ClampToEdge).approx_eq(&half, 1e-6));
+    assert!(image.sample(0.25, 0.5, Filter::Bilinear, Wrap::ClampToEdge).approx_eq(&half, 1e-6));
+    assert!(image.sample(0.5, 0.5, Filter::Bilinear, Wrap::ClampToEdge).approx_eq(&half, 1e-6));
+
+    // A quarter of the way from black to white
+    let quarter = image.sample(0.375, 0.25, Filter::Bilinear, Wrap::ClampToEdge);
+    assert!(quarter.approx_eq(&RgbaColor::new(0.25, 0.25, 0.25, 1.0), 1e-6), "{:?}", quarter);
+}
+
+#[test]
+fn checkerboard_mips_down_to_mid_gray() {
+    // 8x8 squares of one texel each
+    let mut image = TgaImage::new(8, 8);
+    for y in 0..8 {
+        for x in 0..8 {
+            image.set_pixel(x, y, &gray(if (x + y) % 2 == 0 { 0 } else { 255 }));
+        }
+    }
+
+    let mut texture = Texture::new(image);
+    texture.generate_mipmaps();
+    assert_eq!(texture.level_count(), 4);
+    let sizes: Vec<(i32, i32)> = (0..4).map(|l| (texture.level(l).width, texture.level(l).height)).collect();
+    assert_eq!(sizes, vec![(8, 8), (4, 4), (2, 2), (1, 1)]);
+
+    // Averages round to nearest, 127.5 up to 128
+    for level in 1..4 {
+        let mip = texture.level(level);
+        for y in 0..mip.height {
+            for x in 0..mip.width {
+                assert_eq!(mip.get_pixel(x, y), gray(128), "level {} at {}, {}", level, x, y);
+            }
+        }
+    }
+    assert_eq!(texture.sample(0.3, 0.7, 3, Filter::Bilinear, Wrap::Repeat), gray(128));
+
+    // Level 0 samples like the image itself
+    let checker = checkerboard();
+    let texture = Texture::new(checkerboard());
+    for &(u, v) in [(0.1, 0.1), (0.6, 0.2), (0.5, 0.5), (0.9, 0.75)].iter() {
+        assert_eq!(texture.sample(u, v, 0, Filter::Bilinear, Wrap::Repeat), checker.sample(u, v, Filter::Bilinear, Wrap::Repeat));
+    }
+}
+
+#[test]
+fn odd_sizes_round_down_to_one_texel() {
+    let mut texture = Texture::new(TgaImage::new(5, 3));
+    texture.generate_mipmaps();
+    let sizes: Vec<(i32, i32)> = (0..texture.level_count()).map(|l| (texture.level(l).width, texture.level(l).height)).collect();
+    assert_eq!(sizes, vec![(5, 3), (2, 1), (1, 1)]);
+}
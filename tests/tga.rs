@@ -0,0 +1,485 @@
+extern crate renderer;
+
+use std::fs::File;
+use std::io::{self,Cursor,Read,Write};
+use std::path::Path;
+use renderer::tga::{TgaError,TgaWriteOptions};
+use renderer::{Renderer,Vertex,TgaImage,RgbaColor,Vec2f,Vec3f};
+
+// Every pixel a different color, exact in 8 bits
+fn pattern(width: i32, height: i32) -> TgaImage {
+    let mut image = TgaImage::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            image.set_pixel(x, y, &RgbaColor::new_from_u8((x * 30) as u8, (y * 30) as u8, (x * 7 + y * 11) as u8, 255));
+        }
+    }
+    image
+}
+
+fn assert_same(a: &TgaImage, b: &TgaImage) {
+    assert_eq!((a.width, a.height), (b.width, b.height));
+    for y in 0..a.height {
+        for x in 0..a.width {
+            assert_eq!(a.get_pixel(x, y), b.get_pixel(x, y), "at {}, {}", x, y);
+        }
+    }
+}
+
+fn encode(image: &TgaImage, options: TgaWriteOptions) -> Vec<u8> {
+    let mut data = Vec::new();
+    image.encode_with_options(&mut data, options).unwrap();
+    data
+}
+
+// White, in pixel coordinates
+fn vertex(x: f32, y: f32) -> Vertex {
+    Vertex {
+        p: Vec3f::new(x, y, 0.0),
+        t: Vec2f::new(0.0, 0.0),
+        i: 1.0,
+        n: Vec3f::new(0.0, 0.0, 1.0),
+        world: Vec3f::new(0.0, 0.0, 0.0),
+        c: RgbaColor::new(1.0, 1.0, 1.0, 1.0)
+    }
+}
+
+// Uncompressed true color header for a width x height image
+fn header(data_type: u8, width: u16, height: u16, bpp: u8) -> Vec<u8> {
+    vec![0, 0, data_type, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+         width as u8, (width >> 8) as u8, height as u8, (height >> 8) as u8, bpp, 0]
+}
+
+#[test]
+fn oversized_headers_are_rejected() {
+    match TgaImage::decode(Cursor::new(header(2, 65535, 65535, 24))) {
+        Err(TgaError::InvalidDimensions { width: 65535, height: 65535 }) => {},
+        other => panic!("decoded as {:?}", other.map(|i| (i.width, i.height)))
+    }
+}
+
+#[test]
+fn tga_headers_larger_than_the_data_are_rejected() {
+    // About 2G pixels that fit the pixel count, with a few bytes of data
+    for &data_type in [2, 10].iter() {
+        let mut data = header(data_type, 46340, 46340, 24);
+        data.extend(&[0x85, 1, 2, 3]);
+        match TgaImage::decode(Cursor::new(data)) {
+            Err(TgaError::UnexpectedEof) => {},
+            other => panic!("decoded as {:?}", other.map(|i| (i.width, i.height)))
+        }
+    }
+}
+
+// Counts what's written without keeping it
+struct CountingSink {
+    bytes: usize
+}
+
+impl Write for CountingSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct FailingSink;
+
+impl Write for FailingSink {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("disk full"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn uncompressed_encodes_write_header_and_every_pixel() {
+    let image = TgaImage::new(2000, 1500);
+    for &(alpha, bytes_per_pixel) in [(false, 3), (true, 4)].iter() {
+        let mut sink = CountingSink { bytes: 0 };
+        image.encode_with_options(&mut sink, TgaWriteOptions { rle: false, alpha: alpha }).unwrap();
+        assert_eq!(sink.bytes, 18 + 2000 * 1500 * bytes_per_pixel);
+    }
+    let mut sink = CountingSink { bytes: 0 };
+    image.encode_grayscale(&mut sink).unwrap();
+    assert_eq!(sink.bytes, 18 + 2000 * 1500);
+}
+
+#[test]
+fn write_errors_reach_the_caller() {
+    let image = pattern(4, 4);
+    for &rle in [false, true].iter() {
+        match image.encode_with_options(FailingSink, TgaWriteOptions { rle: rle, alpha: false }) {
+            Err(TgaError::Io(ref e)) if e.to_string() == "disk full" => {},
+            other => panic!("encoded as {:?}", other)
+        }
+    }
+    match image.encode_grayscale(FailingSink) {
+        Err(TgaError::Io(ref e)) if e.to_string() == "disk full" => {},
+        other => panic!("encoded as {:?}", other)
+    }
+}
+
+#[test]
+fn sizes_past_16_bits_are_not_encoded() {
+    for &(width, height) in [(65536, 1), (1, 65536)].iter() {
+        let image = TgaImage::new(width, height);
+        match image.encode_with_options(CountingSink { bytes: 0 }, TgaWriteOptions { rle: false, alpha: false }) {
+            Err(TgaError::InvalidDimensions { width: w, height: h }) => assert_eq!((w, h), (width, height)),
+            other => panic!("encoded as {:?}", other)
+        }
+        match image.encode_grayscale(CountingSink { bytes: 0 }) {
+            Err(TgaError::InvalidDimensions { .. }) => {},
+            other => panic!("encoded as {:?}", other)
+        }
+    }
+    let mut sink = CountingSink { bytes: 0 };
+    TgaImage::new(65535, 1).encode_grayscale(&mut sink).unwrap();
+    assert_eq!(sink.bytes, 18 + 65535);
+}
+
+#[test]
+fn ppm_headers_larger_than_the_data_are_rejected() {
+    match TgaImage::read_ppm(Cursor::new(b"P6\n100000 100000\n255\n".to_vec())) {
+        Err(TgaError::InvalidDimensions { width: 100000, height: 100000 }) => {},
+        other => panic!("read as {:?}", other.map(|i| (i.width, i.height)))
+    }
+    match TgaImage::read_ppm(Cursor::new(b"P6\n1000 1000\n255\n\x00\x00\x00".to_vec())) {
+        Err(TgaError::UnexpectedEof) => {},
+        other => panic!("read as {:?}", other.map(|i| (i.width, i.height)))
+    }
+}
+
+#[test]
+fn non_square_images_index_rows_by_width() {
+    for &(width, height) in [(4, 7), (7, 4)].iter() {
+        let image = pattern(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                assert_eq!(image.get_pixel(x, y), RgbaColor::new_from_u8((x * 30) as u8, (y * 30) as u8, (x * 7 + y * 11) as u8, 255));
+                assert_eq!(image.index(x, y), Some((x + width * y) as usize));
+            }
+        }
+        assert_eq!(image.index(width, 0), None);
+        assert_eq!(image.index(0, height), None);
+    }
+}
+
+#[test]
+fn non_square_render() {
+    // Lower right half of a 40x10 image
+    let mut renderer = Renderer::new(40, 10);
+    renderer.triangle(vertex(0.0, 0.0), vertex(40.0, 0.0), vertex(40.0, 10.0));
+    let image = renderer.to_image();
+    for &(x, y, lit) in [(4, 0, true), (38, 8, true), (39, 9, true), (20, 2, true), (2, 8, false), (20, 8, false), (0, 9, false)].iter() {
+        assert_eq!(image.get_pixel(x, y).r > 0.5, lit, "at {}, {}", x, y);
+        assert_eq!(renderer.depth_at(x, y).is_some(), lit, "depth at {}, {}", x, y);
+    }
+}
+
+#[test]
+fn uncompressed_images_read_back() {
+    let image = pattern(5, 3);
+    let data = encode(&image, TgaWriteOptions::default());
+    assert_eq!((data[2], data[16], data.len()), (2, 24, 18 + 5 * 3 * 3));
+    assert_same(&TgaImage::decode(Cursor::new(data)).unwrap(), &image);
+
+    let path = std::env::temp_dir().join("renderer_tga_round_trip.tga");
+    image.write_to_file(&path).unwrap();
+    assert_same(&TgaImage::new_from_file(&path).unwrap(), &image);
+
+    match TgaImage::decode(Cursor::new(header(7, 2, 2, 24))) {
+        Err(e @ TgaError::UnsupportedFormat { data_type: 7, bpp: 24 }) => assert!(e.to_string().contains("type 7")),
+        other => panic!("decoded as {:?}", other.map(|i| (i.width, i.height)))
+    }
+}
+
+// Pixel count of each RLE packet in a single row image, negative for raw packets
+fn packets(data: &[u8], bytes_per_pixel: usize) -> Vec<i32> {
+    let mut result = Vec::new();
+    let mut i = 18;
+    while i < data.len() {
+        let count = (data[i] & 127) as i32 + 1;
+        if data[i] & 128 > 0 {
+            result.push(count);
+            i += 1 + bytes_per_pixel;
+        } else {
+            result.push(-count);
+            i += 1 + count as usize * bytes_per_pixel;
+        }
+    }
+    result
+}
+
+#[test]
+fn rle_packets_split_at_128_pixels() {
+    let rle = TgaWriteOptions { rle: true, alpha: false };
+    let red = RgbaColor::new(1.0, 0.0, 0.0, 1.0);
+
+    let mut long = TgaImage::new(300, 1);
+    for x in 0..300 { long.set_pixel(x, 0, &red); }
+    let data = encode(&long, rle);
+    assert_eq!(data[2], 10);
+    assert_eq!(packets(&data, 3), vec![128, 128, 44]);
+    assert_same(&TgaImage::decode(Cursor::new(data)).unwrap(), &long);
+
+    let distinct = pattern(200, 1);
+    let data = encode(&distinct, rle);
+    assert_eq!(packets(&data, 3), vec![-128, -72]);
+    assert_same(&TgaImage::decode(Cursor::new(data)).unwrap(), &distinct);
+
+    // A run just past the limit leaves a short run, then raw pixels
+    let mut crossing = pattern(133, 1);
+    for x in 0..130 { crossing.set_pixel(x, 0, &red); }
+    let data = encode(&crossing, rle);
+    assert_eq!(packets(&data, 3), vec![128, 2, -3]);
+    assert_same(&TgaImage::decode(Cursor::new(data)).unwrap(), &crossing);
+
+    let image = pattern(5, 3);
+    assert_same(&TgaImage::decode(Cursor::new(encode(&image, rle))).unwrap(), &image);
+}
+
+#[test]
+fn alpha_survives_32_bit_round_trip() {
+    let mut image = TgaImage::new(3, 1);
+    for (x, &a) in [0u8, 127, 255].iter().enumerate() {
+        image.set_pixel(x as i32, 0, &RgbaColor::new_from_u8(10, 20, 30, a));
+    }
+
+    for &rle in [false, true].iter() {
+        let data = encode(&image, TgaWriteOptions { rle: rle, alpha: true });
+        assert_eq!((data[16], data[17] & 0x0F), (32, 8));
+        let decoded = TgaImage::decode(Cursor::new(data)).unwrap();
+        assert_same(&decoded, &image);
+        assert_eq!(decoded.get_pixel(1, 0).to_u32() & 0xFF, 127);
+    }
+
+    let data = encode(&image, TgaWriteOptions::default());
+    assert_eq!(data[16], 24);
+    assert!(TgaImage::decode(Cursor::new(data)).unwrap().is_opaque());
+}
+
+#[test]
+fn origin_bits_pick_the_stored_corner() {
+    // Bottom left, bottom right, top left, top right as BGR
+    let corners = [[0u8, 0, 255], [0, 255, 0], [255, 0, 0], [255, 255, 255]];
+    let expected = [(0, 0), (1, 0), (0, 1), (1, 1)];
+
+    // Storage order of the corners for each descriptor
+    let layouts = [(0x00, [0, 1, 2, 3]), (0x10, [1, 0, 3, 2]), (0x20, [2, 3, 0, 1]), (0x30, [3, 2, 1, 0])];
+    for &(desc, order) in layouts.iter() {
+        let mut data = header(2, 2, 2, 24);
+        data[17] = desc;
+        for &i in order.iter() { data.extend(&corners[i]); }
+
+        let image = TgaImage::decode(Cursor::new(data)).unwrap();
+        for (c, &(x, y)) in corners.iter().zip(expected.iter()) {
+            assert_eq!(image.get_pixel(x, y), RgbaColor::new_from_u8(c[2], c[1], c[0], 255),
+                       "descriptor {:#x} at {}, {}", desc, x, y);
+        }
+    }
+}
+
+// Color mapped header with a palette of entries of the given size
+fn palette_header(data_type: u8, width: u16, height: u16, entries: u16, entry_bits: u8) -> Vec<u8> {
+    let mut data = header(data_type, width, height, 8);
+    data[1] = 1;
+    data[5] = entries as u8;
+    data[6] = (entries >> 8) as u8;
+    data[7] = entry_bits;
+    data
+}
+
+#[test]
+fn palettized_images_decode() {
+    let palette = [[0u8, 0, 0], [0, 0, 255], [0, 255, 0], [255, 0, 0]];
+    let indices = [3u8, 2, 1, 0, 1, 1];
+    let expected = |image: &TgaImage| {
+        for (i, &index) in indices.iter().enumerate() {
+            let c = palette[index as usize];
+            let (x, y) = (i as i32 % 3, i as i32 / 3);
+            assert_eq!(image.get_pixel(x, y), RgbaColor::new_from_u8(c[2], c[1], c[0], 255), "at {}, {}", x, y);
+        }
+    };
+
+    let mut raw = palette_header(1, 3, 2, 4, 24);
+    for c in palette.iter() { raw.extend(c); }
+    let mut rle = raw.clone();
+    rle[2] = 9;
+
+    raw.extend(&indices);
+    expected(&TgaImage::decode(Cursor::new(raw)).unwrap());
+
+    rle.extend(&[0x03, 3, 2, 1, 0, 0x81, 1]);
+    expected(&TgaImage::decode(Cursor::new(rle)).unwrap());
+
+    let mut out_of_range = palette_header(1, 1, 1, 4, 24);
+    out_of_range.extend(&[0u8; 12]);
+    out_of_range.push(4);
+    match TgaImage::decode(Cursor::new(out_of_range)) {
+        Err(TgaError::InvalidColorIndex(4)) => {},
+        other => panic!("decoded as {:?}", other.map(|i| (i.width, i.height)))
+    }
+
+    let mut unsupported = palette_header(1, 1, 1, 4, 8);
+    unsupported.extend(&[0u8; 5]);
+    match TgaImage::decode(Cursor::new(unsupported)) {
+        Err(e @ TgaError::UnsupportedFormat { data_type: 1, bpp: 8 }) => assert!(!e.to_string().is_empty()),
+        other => panic!("decoded as {:?}", other.map(|i| (i.width, i.height)))
+    }
+}
+
+#[test]
+fn grayscale_images_replicate_luminance() {
+    let gray = |v: u8| RgbaColor::new_from_u8(v, v, v, 255);
+    let mut image = TgaImage::new(4, 2);
+    for i in 0..8 {
+        image.set_pixel(i % 4, i / 4, &gray(i as u8 * 30));
+    }
+
+    let mut data = Vec::new();
+    image.encode_grayscale(&mut data).unwrap();
+    assert_eq!((data[2], data[16], data.len()), (3, 8, 18 + 8));
+    let decoded = TgaImage::decode(Cursor::new(data)).unwrap();
+    assert_same(&decoded, &image);
+    assert_eq!(decoded.get_pixel(3, 1), gray(210));
+
+    let mut rle = header(11, 4, 2, 8);
+    rle.extend(&[0x83, 0, 0x02, 30, 60, 90, 0x80, 120]);
+    let decoded = TgaImage::decode(Cursor::new(rle)).unwrap();
+    let expected = [0u8, 0, 0, 0, 30, 60, 90, 120];
+    for (i, &v) in expected.iter().enumerate() {
+        assert_eq!(decoded.get_pixel(i as i32 % 4, i as i32 / 4), gray(v));
+    }
+
+    // 16 bit grayscale carries alpha in the second byte
+    let mut with_alpha = header(3, 1, 1, 16);
+    with_alpha.extend(&[200, 100]);
+    let decoded = TgaImage::decode(Cursor::new(with_alpha)).unwrap();
+    assert_eq!(decoded.get_pixel(0, 0), RgbaColor::new_from_u8(200, 200, 200, 100));
+}
+
+#[test]
+fn truncated_data_is_an_unexpected_eof() {
+    let mut data = Vec::new();
+    pattern(4, 4).encode(Cursor::new(&mut data)).unwrap();
+
+    // Empty, header cut short, and pixel data cut short
+    for &len in [0, 10, 17, 18, data.len() - 1].iter() {
+        match TgaImage::decode(Cursor::new(&data[..len])) {
+            Err(TgaError::UnexpectedEof) => {},
+            other => panic!("{} bytes decoded as {:?}", len, other.map(|i| (i.width, i.height)))
+        }
+    }
+
+    let mut rle = Vec::new();
+    pattern(4, 4).encode_with_options(&mut rle, TgaWriteOptions { rle: true, alpha: true }).unwrap();
+    match TgaImage::decode(&rle[..rle.len() - 2]) {
+        Err(TgaError::UnexpectedEof) => {},
+        other => panic!("decoded as {:?}", other.map(|i| (i.width, i.height)))
+    }
+}
+
+// Compared against tests/golden/triangle.ppm, UPDATE_GOLDEN=1 rewrites it
+#[test]
+fn ppm_matches_the_stored_triangle() {
+    let mut renderer = Renderer::new(8, 6);
+    renderer.triangle(vertex(0.0, 0.0), vertex(8.0, 0.0), vertex(8.0, 6.0));
+    let image = renderer.to_image();
+
+    let mut data = Vec::new();
+    image.write_ppm(&mut data).unwrap();
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join("triangle.ppm");
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        File::create(&path).unwrap().write_all(&data).unwrap();
+    }
+    let mut fixture = Vec::new();
+    File::open(&path).unwrap().read_to_end(&mut fixture).unwrap();
+    assert!(data == fixture, "PPM differs from {}", path.display());
+
+    // Top row first, so the filled lower right corner is at the end
+    let header = b"P6\n8 6\n255\n".len();
+    assert_eq!(&data[..header], b"P6\n8 6\n255\n");
+    assert_eq!(&data[data.len() - 3..], &[255, 255, 255]);
+    assert_eq!(&data[header..header + 3], &[0, 0, 0]);
+    // Alpha is dropped, so compare what reads back by writing it again
+    let mut again = Vec::new();
+    TgaImage::read_ppm(Cursor::new(&data)).unwrap().write_ppm(&mut again).unwrap();
+    assert!(again == data);
+
+    // PAM keeps alpha
+    let mut translucent = pattern(3, 2);
+    translucent.set_pixel(1, 1, &RgbaColor::new_from_u8(1, 2, 3, 4));
+    let mut pam = Vec::new();
+    translucent.write_pam(&mut pam).unwrap();
+    assert_same(&TgaImage::read_ppm(Cursor::new(pam)).unwrap(), &translucent);
+}
+
+#[test]
+fn flips_move_every_pixel() {
+    // Odd and even sizes, the middle row and column stay put
+    for &(width, height) in [(3, 3), (4, 2), (1, 5)].iter() {
+        let image = pattern(width, height);
+        let mut vertical = pattern(width, height);
+        vertical.flip_vertical();
+        let mut horizontal = pattern(width, height);
+        horizontal.flip_horizontal();
+        for y in 0..height {
+            for x in 0..width {
+                assert_eq!(vertical.get_pixel(x, height - 1 - y), image.get_pixel(x, y));
+                assert_eq!(horizontal.get_pixel(width - 1 - x, y), image.get_pixel(x, y));
+            }
+        }
+        vertical.flip_vertical();
+        assert_same(&vertical, &image);
+    }
+}
+
+#[test]
+fn crop_checks_the_rectangle() {
+    let image = pattern(5, 4);
+    let region = image.crop(1, 2, 3, 2).unwrap();
+    assert_eq!((region.width, region.height), (3, 2));
+    for y in 0..2 {
+        for x in 0..3 {
+            assert_eq!(region.get_pixel(x, y), image.get_pixel(x + 1, y + 2));
+        }
+    }
+    assert_same(&image.crop(0, 0, 5, 4).unwrap(), &image);
+
+    for &(x, y, w, h) in [(-1, 0, 2, 2), (0, -1, 2, 2), (4, 0, 2, 1), (0, 3, 1, 2), (0, 0, 0, 1), (0, 0, 1, -1)].iter() {
+        match image.crop(x, y, w, h) {
+            Err(TgaError::OutOfBounds { .. }) => {},
+            other => panic!("{:?} cropped to {:?}", (x, y, w, h), other.map(|i| (i.width, i.height)))
+        }
+    }
+}
+
+#[test]
+fn blit_clips_at_every_edge() {
+    let src = pattern(3, 3);
+    let background = RgbaColor::new_from_u8(1, 1, 1, 255);
+    for &(dst_x, dst_y) in [(-2, -2), (3, 3), (-1, 3), (3, -1), (1, 1), (-3, 0), (5, 5)].iter() {
+        let mut dst = TgaImage::new(5, 5);
+        for y in 0..5 {
+            for x in 0..5 {
+                dst.set_pixel(x, y, &background);
+            }
+        }
+        dst.blit(&src, dst_x, dst_y);
+        for y in 0..5 {
+            for x in 0..5 {
+                let (sx, sy) = (x - dst_x, y - dst_y);
+                let expected = if (0..3).contains(&sx) && (0..3).contains(&sy) { src.get_pixel(sx, sy) } else { background };
+                assert_eq!(dst.get_pixel(x, y), expected, "blit at {}, {}: {}, {}", dst_x, dst_y, x, y);
+            }
+        }
+    }
+}
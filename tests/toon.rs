@@ -0,0 +1,92 @@
+extern crate renderer;
+
+use renderer::{Renderer,Shading,Model,RgbaColor,Vec3f,Mat4};
+use renderer::shader::{toon_band,toon_band_at};
+
+#[test]
+fn intensities_snap_to_bands() {
+    // Boundaries at 1/3 and 2/3
+    assert_eq!(toon_band(0.0, 3), 0.0);
+    assert_eq!(toon_band(0.33, 3), 0.0);
+    assert_eq!(toon_band(0.34, 3), 0.5);
+    assert_eq!(toon_band(0.66, 3), 0.5);
+    assert_eq!(toon_band(0.67, 3), 1.0);
+    assert_eq!(toon_band(1.0, 3), 1.0);
+    assert_eq!(toon_band(-0.5, 3), 0.0);
+    assert_eq!(toon_band(2.0, 3), 1.0);
+
+    assert_eq!(toon_band(0.2, 1), 1.0);
+    assert_eq!(toon_band(0.49, 2), 0.0);
+    assert_eq!(toon_band(0.5, 2), 1.0);
+
+    // Many bands follow the intensity closely
+    for i in 0..101 {
+        let intensity = i as f32 / 100.0;
+        assert!((toon_band(intensity, 256) - intensity).abs() <= 1.0 / 255.0, "{}", intensity);
+    }
+}
+
+#[test]
+fn custom_thresholds() {
+    let thresholds = [0.25, 0.75];
+    assert_eq!(toon_band_at(0.0, &thresholds), 0.0);
+    assert_eq!(toon_band_at(0.2499, &thresholds), 0.0);
+    assert_eq!(toon_band_at(0.25, &thresholds), 0.5);
+    assert_eq!(toon_band_at(0.7499, &thresholds), 0.5);
+    assert_eq!(toon_band_at(0.75, &thresholds), 1.0);
+    assert_eq!(toon_band_at(1.0, &thresholds), 1.0);
+}
+
+// Square facing the camera at a slant to the light, normals spread so
+// intensity changes across it
+fn square() -> Model {
+    let vertices = vec![Vec3f::new(-0.5, -0.5, 0.0), Vec3f::new(0.5, -0.5, 0.0), Vec3f::new(0.5, 0.5, 0.0), Vec3f::new(-0.5, 0.5, 0.0)];
+    let normals = vec![Vec3f::new(-0.8, -0.6, 0.2), Vec3f::new(0.8, -0.6, 0.2), Vec3f::new(0.8, 0.6, 0.2), Vec3f::new(-0.8, 0.6, 0.2)];
+    let normals = normals.into_iter().map(|n| n.normalized()).collect();
+    let faces = vec![[0, -1, 0, 1, -1, 1, 2, -1, 2], [0, -1, 0, 2, -1, 2, 3, -1, 3]];
+    Model::new(vertices, Vec::new(), normals, faces).unwrap()
+}
+
+fn render(shading: Shading, outline: Option<f32>) -> renderer::TgaImage {
+    let mut renderer = Renderer::new(32, 32);
+    renderer.set_light_dir(Vec3f::new(-1.0, -1.0, -1.0));
+    renderer.set_shading(shading);
+    renderer.set_outline(outline);
+    renderer.draw_model(&square(), &Mat4::identity());
+    renderer.to_image()
+}
+
+#[test]
+fn few_bands_give_few_levels_and_many_look_like_phong() {
+    let image = render(Shading::Toon { bands: 3 }, None);
+    let mut levels: Vec<u8> = Vec::new();
+    for y in 8..24 {
+        for x in 8..24 {
+            let v = (image.get_pixel(x, y).r * 255.0).round() as u8;
+            if !levels.contains(&v) {
+                levels.push(v);
+            }
+        }
+    }
+    assert!(levels.len() >= 2 && levels.len() <= 3, "{:?}", levels);
+
+    let (toon, phong) = (render(Shading::Toon { bands: 256 }, None), render(Shading::Phong, None));
+    let diff = toon.diff_with(&phong, 2, false);
+    assert!(diff.is_match(), "{}", diff);
+}
+
+#[test]
+fn outlines_follow_the_silhouette() {
+    let plain = render(Shading::Phong, None);
+    let outlined = render(Shading::Phong, Some(0.05));
+    let black = RgbaColor::new(0.0, 0.0, 0.0, 1.0);
+
+    // The square covers pixels 8 to 23
+    for &(x, y) in [(8, 8), (8, 15), (23, 23), (15, 23)].iter() {
+        assert_eq!(outlined.get_pixel(x, y), black, "at {}, {}", x, y);
+    }
+    for &(x, y) in [(9, 9), (15, 15), (22, 22)].iter() {
+        assert_eq!(outlined.get_pixel(x, y), plain.get_pixel(x, y), "at {}, {}", x, y);
+    }
+    assert_eq!(outlined.get_pixel(4, 4), plain.get_pixel(4, 4));
+}
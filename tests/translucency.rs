@@ -0,0 +1,93 @@
+extern crate renderer;
+
+mod common;
+
+use renderer::{Renderer,Model,Texture,TgaImage,RgbaColor,Vec2f,Vec3f,Mat4};
+use common::unlit;
+
+// A 4x4 checkerboard quad at z = 0.5 in front of a red triangle at z = -0.5
+// covering the whole image. The quad comes first so drawing in submission
+// order would put the triangle over it
+fn scene() -> Model {
+    let vertices = vec![
+        Vec3f::new(-1.0, -1.0, 0.5), Vec3f::new(1.0, -1.0, 0.5), Vec3f::new(1.0, 1.0, 0.5), Vec3f::new(-1.0, 1.0, 0.5),
+        Vec3f::new(-1.0, -1.0, -0.5), Vec3f::new(3.0, -1.0, -0.5), Vec3f::new(-1.0, 3.0, -0.5)
+    ];
+    let uvs = vec![Vec2f::new(0.0, 0.0), Vec2f::new(1.0, 0.0), Vec2f::new(1.0, 1.0), Vec2f::new(0.0, 1.0)];
+    let faces = vec![
+        [0, 0, -1, 1, 1, -1, 2, 2, -1],
+        [0, 0, -1, 2, 2, -1, 3, 3, -1],
+        [4, -1, -1, 5, -1, -1, 6, -1, -1]
+    ];
+    let mut model = Model::new(vertices, uvs, Vec::new(), faces).unwrap();
+    let (white, red) = (RgbaColor::new(1.0, 1.0, 1.0, 1.0), RgbaColor::new(1.0, 0.0, 0.0, 1.0));
+    model.vertex_colors = vec![white, white, white, white, red, red, red];
+    model
+}
+
+// Blue squares with the given alpha, holes in between
+fn checker(alpha: u8) -> TgaImage {
+    let mut image = TgaImage::new(4, 4);
+    for y in 0..4 {
+        for x in 0..4 {
+            let a = if (x + y) % 2 == 0 { 0 } else { alpha };
+            image.set_pixel(x, y, &RgbaColor::new_from_u8(0, 0, 255, a));
+        }
+    }
+    image
+}
+
+fn renderer(texture: TgaImage) -> Renderer {
+    let mut renderer = unlit(32, 32);
+    renderer.set_diffuse(Texture::new(texture));
+    renderer
+}
+
+// Hole at texel (0, 0), square at texel (1, 0), 8 pixels each
+const HOLE: (i32, i32) = (4, 4);
+const SQUARE: (i32, i32) = (12, 4);
+
+#[test]
+fn translucent_faces_blend_over_the_opaque_ones() {
+    let mut renderer = renderer(checker(128));
+    renderer.draw_model(&scene(), &Mat4::identity());
+    let image = renderer.to_image();
+
+    let hole = image.get_pixel(HOLE.0, HOLE.1);
+    assert!(hole.r > 0.99 && hole.b == 0.0, "{:?}", hole);
+
+    // Half and half
+    let square = image.get_pixel(SQUARE.0, SQUARE.1);
+    assert!((square.r - 0.5).abs() < 0.02 && (square.b - 0.5).abs() < 0.02 && square.g == 0.0, "{:?}", square);
+
+    // Blended fragments leave the triangle's depth
+    assert_eq!(renderer.depth_at(SQUARE.0, SQUARE.1), renderer.depth_at(HOLE.0, HOLE.1));
+}
+
+#[test]
+fn alpha_test_cuts_holes() {
+    // With alpha testing the quad counts as opaque and is drawn first
+    let mut renderer = renderer(checker(255));
+    renderer.set_alpha_test(0.5);
+    renderer.draw_model(&scene(), &Mat4::identity());
+    let image = renderer.to_image();
+
+    let hole = image.get_pixel(HOLE.0, HOLE.1);
+    assert!(hole.r > 0.99 && hole.b == 0.0, "{:?}", hole);
+    let square = image.get_pixel(SQUARE.0, SQUARE.1);
+    assert!(square.b > 0.99 && square.r == 0.0, "{:?}", square);
+    assert!(renderer.depth_at(SQUARE.0, SQUARE.1) > renderer.depth_at(HOLE.0, HOLE.1));
+    assert!(renderer.stats().fragments.discarded > 0);
+}
+
+#[test]
+fn alpha_test_discards_translucent_texels_too() {
+    let mut renderer = renderer(checker(128));
+    renderer.set_alpha_test(0.75);
+    renderer.draw_model(&scene(), &Mat4::identity());
+    let image = renderer.to_image();
+    for &(x, y) in [HOLE, SQUARE].iter() {
+        let c = image.get_pixel(x, y);
+        assert!(c.r > 0.99 && c.b == 0.0, "{:?}", c);
+    }
+}
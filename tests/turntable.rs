@@ -0,0 +1,88 @@
+extern crate renderer;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use renderer::{Renderer,Shading,Model,TgaImage,RgbaColor,Vec3f};
+use renderer::text;
+
+// Mean position of the pixels that aren't black
+fn centroid(image: &TgaImage) -> (f32, f32) {
+    let (mut x_sum, mut y_sum, mut count) = (0.0, 0.0, 0.0);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let c = image.get_pixel(x, y);
+            if c.r + c.g + c.b > 0.0 {
+                x_sum += x as f32;
+                y_sum += y as f32;
+                count += 1.0;
+            }
+        }
+    }
+    assert!(count > 0.0, "frame is empty");
+    (x_sum / count, y_sum / count)
+}
+
+#[test]
+fn four_frames_of_a_triangle() {
+    let dir = env::temp_dir().join("renderer_turntable_test");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    // Off the y axis and not parallel to it, so it moves and never turns edge-on
+    let vertices = vec![Vec3f::new(0.2, -0.5, 0.6), Vec3f::new(0.8, -0.5, 0.0), Vec3f::new(0.5, 0.5, 0.3)];
+    let model = Model::new(vertices, Vec::new(), Vec::new(), vec![[0, -1, -1, 1, -1, -1, 2, -1, -1]]).unwrap();
+
+    let mut renderer = Renderer::new(64, 64);
+    renderer.set_shading(Shading::Flat);
+    renderer.set_backface_culling(false);
+    renderer.render_turntable(&model, 4, &dir).unwrap();
+
+    let mut names: Vec<String> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name().into_string().unwrap()).collect();
+    names.sort();
+    assert_eq!(names, vec!["output_0001.tga", "output_0002.tga", "output_0003.tga", "output_0004.tga"]);
+
+    let centroids: Vec<(f32, f32)> = names.iter().map(|name| centroid(&TgaImage::new_from_file(&dir.join(name)).unwrap())).collect();
+    for i in 1..centroids.len() {
+        let (a, b) = (centroids[i - 1], centroids[i]);
+        assert!((a.0 - b.0).abs() > 2.0, "frames {} and {} look alike: {:?}", i, i + 1, centroids);
+    }
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn failed_frame_is_reported() {
+    let model = Model::new(vec![Vec3f::new(0.0, 0.0, 0.0); 3], Vec::new(), Vec::new(), vec![[0, -1, -1, 1, -1, -1, 2, -1, -1]]).unwrap();
+    let mut renderer = Renderer::new(8, 8);
+    let error = renderer.render_turntable(&model, 2, Path::new("/nonexistent/renderer")).unwrap_err();
+    assert_eq!(error.frame, 1);
+    assert!(error.to_string().contains("output_0001.tga"));
+}
+
+#[test]
+fn frame_numbers() {
+    let dir = env::temp_dir().join("renderer_turntable_labels");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let model = Model::new(vec![Vec3f::new(-0.1, -0.1, 0.0), Vec3f::new(0.1, -0.1, 0.0), Vec3f::new(0.0, 0.1, 0.0)],
+                           Vec::new(), Vec::new(), vec![[0, -1, -1, 1, -1, -1, 2, -1, -1]]).unwrap();
+    let mut renderer = Renderer::new(64, 64);
+    renderer.set_frame_label(Some(RgbaColor::new(1.0, 1.0, 1.0, 1.0)));
+    renderer.render_turntable(&model, 12, &dir).unwrap();
+
+    // Glyphs start 2 pixels in from the top left corner
+    for &(frame, label) in [(1, "1"), (12, "12")].iter() {
+        let image = TgaImage::new_from_file(&dir.join(format!("output_{:04}.tga", frame))).unwrap();
+        for (i, c) in label.chars().enumerate() {
+            let glyph = text::glyph(c);
+            for row in 0..8 {
+                for bit in 0..8 {
+                    let lit = image.get_pixel(2 + 8 * i as i32 + bit, 61 - row).r > 0.5;
+                    assert_eq!(lit, glyph[row as usize] & (1 << bit) != 0, "frame {} at {}, {}", frame, bit, row);
+                }
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,90 @@
+extern crate renderer;
+
+use renderer::{Renderer,Model,RgbaColor,Vec3f,Mat4};
+
+// Right triangle with its corners at pixels (8, 8), (56, 8) and (8, 56) of a 64x64 image
+fn triangle() -> Model {
+    let vertices = vec![Vec3f::new(-0.75, -0.75, 0.0), Vec3f::new(0.75, -0.75, 0.0), Vec3f::new(-0.75, 0.75, 0.0)];
+    Model::new(vertices, Vec::new(), Vec::new(), vec![[0, -1, -1, 1, -1, -1, 2, -1, -1]]).unwrap()
+}
+
+// Distance of pixel center (x, y) from the nearest edge of triangle()
+fn edge_distance(x: i32, y: i32) -> f32 {
+    let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+    let hypotenuse = (px + py - 64.0).abs() / 2f32.sqrt();
+    (py - 8.0).abs().min((px - 8.0).abs()).min(hypotenuse)
+}
+
+fn is_green(c: RgbaColor) -> bool {
+    c.g > 0.5 && c.r < 0.5
+}
+
+#[test]
+fn edges_are_drawn_and_the_inside_is_not() {
+    let mut renderer = Renderer::new(64, 64);
+    renderer.set_color(RgbaColor::new(0.0, 1.0, 0.0, 1.0));
+    renderer.draw_model_wireframe(&triangle());
+    let image = renderer.to_image();
+
+    let mut lit = 0;
+    for y in 0..64 {
+        for x in 0..64 {
+            if is_green(image.get_pixel(x, y)) {
+                lit += 1;
+                assert!(edge_distance(x, y) < 1.5, "off the edges at {}, {}", x, y);
+            }
+        }
+    }
+    assert!(lit >= 48 * 2 + 48, "{} edge pixels", lit);
+
+    for &(x, y) in [(32, 8), (8, 32), (32, 31), (8, 8), (55, 8), (8, 55)].iter() {
+        let near = (-1..2).any(|dy| (-1..2).any(|dx| is_green(image.get_pixel(x + dx, y + dy))));
+        assert!(near, "nothing drawn around {}, {}", x, y);
+    }
+    for &(x, y) in [(16, 16), (24, 20), (12, 40), (40, 12)].iter() {
+        assert_eq!(image.get_pixel(x, y).g, 0.0, "inside at {}, {}", x, y);
+    }
+}
+
+#[test]
+fn overlay_on_a_filled_render() {
+    let mut renderer = Renderer::new(64, 64);
+    renderer.clear_lights();
+    renderer.set_ambient(1.0);
+    renderer.set_color(RgbaColor::new(1.0, 0.0, 0.0, 1.0));
+    renderer.draw_model(&triangle(), &Mat4::identity());
+    renderer.set_color(RgbaColor::new(0.0, 1.0, 0.0, 1.0));
+    renderer.set_wireframe_depth_test(Some(0.01));
+    renderer.draw_model_wireframe(&triangle());
+
+    let image = renderer.to_image();
+    assert!(is_green(image.get_pixel(32, 8)) || is_green(image.get_pixel(32, 9)));
+    assert_eq!(image.get_pixel(20, 20), RgbaColor::new(1.0, 0.0, 0.0, 1.0));
+
+    // A smaller triangle behind the filled one is hidden
+    let vertices = vec![Vec3f::new(-0.5, -0.5, -0.5), Vec3f::new(0.25, -0.5, -0.5), Vec3f::new(-0.5, 0.25, -0.5)];
+    let behind = Model::new(vertices, Vec::new(), Vec::new(), vec![[0, -1, -1, 1, -1, -1, 2, -1, -1]]).unwrap();
+    let mut renderer = Renderer::new(64, 64);
+    renderer.clear_lights();
+    renderer.set_ambient(1.0);
+    renderer.draw_model(&triangle(), &Mat4::identity());
+    renderer.set_color(RgbaColor::new(0.0, 1.0, 0.0, 1.0));
+    renderer.set_wireframe_depth_test(Some(0.01));
+    renderer.draw_model_wireframe(&behind);
+    let image = renderer.to_image();
+    assert!((0..64).all(|y| (0..64).all(|x| !is_green(image.get_pixel(x, y)))));
+
+    renderer.set_wireframe_depth_test(None);
+    renderer.draw_model_wireframe(&behind);
+    assert!(is_green(renderer.to_image().get_pixel(24, 16)));
+}
+
+#[test]
+fn edges_leaving_the_screen() {
+    let vertices = vec![Vec3f::new(-0.5, -0.5, 0.0), Vec3f::new(50.0, -0.5, 0.0), Vec3f::new(-0.5, -40.0, 0.5)];
+    let model = Model::new(vertices, Vec::new(), Vec::new(), vec![[0, -1, -1, 1, -1, -1, 2, -1, -1]]).unwrap();
+    let mut renderer = Renderer::new(64, 64);
+    renderer.draw_model_wireframe(&model);
+    let image = renderer.to_image();
+    assert!(image.get_pixel(40, 16).r > 0.5 && image.get_pixel(16, 4).r > 0.5);
+}